@@ -2,16 +2,44 @@
 main_factions = Factions
 main_persons = People
 main_districts = Districts
+main_clocks = Clocks
 main_item_faction = Faction
 main_item_person = Person
 main_item_district = District
+main_item_clock = Clock
 menu = Menu
 menu_restart = Restart
+menu_new_doskvol = New Campaign from Doskvol
+menu_new_pack = New Campaign from Pack...
 menu_load = Load...
+menu_recent = Recent
+recent_none = No recent campaigns.
+recent_reopen_last = Reopen Last Campaign on Start
+recent_clear = Clear Recent
+menu_restore_backup = Restore Backup...
+menu_update_vault = Update From Obsidian Vault...
+menu_compare = Compare With File...
+menu_merge_campaigns = Merge With Campaign...
 menu_save = Save
 menu_save_as = Save As...
+menu_undo = Undo
+menu_redo = Redo
 menu_import = Import...
 menu_export = Export...
+menu_export_markdown_pages = Export Markdown Pages...
+menu_export_markdown_document = Export Markdown Document...
+menu_export_vault = Export Obsidian Vault...
+menu_export_html = Export HTML Site...
+menu_csv = CSV
+menu_csv_export = Export { $kind }...
+menu_csv_import = Import { $kind }...
+menu_web = Faction Web
+menu_web_dot = Export Graphviz DOT...
+menu_web_svg = Export SVG Picture...
+web_min_tier = Lowest tier
+web_turf_in = Turf in
+web_any_district = any district
+menu_faction_turn = Advance Factions...
 menu_settings = Settings...
 menu_exit = Exit
 about = About
@@ -22,11 +50,23 @@ app_starting = Starting
 app_ready = Ready
 app_edit_itm = Editing {$itm}
 app_add_itm = Add New {$itm}
+app_show_graph = Show Graph
+app_show_table = Show Table
+graph_circle = Circle
+graph_spread = Spread Out
+graph_reset_view = Reset View
 app_create_itm = Creating New {$itm}
 app_loading = Loading
+app_restoring = Restoring Backup
 app_saving = Saving
 app_importing = Importing
 app_exporting = Exporting
+app_faction_turn = Advancing Factions
+app_recovering = Recovering
+app_choosing_pack = Choosing a Setting Pack
+app_comparing = Comparing
+app_merging = Merging Campaigns
+app_unsaved = Unsaved Changes
 name_heading = Name
 tier_heading = Tier
 hq_heading = HQ
@@ -44,7 +84,80 @@ enemies_heading = Enemies
 personality_heading = Personality
 summary_heading = Summary
 found_in_heading = Found In
+relationships_heading = Relationships
+reason_heading = Reason
+clocks_heading = Clocks
+assets_heading = Assets
+general_heading = General
+clock_new = New Clock
+clock_kind_heading = Kind
+clock_progress_heading = Progress
+clock_state_heading = State
+clock_next_heading = Starts Next
+clock_rival_heading = Racing
+clock_links_heading = Linked To
+clock_finished = Finished
+clock_kind_project = Project
+clock_kind_countdown = Countdown
+clock_kind_danger = Danger
+clock_kind_other = Other
+clock_state_running = Running
+clock_state_waiting = Waiting
+clock_state_stopped = Stopped
+turn_heading = Faction Turn {$turn}
+turn_no_clocks = No faction has a clock to work on.
+turn_dice_heading = Dice
+turn_clock_heading = Clock
+turn_ticks_heading = Ticks
+turn_ticks_finished = {$ticks} (finishes)
+turn_apply = Apply
+turn_reroll = Reroll
+turn_cancel = Cancel
+turn_log_heading = Previous Turns
+pack_heading = New Campaign from a Setting Pack
+pack_folder = Packs are folders or zips holding a manifest.json and an export.json, installed in {$folder}
+pack_none = No packs are installed.
+pack_counts = {$factions} factions, {$persons} people, {$districts} districts
+pack_start = Start Campaign
+pack_refresh = Refresh
+pack_cancel = Cancel
+merge_heading = Import
+merge_counts = {$new} new, {$identical} identical, {$conflicts} in conflict
+merge_conflicts = In Conflict
+merge_new = New
+merge_identical = Identical
+merge_keep = Keep existing
+merge_take = Take incoming
+merge_fields = Merge fields
+merge_rename = Import as
+merge_apply = Import
+merge_cancel = Cancel
+diff_heading = Changes
+diff_between = From {$before} to {$after}
+diff_counts = {$added} added, {$removed} removed, {$modified} changed
+diff_none = No factions, people or districts have changed.
+diff_added = {$name} was added
+diff_removed = {$name} was removed
+diff_modified = {$name} was changed
+diff_export = Export Markdown...
+diff_close = Close
+merge_campaigns_heading = Merge Campaigns
+merge_campaigns_between = Adding {$second} to {$first}
+merge_campaigns_exact = {$count} items have the same name in both, and will be merged.
+merge_campaigns_similar = These items have similar names; tick those which are the same:
+merge_campaigns_no_similar = No other items have similar names.
+merge_campaigns_save = Save Merged Campaign As...
+merge_campaigns_cancel = Cancel
+turn_log_ticked = {$faction} rolled {$dice}: {$ticks} on {$clock}
+turn_log_finished = {$faction} rolled {$dice}: {$ticks} on {$clock}, which is finished
 edit_save = Save
+edit_delete = Delete
+delete_itm_name = Delete {$itm} "{$name}"?
+delete_no_references = Nothing else refers to this.
+delete_references = These references will also be cleared:
+delete_confirm = Delete
+delete_cancel = Cancel
+app_delete_itm = Deleting {$itm}
 acknowledge_error = Dismiss
 default_export_file = export
 default_internal_file = savefile
@@ -52,3 +165,53 @@ file_dialog_save_files = Save Files
 file_dialog_export_files = Export Files
 file_dialog_save_file = Save File
 file_dialog_export_file = Export File
+menu_dice = Dice
+dice_heading = Dice
+dice_kind = Roll
+dice_pool = Dice
+dice_roll = Roll
+dice_clear = Clear
+dice_no_rolls = No rolls yet.
+dice_kind_pool = {$kind} ({$pool}d)
+roll_kind_action = Action
+roll_kind_fortune = Fortune
+roll_kind_resistance = Resistance
+roll_kind_engagement = Engagement
+roll_stress = Stress {$stress}
+roll_action_failure = Bad outcome
+roll_action_partial = Success, with a consequence
+roll_action_success = Full success
+roll_action_critical = Critical success
+roll_fortune_failure = Poor result
+roll_fortune_partial = Standard result
+roll_fortune_success = Good result
+roll_fortune_critical = Exceptional result
+roll_engagement_failure = Desperate position
+roll_engagement_partial = Risky position
+roll_engagement_success = Controlled position
+roll_engagement_critical = Controlled, and already past the first obstacle
+recovery_heading = Recover unsaved changes?
+recovery_campaign_name = The last session ended before "{$name}" was saved.
+recovery_campaign_unsaved = The last session ended before its campaign was saved.
+recovery_restore = Recover
+recovery_discard = Discard
+unsaved_changes = (unsaved changes)
+unsaved_before_exit = Save changes before exiting?
+unsaved_before_restart = Save changes before restarting?
+unsaved_before_load = Save changes before loading?
+unsaved_lost = Any changes which are not saved will be lost.
+unsaved_save = Save
+unsaved_discard = Discard
+unsaved_cancel = Cancel
+markdown_campaign = Campaign
+file_dialog_markdown_files = Markdown Files
+file_dialog_markdown_file = Markdown File
+default_markdown_file = campaign
+file_dialog_csv_files = CSV Files
+file_dialog_csv_file = CSV File
+default_csv_file = items
+file_dialog_dot_files = Graphviz Files
+file_dialog_dot_file = Graphviz File
+file_dialog_svg_files = SVG Files
+file_dialog_svg_file = SVG File
+default_web_file = faction web
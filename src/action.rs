@@ -1,10 +1,13 @@
 use std::collections::VecDeque;
 
 use crate::{
+    campaign_clock::CampaignClock,
     district::District,
     faction::Faction,
-    managed_list::{DistrictRef, FactionRef, PersonRef},
+    faction_turn::TurnLogEntry,
+    managed_list::{CampaignClockRef, DistrictRef, FactionRef, PersonRef},
     person::Person,
+    relationship::{ActorPair, Relationship},
 };
 
 pub type ActionNode = VecDeque<Action>;
@@ -24,6 +27,8 @@ pub enum Action {
     DistrictAdd(District),
     // Remove district
     DistrictRemove(DistrictRef),
+    // Restore removed district (reviving its old references)
+    DistrictRestore(DistrictRef, District),
     // Replace district
     DistrictReplace(DistrictRef, District),
 
@@ -31,6 +36,8 @@ pub enum Action {
     PersonAdd(Person),
     // Remove person
     PersonRemove(PersonRef),
+    // Restore removed person (reviving its old references)
+    PersonRestore(PersonRef, Person),
     // Replace person
     PersonReplace(PersonRef, Person),
 
@@ -38,8 +45,27 @@ pub enum Action {
     FactionAdd(Faction),
     // Remove faction
     FactionRemove(FactionRef),
+    // Restore removed faction (reviving its old references)
+    FactionRestore(FactionRef, Faction),
     // Replace faction
     FactionReplace(FactionRef, Faction),
+
+    // Add campaign clock
+    ClockAdd(CampaignClock),
+    // Remove campaign clock
+    ClockRemove(CampaignClockRef),
+    // Restore removed campaign clock (reviving its old references)
+    ClockRestore(CampaignClockRef, CampaignClock),
+    // Replace campaign clock
+    ClockReplace(CampaignClockRef, CampaignClock),
+
+    // Add an entry to the end of the faction turn log
+    TurnLogPush(TurnLogEntry),
+    // Remove the last entry of the faction turn log
+    TurnLogPop,
+
+    // Set (or clear, with None) the relationship between a pair of items
+    RelationshipSet(ActorPair, Option<Relationship>),
     // Clear all
     // ClearAll,
 }
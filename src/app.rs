@@ -3,14 +3,10 @@ use std::{
     collections::BTreeMap,
     ffi::OsStr,
     fmt::Display,
-    fs::{self, File, OpenOptions, create_dir_all},
-    io::{BufReader, BufWriter, Read, Write},
-    path::Path,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
-use anyhow::anyhow;
-use bytes_cast::{BytesCast, unaligned};
 use directories_next::ProjectDirs;
 use eframe::egui::FontFamily::Proportional;
 use eframe::egui::FontId;
@@ -18,33 +14,58 @@ use eframe::egui::TextStyle::*;
 use eframe::{
     CreationContext, Frame,
     egui::{
-        Align, Button, CentralPanel, Color32, Context, FontData, FontDefinitions, FontFamily,
-        Label, Layout, Margin, MenuBar, RichText, Sense, Separator, Stroke, Theme, TopBottomPanel,
-        Ui, ViewportCommand,
+        Align, Button, CentralPanel, CollapsingHeader, Color32, ComboBox, Context, FontData,
+        FontDefinitions, FontFamily, Key, KeyboardShortcut, Label, Layout, Margin, MenuBar,
+        Modifiers, RichText, Sense, Separator, Stroke, Theme, TopBottomPanel, Ui, ViewportCommand,
     },
 };
 use egui_extras::TableBuilder;
-use enum_iterator::{Sequence, all, cardinality};
-use log::{debug, error, info};
-use serde::{Serialize, de::DeserializeOwned};
+use enum_iterator::{all, cardinality};
+use log::{debug, error, info, warn};
 
 use crate::{
+    APP_NAME,
     action::{Action, ActionNode},
-    app_data::AppData,
-    app_display::{ShowEdit, ShowEditInfo},
+    app_data::{AppData, DataIndex},
+    app_display::{ShowEdit, ShowEditInfo, column_definitions_iter, headings_iter},
     app_settings::AppSettings,
+    backup::{BACKUP_DIR, backup_dir, original_of},
+    campaign_clock::CampaignClock,
+    campaign_diff::CampaignDiff,
+    campaign_merge::CampaignMerge,
     child_windows::{ChildWindows, FileDialogType, FileTarget},
+    dataset::DOSKVOL,
+    dice::DiceRoller,
+    display::MainView,
     district::District,
+    export_format::ItemKind,
     faction::Faction,
+    faction_graph::FactionGraph,
+    faction_turn::FactionTurn,
+    faction_web::{WebFilter, WebFormat},
     localize::fl,
-    managed_list::{DistrictRef, FactionRef, Named, PersonRef},
+    managed_list::{CampaignClockRef, DistrictRef, FactionRef, Named, PersonRef},
+    markdown::MarkdownLayout,
+    merge::MergePreview,
     person::Person,
-    todo::TodoUndo,
+    recovery::{AUTOSAVE_INTERVAL, RecoveredCampaign, Recovery},
+    relationship::MyRelationships,
+    setting_pack::PackChooser,
+    tier::Tier,
+    todo::{TodoKind, TodoUndo},
 };
 
 const ZOOM: f32 = 1.0;
 pub const UI_PADDING: f32 = 8.0;
 const ERROR_SPACE: f32 = 16.0;
+const UNSAVED_MARK: &str = " *";
+
+/// The kinds of item which can go to and from CSV, with the view they are listed in
+const CSV_KINDS: [(ItemKind, MainView); 3] = [
+    (ItemKind::Faction, MainView::Factions),
+    (ItemKind::Person, MainView::Persons),
+    (ItemKind::District, MainView::Districts),
+];
 
 const ERROR_BACKGROUND: Color32 = Color32::from_rgb(255, 190, 190);
 const ERROR_FOREGROUND: Color32 = Color32::DARK_RED;
@@ -71,12 +92,21 @@ pub const FONT_NOTES: &[&str] = &[
 pub struct App {
     status: AppStatus,
     main_view: MainView,
+    show_faction_graph: bool,
+    faction_graph: FactionGraph,
+    web_filter: WebFilter,
     settings: AppSettings,
     project_directories: ProjectDirs,
     data: AppData,
     message: Option<String>,
     child_windows: ChildWindows,
     todo_undo: TodoUndo,
+    dice_roller: DiceRoller,
+    recovery: Recovery,
+    after_save: Option<PendingAction>,
+    continue_with: Option<PendingAction>,
+    close_confirmed: bool,
+    window_title: String,
 }
 
 impl App {
@@ -86,16 +116,46 @@ impl App {
         cc: &CreationContext<'_>,
     ) -> Self {
         configure_fonts(cc, ZOOM);
-        App {
+        let recovery = Recovery::new(project_directories.data_dir());
+        recovery.install_panic_hook();
+        let status = match RecoveredCampaign::find(project_directories.data_dir()) {
+            Some(recovered) => AppStatus::OfferRecovery(recovered),
+            None => AppStatus::default(),
+        };
+        let mut app = App {
             settings,
             project_directories,
-            status: AppStatus::default(),
+            status,
             main_view: MainView::default(),
+            show_faction_graph: false,
+            faction_graph: FactionGraph::default(),
+            web_filter: WebFilter::default(),
             data: AppData::default(),
             message: None,
             child_windows: ChildWindows::default(),
             todo_undo: TodoUndo::default(),
+            dice_roller: DiceRoller::default(),
+            recovery,
+            after_save: None,
+            continue_with: None,
+            close_confirmed: false,
+            window_title: String::new(),
+        };
+
+        let pruned = app.prune_recent();
+        if matches!(app.status, AppStatus::Starting) {
+            let last = app.settings.recent_files().first().cloned();
+            let reopen = last.filter(|_| app.settings.reopen_last());
+            if let Some(last) = &reopen {
+                info!("reopening the last campaign, {}", last.to_string_lossy());
+                app.open_recent(last);
+            }
+            if pruned || reopen.is_some() {
+                // starting would reset the data and the notice
+                app.status = AppStatus::Ready(RefCell::new(None));
+            }
         }
+        app
     }
 
     fn reset(&mut self) {
@@ -106,6 +166,17 @@ impl App {
         self.message = None;
         self.child_windows = ChildWindows::default(); // is this sufficient?
         self.todo_undo = TodoUndo::default();
+        self.web_filter = WebFilter::default();
+        self.recovery.clear();
+        self.after_save = None;
+    }
+
+    /// Puts newly loaded data in place of the current, whose history and views refer to the old data
+    fn replace_data(&mut self, data: AppData) {
+        self.data = data;
+        self.todo_undo.clear();
+        self.recovery.clear();
+        self.main_view = MainView::default();
     }
 
     fn show_top(&mut self, ctx: &Context, _frame: &mut Frame) {
@@ -116,11 +187,30 @@ impl App {
                     let load_enabled = matches!(self.status, AppStatus::Ready(_)); // only save from main list
                     let save_as_enabled = load_enabled && !self.data.no_items();
 
+                    let undo_shortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::Z);
+                    let redo_shortcut =
+                        KeyboardShortcut::new(Modifiers::COMMAND | Modifiers::SHIFT, Key::Z);
+                    if load_enabled {
+                        // check redo first, as undo would also match it
+                        if ui.input_mut(|i| i.consume_shortcut(&redo_shortcut)) {
+                            self.request_redo();
+                        } else if ui.input_mut(|i| i.consume_shortcut(&undo_shortcut)) {
+                            self.request_undo();
+                        }
+                    }
+
                     ui.menu_button(fl!("menu"), |ui| {
                         if ui.button(fl!("menu_restart")).clicked() {
-                            self.status = AppStatus::Starting;
-                            self.message = None;
                             info!("Requested Restart");
+                            self.check_unsaved(PendingAction::Restart);
+                        }
+                        if ui.button(fl!("menu_new_doskvol")).clicked() {
+                            info!("Requested New Campaign from Doskvol");
+                            self.check_unsaved(PendingAction::NewDoskvol);
+                        }
+                        if ui.button(fl!("menu_new_pack")).clicked() {
+                            info!("Requested New Campaign from Pack");
+                            self.check_unsaved(PendingAction::NewFromPack);
                         }
                         ui.add(Separator::default().spacing(2.));
                         if ui
@@ -128,12 +218,92 @@ impl App {
                             .clicked()
                         {
                             info!("Requested Load");
+                            self.check_unsaved(PendingAction::Load);
+                        }
+                        ui.menu_button(fl!("menu_recent"), |ui| {
+                            let recent = self.settings.recent_files().to_vec();
+                            if recent.is_empty() {
+                                ui.label(RichText::new(fl!("recent_none")).italics());
+                            }
+                            for file in &recent {
+                                let name = file.file_name().map_or_else(
+                                    || file.to_string_lossy(),
+                                    |n| n.to_string_lossy(),
+                                );
+                                if ui
+                                    .add_enabled(load_enabled, Button::new(name))
+                                    .on_hover_text(file.to_string_lossy())
+                                    .clicked()
+                                {
+                                    info!("Requested Open Recent {}", file.to_string_lossy());
+                                    self.check_unsaved(PendingAction::OpenRecent(file.clone()));
+                                }
+                            }
+                            ui.add(Separator::default().spacing(2.));
+                            let mut reopen_last = self.settings.reopen_last();
+                            if ui
+                                .checkbox(&mut reopen_last, fl!("recent_reopen_last"))
+                                .changed()
+                            {
+                                self.settings.set_reopen_last(reopen_last);
+                                self.save_settings();
+                            }
+                            if ui
+                                .add_enabled(!recent.is_empty(), Button::new(fl!("recent_clear")))
+                                .clicked()
+                            {
+                                self.settings.clear_recent();
+                                self.save_settings();
+                            }
+                        });
+                        if ui
+                            .add_enabled(load_enabled, Button::new(fl!("menu_restore_backup")))
+                            .clicked()
+                        {
+                            info!("Requested Restore Backup");
+                            self.check_unsaved(PendingAction::RestoreBackup);
+                        }
+                        if ui
+                            .add_enabled(load_enabled, Button::new(fl!("menu_compare")))
+                            .clicked()
+                        {
+                            info!("Requested Compare");
+                            // start with the backups of the current campaign, if there is one
+                            let initial_directory = match self.data.get_loaded_from() {
+                                Some(file) => backup_dir(&file),
+                                None => self.project_directories.data_dir().to_path_buf(),
+                            };
+                            self.child_windows.start_file_dialog(
+                                FileDialogType::Load,
+                                FileTarget::Internal,
+                                initial_directory,
+                            );
+                            self.status = AppStatus::Compare;
+                        }
+                        if ui
+                            .add_enabled(load_enabled, Button::new(fl!("menu_merge_campaigns")))
+                            .clicked()
+                        {
+                            info!("Requested Merge Campaigns");
                             self.child_windows.start_file_dialog(
                                 FileDialogType::Load,
                                 FileTarget::Internal,
                                 self.project_directories.data_dir().to_path_buf(),
                             );
-                            self.status = AppStatus::Load;
+                            self.status = AppStatus::MergeWith;
+                        }
+                        if ui
+                            .add_enabled(load_enabled, Button::new(fl!("menu_update_vault")))
+                            .clicked()
+                        {
+                            // an update can be undone, so there is nothing to save first
+                            info!("Requested Update From Vault");
+                            self.child_windows.start_file_dialog(
+                                FileDialogType::Folder,
+                                FileTarget::Markdown,
+                                self.project_directories.data_dir().to_path_buf(),
+                            );
+                            self.status = AppStatus::UpdateFromVault;
                         }
                         if ui
                             .add_enabled(save_as_enabled, Button::new(fl!("menu_save")))
@@ -169,6 +339,27 @@ impl App {
                             self.status = AppStatus::SaveAs;
                         }
                         ui.add(Separator::default().spacing(2.));
+                        if ui
+                            .add_enabled(
+                                load_enabled && self.todo_undo.can_undo(),
+                                Button::new(fl!("menu_undo"))
+                                    .shortcut_text(ctx.format_shortcut(&undo_shortcut)),
+                            )
+                            .clicked()
+                        {
+                            self.request_undo();
+                        }
+                        if ui
+                            .add_enabled(
+                                load_enabled && self.todo_undo.can_redo(),
+                                Button::new(fl!("menu_redo"))
+                                    .shortcut_text(ctx.format_shortcut(&redo_shortcut)),
+                            )
+                            .clicked()
+                        {
+                            self.request_redo();
+                        }
+                        ui.add(Separator::default().spacing(2.));
                         if ui
                             .add_enabled(load_enabled, Button::new(fl!("menu_import")))
                             .clicked()
@@ -195,6 +386,135 @@ impl App {
                             );
                             self.status = AppStatus::Export;
                         }
+                        if ui
+                            .add_enabled(
+                                save_as_enabled,
+                                Button::new(fl!("menu_export_markdown_pages")),
+                            )
+                            .clicked()
+                        {
+                            info!("Requested Markdown Pages Export");
+                            self.child_windows.start_file_dialog(
+                                FileDialogType::Folder,
+                                FileTarget::Markdown,
+                                self.project_directories.data_dir().to_path_buf(),
+                            );
+                            self.status = AppStatus::ExportMarkdown(MarkdownLayout::Pages);
+                        }
+                        if ui
+                            .add_enabled(
+                                save_as_enabled,
+                                Button::new(fl!("menu_export_markdown_document")),
+                            )
+                            .clicked()
+                        {
+                            info!("Requested Markdown Document Export");
+                            self.child_windows.start_file_dialog(
+                                FileDialogType::Save,
+                                FileTarget::Markdown,
+                                self.project_directories.data_dir().to_path_buf(),
+                            );
+                            self.status = AppStatus::ExportMarkdown(MarkdownLayout::Document);
+                        }
+                        if ui
+                            .add_enabled(save_as_enabled, Button::new(fl!("menu_export_vault")))
+                            .clicked()
+                        {
+                            info!("Requested Vault Export");
+                            self.child_windows.start_file_dialog(
+                                FileDialogType::Folder,
+                                FileTarget::Markdown,
+                                self.project_directories.data_dir().to_path_buf(),
+                            );
+                            self.status = AppStatus::ExportVault;
+                        }
+                        if ui
+                            .add_enabled(save_as_enabled, Button::new(fl!("menu_export_html")))
+                            .clicked()
+                        {
+                            info!("Requested HTML Export");
+                            self.child_windows.start_file_dialog(
+                                FileDialogType::Folder,
+                                FileTarget::Markdown,
+                                self.project_directories.data_dir().to_path_buf(),
+                            );
+                            self.status = AppStatus::ExportHtml;
+                        }
+                        ui.menu_button(fl!("menu_csv"), |ui| {
+                            for (kind, view) in CSV_KINDS {
+                                if ui
+                                    .add_enabled(
+                                        save_as_enabled,
+                                        Button::new(fl!(
+                                            "menu_csv_export",
+                                            kind = view.to_string()
+                                        )),
+                                    )
+                                    .clicked()
+                                {
+                                    info!("Requested CSV Export of {kind}");
+                                    self.child_windows.start_file_dialog(
+                                        FileDialogType::Save,
+                                        FileTarget::Csv,
+                                        self.project_directories.data_dir().to_path_buf(),
+                                    );
+                                    self.status = AppStatus::ExportCsv(kind);
+                                }
+                            }
+                            ui.add(Separator::default().spacing(2.));
+                            for (kind, view) in CSV_KINDS {
+                                if ui
+                                    .add_enabled(
+                                        load_enabled,
+                                        Button::new(fl!(
+                                            "menu_csv_import",
+                                            kind = view.to_string()
+                                        )),
+                                    )
+                                    .clicked()
+                                {
+                                    info!("Requested CSV Import of {kind}");
+                                    self.child_windows.start_file_dialog(
+                                        FileDialogType::Load,
+                                        FileTarget::Csv,
+                                        self.project_directories.data_dir().to_path_buf(),
+                                    );
+                                    self.status = AppStatus::ImportCsv(kind);
+                                }
+                            }
+                        });
+                        ui.menu_button(fl!("menu_web"), |ui| {
+                            self.show_web_filter(ui);
+                            ui.add(Separator::default().spacing(2.));
+                            for (format, target, label) in [
+                                (WebFormat::Dot, FileTarget::Dot, fl!("menu_web_dot")),
+                                (WebFormat::Svg, FileTarget::Svg, fl!("menu_web_svg")),
+                            ] {
+                                if ui
+                                    .add_enabled(save_as_enabled, Button::new(label))
+                                    .clicked()
+                                {
+                                    info!("Requested Faction Web Export as {format:?}");
+                                    self.child_windows.start_file_dialog(
+                                        FileDialogType::Save,
+                                        target,
+                                        self.project_directories.data_dir().to_path_buf(),
+                                    );
+                                    self.status = AppStatus::ExportWeb(format);
+                                }
+                            }
+                        });
+                        ui.add(Separator::default().spacing(2.));
+                        let turn_enabled =
+                            load_enabled && self.data.view_size(MainView::Factions) > 0;
+                        if ui
+                            .add_enabled(turn_enabled, Button::new(fl!("menu_faction_turn")))
+                            .clicked()
+                        {
+                            info!("Requested Faction Turn");
+                            let turn = FactionTurn::roll(&self.data, &mut rand::thread_rng());
+                            self.status = AppStatus::FactionTurn(RefCell::new(turn));
+                        }
                         ui.add(Separator::default().spacing(2.));
                         if ui
                             .add_enabled(false, Button::new(fl!("menu_settings")))
@@ -215,12 +535,171 @@ impl App {
                             self.child_windows.toggle_about();
                             info!("Requested About");
                         }
+                        if ui
+                            .selectable_label(self.dice_roller.is_visible(), fl!("menu_dice"))
+                            .clicked()
+                        {
+                            self.dice_roller.toggle();
+                            info!("Requested Dice");
+                        }
                     });
                 });
             });
         });
     }
 
+    /// Carries out the action, unless there are unsaved changes to ask about first
+    fn check_unsaved(&mut self, pending: PendingAction) {
+        if self.data.is_changed() {
+            info!("unsaved changes before {pending:?}");
+            self.status = AppStatus::ConfirmUnsaved(pending);
+        } else {
+            self.continue_with = Some(pending);
+        }
+    }
+
+    fn continue_pending(&mut self, ctx: &Context, pending: PendingAction) {
+        info!("continuing with {pending:?}");
+        match pending {
+            PendingAction::Exit => {
+                self.close_confirmed = true;
+                ctx.send_viewport_cmd(ViewportCommand::Close);
+            }
+            PendingAction::Restart => {
+                self.status = AppStatus::Starting;
+                self.message = None;
+            }
+            PendingAction::NewDoskvol => match AppData::from_dataset(DOSKVOL) {
+                Ok(data) => {
+                    self.reset();
+                    self.data = data;
+                    self.status = AppStatus::Ready(RefCell::new(None));
+                    info!("started a new campaign from {DOSKVOL}");
+                }
+                Err(e) => {
+                    self.message = Some(format!("Unable to start a campaign from [{DOSKVOL}]"));
+                    error!("Error on loading dataset {DOSKVOL}: {e}");
+                }
+            },
+            PendingAction::NewFromPack => {
+                let chooser = PackChooser::new(self.project_directories.data_dir());
+                self.status = AppStatus::ChoosePack(RefCell::new(chooser));
+            }
+            PendingAction::Load => {
+                self.child_windows.start_file_dialog(
+                    FileDialogType::Load,
+                    FileTarget::Internal,
+                    self.project_directories.data_dir().to_path_buf(),
+                );
+                self.status = AppStatus::Load;
+            }
+            PendingAction::RestoreBackup => {
+                // start with the backups of the current campaign, if there is one
+                let initial_directory = match self.data.get_loaded_from() {
+                    Some(file) => backup_dir(&file),
+                    None => self.project_directories.data_dir().join(BACKUP_DIR),
+                };
+                self.child_windows.start_file_dialog(
+                    FileDialogType::Load,
+                    FileTarget::Internal,
+                    initial_directory,
+                );
+                self.status = AppStatus::RestoreBackup;
+            }
+            PendingAction::OpenRecent(file_path) => self.open_recent(&file_path),
+        }
+    }
+
+    /// Loads a campaign from the recent files, dropping any which have gone from the list
+    fn open_recent(&mut self, file_path: &Path) {
+        if !file_path.is_file() {
+            self.prune_recent();
+            return;
+        }
+        match AppData::load_from_file(file_path) {
+            Ok(data) => {
+                self.replace_data(data);
+                if self.settings.add_recent(file_path) {
+                    self.save_settings();
+                }
+                info!("loaded data from {}", file_path.to_string_lossy());
+            }
+            Err(e) => {
+                let file = file_path
+                    .file_name()
+                    .map_or(OsStr::new("<no file>").to_string_lossy(), |f| {
+                        f.to_string_lossy()
+                    });
+                self.message = Some(format!("Unable to load save file [{file}]"));
+                error!(
+                    "Error on file load for [{}]: {}",
+                    file_path.to_string_lossy(),
+                    e
+                );
+            }
+        }
+    }
+
+    /// Drops recent files which no longer exist, with a notice, returning true if any were
+    fn prune_recent(&mut self) -> bool {
+        let missing = self.settings.prune_recent();
+        if missing.is_empty() {
+            return false;
+        }
+        let files: Vec<String> = missing
+            .iter()
+            .map(|f| f.to_string_lossy().to_string())
+            .collect();
+        warn!("recent files no longer found: {}", files.join(", "));
+        self.message = Some(format!(
+            "Removed from recent files, as they were not found: {}",
+            files.join(", ")
+        ));
+        self.save_settings();
+        true
+    }
+
+    fn save_settings(&self) {
+        let config_dir = self.project_directories.config_dir();
+        if let Err(err) = self.settings.save_to_file(config_dir) {
+            error!(
+                "Unable to save settings to {}: {}",
+                config_dir.display(),
+                err
+            );
+        }
+    }
+
+    /// The window title, with the campaign name, and a mark if it has unsaved changes
+    fn window_title(&self) -> String {
+        let mut title = format!("{} (version {})", APP_NAME, env!("CARGO_PKG_VERSION"));
+        if let Some(file) = self.data.get_loaded_from()
+            && let Some(name) = file.file_stem()
+        {
+            title.push_str(&format!(" - {}", name.to_string_lossy()));
+        }
+        if self.data.is_changed() {
+            title.push_str(UNSAVED_MARK);
+        }
+        title
+    }
+
+    fn request_undo(&mut self) {
+        if self.todo_undo.undo() {
+            info!("Requested Undo");
+        } else {
+            info!("Requested Undo, with nothing to undo");
+        }
+    }
+
+    fn request_redo(&mut self) {
+        if self.todo_undo.redo() {
+            info!("Requested Redo");
+        } else {
+            info!("Requested Redo, with nothing to redo");
+        }
+    }
+
     fn show_footer(&mut self, ctx: &Context) {
         TopBottomPanel::bottom("footer").show(ctx, |ui| {
             ui.add_space(5.);
@@ -231,6 +710,9 @@ impl App {
                 {
                     ui.label(RichText::new(format!("({})", name.to_string_lossy())).italics());
                 }
+                if self.data.is_changed() {
+                    ui.label(RichText::new(fl!("unsaved_changes")).italics());
+                }
                 if let Some(message) = &self.message {
                     let error_message = RichText::new(fl!("app_error_err", err = message))
                         .strong()
@@ -262,6 +744,30 @@ impl eframe::App for App {
 
         self.show_top(ctx, frame);
         self.show_footer(ctx);
+        self.dice_roller.show(ctx);
+
+        self.recovery.autosave_if_due();
+        if self.recovery.is_pending() {
+            ctx.request_repaint_after(AUTOSAVE_INTERVAL);
+        }
+        if ctx.input(|i| i.viewport().close_requested()) {
+            if self.data.is_changed() && !self.close_confirmed {
+                ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+                if !matches!(self.status, ConfirmUnsaved(_)) {
+                    info!("close requested, with unsaved changes");
+                    self.status = ConfirmUnsaved(PendingAction::Exit);
+                }
+            } else {
+                info!("closing - recovery file no longer needed");
+                self.recovery.clear();
+            }
+        }
+
+        let title = self.window_title();
+        if title != self.window_title {
+            ctx.send_viewport_cmd(ViewportCommand::Title(title.clone()));
+            self.window_title = title;
+        }
 
         // todo: can we run the todo list here? Or will that lead to slowdown?
 
@@ -280,6 +786,65 @@ impl eframe::App for App {
                     // None
                 }
 
+                ConfirmUnsaved(pending) => {
+                    match self.show_confirm_unsaved(ui, pending) {
+                        Some(UnsavedChoice::Save) => {
+                            self.after_save = Some(pending.clone());
+                            if self.data.get_loaded_from().is_some() {
+                                info!("ConfirmUnsaved => SaveTo");
+                                Some(SaveTo)
+                            } else {
+                                self.child_windows.start_file_dialog(
+                                    FileDialogType::Save,
+                                    FileTarget::Internal,
+                                    self.project_directories.data_dir().to_path_buf(),
+                                );
+                                info!("ConfirmUnsaved => SaveAs");
+                                Some(SaveAs)
+                            }
+                        }
+                        Some(UnsavedChoice::Discard) => {
+                            info!("discarding unsaved changes");
+                            self.continue_with = Some(pending.clone());
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        Some(UnsavedChoice::Cancel) => {
+                            info!("ConfirmUnsaved => Ready");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        None => None,
+                    }
+                }
+
+                OfferRecovery(recovered) => {
+                    match self.show_offer_recovery(ui, recovered) {
+                        Some(EditResult::Submit) => {
+                            match recovered.load() {
+                                Ok(data) => {
+                                    self.data = data;
+                                    // still unsaved, so keep it recoverable
+                                    self.recovery.record(&self.data);
+                                    info!("recovered campaign");
+                                }
+
+                                Err(e) => {
+                                    self.message = Some("Unable to recover campaign".to_string());
+                                    error!("Error on recovering campaign: {e}");
+                                    recovered.discard();
+                                }
+                            }
+                            info!("OfferRecovery => Ready");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        Some(_) => {
+                            recovered.discard();
+                            info!("OfferRecovery => Starting");
+                            Some(Starting)
+                        }
+                        None => None,
+                    }
+                }
+
                 Ready ( hovered_line ) => {
                     // what are we looking at?
                     // select between views
@@ -288,13 +853,16 @@ impl eframe::App for App {
                         info!("Ready -> Create New {}", self.main_view.item_name());
                         match self.main_view {
                             MainView::Districts => Some(ShowEditDistrict(None, RefCell::new(District::default()))),
-                            MainView::Persons => Some(ShowEditPerson(None, RefCell::new(Person::default()))),
-                            MainView::Factions => Some(ShowEditFaction(None, RefCell::new(Faction::default()))),
+                            MainView::Persons => Some(ShowEditPerson(None, RefCell::new(Person::default()), RefCell::new(MyRelationships::none()))),
+                            MainView::Factions => Some(ShowEditFaction(None, RefCell::new(Faction::default()), RefCell::new(MyRelationships::none()))),
+                            MainView::Clocks => Some(ShowEditClock(None, RefCell::new(CampaignClock::default()))),
                         }
                     } else {
                         // not asking for a new item
-                        if let ViewRequest::NewView(new_view) = view_request {
-                            self.main_view = new_view;
+                        match view_request {
+                            ViewRequest::NewView(new_view) => self.main_view = new_view,
+                            ViewRequest::ToggleGraph => self.show_faction_graph = !self.show_faction_graph,
+                            _ => (),
                         }
 
                         // find or build display data table
@@ -310,6 +878,10 @@ impl eframe::App for App {
                             MainView::Factions => {
                                 self.data.factions_display_table()
                             }
+
+                            MainView::Clocks => {
+                                self.data.clocks_display_table()
+                            }
                         };
 
                         // show table with display data
@@ -324,83 +896,91 @@ impl eframe::App for App {
                         let mut new_selected = None;
                         let mut new_hovered_line = None;
 
-                        ui.horizontal_top(|ui| {
-                            ui.with_layout(Layout::top_down_justified(Align::Min), |ui| {
-                                ui.add_space(TINY_SPACE);
-                                eframe::egui::Frame::default()
-                                    .stroke(Stroke::new(STROKE_WIDTH, STROKE_COLOR))
-                                    .inner_margin(INNER_MARGIN)
-                                    .show(ui, |ui| {
-                                        ui.vertical(|ui| {
-                                            ui.style_mut().wrap_mode = Some(eframe::egui::TextWrapMode::Extend);
-                                            let mut table = TableBuilder::new(ui)
-                                                .striped(true)
-                                                .sense(Sense::click())
-                                                .resizable(true)
-                                                .auto_shrink([false, true]);
-
-                                            for col in display_table.column_definitions_iter() {
-                                                table = table.column(col);
-                                            }
-
-                                            table.header(HEADER_HEIGHT, |mut header| {
-                                                for (i, heading) in display_table.headings_iter().enumerate() {
-                                                    header.col(|ui| {
-                                                        if ui.add(Label::new(heading).sense(Sense::click())).clicked() {
-                                                            new_sort = Some(i);
-                                                        }
-                                                    });
+                        let mut graph_selected = None;
+                        if self.main_view == MainView::Factions && self.show_faction_graph {
+                            self.faction_graph.sync(&self.data);
+                            graph_selected = self.faction_graph.show(ui);
+                        } else {
+                            ui.horizontal_top(|ui| {
+                                ui.with_layout(Layout::top_down_justified(Align::Min), |ui| {
+                                    ui.add_space(TINY_SPACE);
+                                    eframe::egui::Frame::default()
+                                        .stroke(Stroke::new(STROKE_WIDTH, STROKE_COLOR))
+                                        .inner_margin(INNER_MARGIN)
+                                        .show(ui, |ui| {
+                                            ui.vertical(|ui| {
+                                                ui.style_mut().wrap_mode = Some(eframe::egui::TextWrapMode::Extend);
+                                                let mut table = TableBuilder::new(ui)
+                                                    .striped(true)
+                                                    .sense(Sense::click())
+                                                    .resizable(true)
+                                                    .auto_shrink([false, true]);
+
+                                                for col in column_definitions_iter(&display_table) {
+                                                    table = table.column(col);
                                                 }
-                                            })
-                                            .body(|body| {
-                                                body.rows(ROW_HEIGHT, display_table.lines_len(), | mut row | {
-                                                    let i = row.index();
-                                                    let display_line = display_table.line(i);
-                                                    let mut field_click = false;
-                                                    let mut field_hover = false;
-
-                                                    if let Some(h_line) = *hovered_line.borrow() {
-                                                        let hovering = i == h_line;
-                                                        row.set_hovered(hovering);
-                                                    }
-
-                                                    for f in display_line.field_iter() {
-                                                        row.col(|ui| {
-                                                            let col_resp = ui.add(
-                                                                Label::new(f)
-                                                                .sense(Sense::click())
-                                                            );
-
-                                                            field_click |= col_resp.clicked();
 
-                                                            if col_resp.hovered() {
-                                                                field_hover = true;
+                                                table.header(HEADER_HEIGHT, |mut header| {
+                                                    for (i, heading) in headings_iter(&display_table).enumerate() {
+                                                        header.col(|ui| {
+                                                            if ui.add(Label::new(heading).sense(Sense::click())).clicked() {
+                                                                new_sort = Some(i);
                                                             }
                                                         });
                                                     }
+                                                })
+                                                .body(|body| {
+                                                    body.rows(ROW_HEIGHT, display_table.lines_len(), | mut row | {
+                                                        let i = row.index();
+                                                        let display_line = display_table.line(i);
+                                                        let mut field_click = false;
+                                                        let mut field_hover = false;
+
+                                                        if let Some(h_line) = *hovered_line.borrow() {
+                                                            let hovering = i == h_line;
+                                                            row.set_hovered(hovering);
+                                                        }
+
+                                                        for f in display_line.field_iter() {
+                                                            row.col(|ui| {
+                                                                let col_resp = ui.add(
+                                                                    Label::new(f)
+                                                                    .sense(Sense::click())
+                                                                );
 
-                                                    let row_resp = row.response();
-
-                                                    if field_click || row_resp.clicked() {
-                                                        debug!("row {} ({}) clicked", i, display_line.id());
-                                                        new_selected = Some(display_line.id());
-                                                    } else if field_hover || row_resp.hovered() {
-                                                        let already_hovered = {
-                                                            if let Some(hover) = *hovered_line.borrow() {
-                                                                hover == i
-                                                            } else { false }
-                                                        };
-                                                        if !already_hovered {
-                                                            debug!("row {i} hovered");
+                                                                field_click |= col_resp.clicked();
+
+                                                                if col_resp.hovered() {
+                                                                    field_hover = true;
+                                                                }
+                                                            });
                                                         }
-                                                        new_hovered_line = Some(i);  // set this regardless
-                                                    }
+
+                                                        let row_resp = row.response();
+
+                                                        if field_click || row_resp.clicked() {
+                                                            debug!("row {} ({}) clicked", i, display_line.id());
+                                                            new_selected = Some(display_line.id());
+                                                        } else if field_hover || row_resp.hovered() {
+                                                            let already_hovered = {
+                                                                if let Some(hover) = *hovered_line.borrow() {
+                                                                    hover == i
+                                                                } else { false }
+                                                            };
+                                                            if !already_hovered {
+                                                                debug!("row {i} hovered");
+                                                            }
+                                                            new_hovered_line = Some(i);  // set this regardless
+                                                        }
+                                                    });
                                                 });
                                             });
                                         });
-                                    });
+                                });
                             });
-                        });
+
+                        }
+                        let new_selected = new_selected.or(graph_selected.as_ref());
 
                         *hovered_line.borrow_mut() = new_hovered_line;
 
@@ -431,7 +1011,8 @@ impl eframe::App for App {
                                     if let Some(show) = self.data.find_person(id) {
                                         if let Some(person) = self.data.clone_person(&show) {
                                             info!("Ready -> Show Person ({id})");
-                                            Some(ShowEditPerson(Some(show), RefCell::new(person)))
+                                            let relationships = self.data.relationships().my_relationships(show.data_index());
+                                            Some(ShowEditPerson(Some(show), RefCell::new(person), RefCell::new(relationships)))
                                         } else { unreachable!("unable to clone person {id} using reference"); }
                                     } else { unreachable!("selected person '{id}' which is not in list"); }
                                 } else { None }
@@ -447,11 +1028,28 @@ impl eframe::App for App {
                                     if let Some(show) = self.data.find_faction(id) {
                                         if let Some(faction) = self.data.clone_faction(&show) {
                                             info!("Ready -> Show Faction ({id})");
-                                            Some(ShowEditFaction(Some(show), RefCell::new(faction)))
+                                            let relationships = self.data.relationships().my_relationships(show.data_index());
+                                            Some(ShowEditFaction(Some(show), RefCell::new(faction), RefCell::new(relationships)))
                                         } else { unreachable!("unable to clone faction {id} using reference"); }
                                     } else { unreachable!("selected faction '{id}' which is not in list"); }
                                 } else { None }
                             }
+
+                            MainView::Clocks => {
+                                if let Some(sort_index) = new_sort {
+                                    debug!("setting clocks col {sort_index} to sort");
+                                    self.data.set_clocks_sort(sort_index);
+                                    None
+                                } else if let Some(id) = new_selected {
+                                    debug!("selected clock {id}");
+                                    if let Some(show) = self.data.find_clock(id) {
+                                        if let Some(clock) = self.data.clone_clock(&show) {
+                                            info!("Ready -> Show Clock ({id})");
+                                            Some(ShowEditClock(Some(show), RefCell::new(clock)))
+                                        } else { unreachable!("unable to clone clock {id} using reference"); }
+                                    } else { unreachable!("selected clock '{id}' which is not in list"); }
+                                } else { None }
+                            }
                         }
                     }
                 }
@@ -501,11 +1099,17 @@ impl eframe::App for App {
                                 info!("ignore edited district");
                                 Some(Ready(RefCell::new(None)))
                             },
+                            Delete => {
+                                if let Some(index_ref) = index_ref {
+                                    info!("ShowDistrict -> Confirm Delete");
+                                    Some(ConfirmDelete(index_ref.data_index()))
+                                } else { Some(Ready(RefCell::new(None))) }
+                            },
                         }
                     } else { None }
                 }
 
-                ShowEditPerson( index_ref, person, ) => {
+                ShowEditPerson( index_ref, person, relationships, ) => {
                     let mut person = person.borrow_mut();
                     let (name_collision, differs_from) = if let Some(index_ref) = index_ref {
                         let old_name = index_ref.name().map_or("<none>".to_string(), |n| n);
@@ -518,8 +1122,10 @@ impl eframe::App for App {
                             (false, true)
                         }
                     } else { (self.data.find_person(person.name()).is_some(), Person::default() != *person) };
+                    let differs_from = differs_from || relationships.borrow().is_changed();
 
-                    let item_info = ShowEditInfo::new(name_collision, differs_from, index_ref.is_none(), &self.data);
+                    let item_info = ShowEditInfo::new(name_collision, differs_from, index_ref.is_none(), &self.data)
+                        .with_relationships(relationships);
 
                     if let Some(edit_result) = person.show_edit(ui, item_info) {
                         use EditResult::*;
@@ -529,9 +1135,14 @@ impl eframe::App for App {
                                 if let Some(index_ref) = index_ref {
                                     // fetch indexed item
                                     if let Some(old_person) = self.data.clone_person(index_ref) {
+                                        let mut node = ActionNode::new();
                                         if old_person != *person {
                                             info!("replacing existing person");
-                                            self.todo_undo.add_todo(ActionNode::from(Action::PersonReplace(index_ref.clone(), person.clone())));
+                                            node.push_back(Action::PersonReplace(index_ref.clone(), person.clone()));
+                                        }
+                                        node.extend(relationships.borrow().changes().into_iter().map(|(pair, r)| Action::RelationshipSet(pair, r)));
+                                        if !node.is_empty() {
+                                            self.todo_undo.add_todo(node);
                                         }  else {
                                             debug!("new person matches existing person - no action taken");
                                         }
@@ -550,11 +1161,17 @@ impl eframe::App for App {
                                 info!("ignore edited person");
                                 Some(Ready(RefCell::new(None)))
                             },
+                            Delete => {
+                                if let Some(index_ref) = index_ref {
+                                    info!("ShowPerson -> Confirm Delete");
+                                    Some(ConfirmDelete(index_ref.data_index()))
+                                } else { Some(Ready(RefCell::new(None))) }
+                            },
                         }
                     } else { None }
                 }
 
-                ShowEditFaction( index_ref, faction, ) => {
+                ShowEditFaction( index_ref, faction, relationships, ) => {
                     let mut faction = faction.borrow_mut();
                     let (name_collision, differs_from) = if let Some(index_ref) = index_ref {
                         let old_name = index_ref.name().map_or("<none>".to_string(), |n| n);
@@ -567,8 +1184,10 @@ impl eframe::App for App {
                             (false, true)
                         }
                     } else { (self.data.find_faction(faction.name()).is_some(), Faction::default() != *faction) };
+                    let differs_from = differs_from || relationships.borrow().is_changed();
 
-                    let item_info = ShowEditInfo::new(name_collision, differs_from, index_ref.is_none(), &self.data);
+                    let item_info = ShowEditInfo::new(name_collision, differs_from, index_ref.is_none(), &self.data)
+                        .with_relationships(relationships);
 
                     if let Some(edit_result) = faction.show_edit(ui, item_info) {
                         use EditResult::*;
@@ -578,9 +1197,14 @@ impl eframe::App for App {
                                 if let Some(index_ref) = index_ref {
                                     // fetch indexed item
                                     if let Some(old_faction) = self.data.clone_faction(index_ref) {
+                                        let mut node = ActionNode::new();
                                         if old_faction != *faction {
                                             info!("replacing existing faction");
-                                            self.todo_undo.add_todo(ActionNode::from(Action::FactionReplace(index_ref.clone(), faction.clone())));
+                                            node.push_back(Action::FactionReplace(index_ref.clone(), faction.clone()));
+                                        }
+                                        node.extend(relationships.borrow().changes().into_iter().map(|(pair, r)| Action::RelationshipSet(pair, r)));
+                                        if !node.is_empty() {
+                                            self.todo_undo.add_todo(node);
                                         }  else {
                                             debug!("new faction matches existing faction - no action taken");
                                         }
@@ -599,47 +1223,409 @@ impl eframe::App for App {
                                 info!("ignore edited faction");
                                 Some(Ready(RefCell::new(None)))
                             },
+                            Delete => {
+                                if let Some(index_ref) = index_ref {
+                                    info!("ShowFaction -> Confirm Delete");
+                                    Some(ConfirmDelete(index_ref.data_index()))
+                                } else { Some(Ready(RefCell::new(None))) }
+                            },
                         }
                     } else { None }
                 }
 
-                Load => {
-                    if let Some(selected) = self.child_windows.selected_file() {
-                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
-                            // process file
-                            info!("selected file: {}", selected.to_string_lossy());
+                ShowEditClock( index_ref, clock, ) => {
+                    let mut clock = clock.borrow_mut();
+                    let (name_collision, differs_from) = if let Some(index_ref) = index_ref {
+                        let old_name = index_ref.name().map_or("<none>".to_string(), |n| n);
+                        if let Some(old_clock) = self.data.clone_clock(index_ref) {
+                            if old_name != clock.name() {
+                                (self.data.find_clock(clock.name()).is_some(), true)
+                            } else { (false, old_clock != *clock) }
+                        } else {
+                            error!("unable to find clock '{old_name}' when index ref exists, during replace");
+                            (false, true)
+                        }
+                    } else { (self.data.find_clock(clock.name()).is_some(), CampaignClock::default() != *clock) };
 
-                            match AppData::load_from_file(selected.as_path()) {
-                                Ok(data) => {
-                                    self.data = data;
-                                    self.main_view = MainView::default();
-                                    info!("loaded data from {}", selected.to_string_lossy());
-                                }
+                    let item_info = ShowEditInfo::new(name_collision, differs_from, index_ref.is_none(), &self.data);
 
-                                Err(e) => {
-                                    let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
-                                    // let message = format!("{}, when loading file [{file}]", e);
-                                    let message = format!("Unable to load save file [{file}]");
-                                    self.message = Some(message);
-                                    error!("Error on file load for [{}]: {}", selected.to_string_lossy(), e);
+                    if let Some(edit_result) = clock.show_edit(ui, item_info) {
+                        use EditResult::*;
+                        match edit_result {
+                            Submit => {
+                                info!("submit edited clock");
+                                if let Some(index_ref) = index_ref {
+                                    // fetch indexed item
+                                    if let Some(old_clock) = self.data.clone_clock(index_ref) {
+                                        if old_clock != *clock {
+                                            info!("replacing existing clock");
+                                            self.todo_undo.add_todo(self.data.clock_replace_actions(index_ref, clock.clone()));
+                                        }  else {
+                                            debug!("new clock matches existing clock - no action taken");
+                                        }
+                                    } else {
+                                        let old_name = index_ref.name().map_or("<none>".to_string(), |n| n);
+                                        error!("unable to find existing clock {old_name} in data, on replace attempt");
+                                    }
+                                } else {
+                                    // no index, thus this is an Add
+                                    info!("adding new clock");
+                                    self.todo_undo.add_todo(ActionNode::from(Action::ClockAdd(clock.clone())));
                                 }
-                            }
-                        } else { info!("no load file selected - ignoring"); }
-                        info!("Loading => Ready");
-                        Some(Ready(RefCell::new(None)))
-                    } else { None }
-                }
-
-                SaveAs => {
-                    if let Some(selected) = self.child_windows.selected_file() {
-                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
-                            // process file
+                                Some(Ready(RefCell::new(None)))
+                            },
+                            Ignore => {
+                                info!("ignore edited clock");
+                                Some(Ready(RefCell::new(None)))
+                            },
+                            Delete => {
+                                if let Some(index_ref) = index_ref {
+                                    info!("ShowClock -> Confirm Delete");
+                                    Some(ConfirmDelete(index_ref.data_index()))
+                                } else { Some(Ready(RefCell::new(None))) }
+                            },
+                        }
+                    } else { None }
+                }
+
+                ConfirmDelete(target) => {
+                    let target = *target;
+                    match self.show_confirm_delete(ui, target) {
+                        Some(EditResult::Submit) => {
+                            if let Some(node) = self.data.remove_item_actions(target) {
+                                info!("deleting {target:?}, with {} actions", node.len());
+                                self.todo_undo.add_todo(node);
+                            } else {
+                                error!("unable to build delete actions for {target:?}");
+                            }
+                            info!("ConfirmDelete => Ready");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        Some(_) => {
+                            info!("delete cancelled");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        None => None,
+                    }
+                }
+
+                FactionTurn(turn) => {
+                    match self.show_faction_turn(ui, turn) {
+                        Some(EditResult::Submit) => {
+                            let node = turn.borrow().actions(&self.data);
+                            if node.is_empty() {
+                                info!("faction turn has nothing to apply");
+                            } else {
+                                info!("applying faction turn, with {} actions", node.len());
+                                self.todo_undo.add_todo(node);
+                            }
+                            info!("FactionTurn => Ready");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        Some(_) => {
+                            info!("faction turn cancelled");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        None => None,
+                    }
+                }
+
+                ChoosePack(chooser) => {
+                    match self.show_pack_chooser(ui, chooser) {
+                        Some(EditResult::Submit) => {
+                            // a copy, so the chooser is not borrowed while the data is replaced
+                            let selected = chooser.borrow().selected().cloned();
+                            if let Some(pack) = selected {
+                                match AppData::from_pack(pack.path()) {
+                                    Ok(data) => {
+                                        self.replace_data(data);
+                                        info!("started a new campaign from pack {}", pack.path().to_string_lossy());
+                                    }
+
+                                    Err(e) => {
+                                        let message = format!("Unable to start a campaign from pack [{}]", pack.manifest().name);
+                                        self.message = Some(message);
+                                        error!("Error on loading pack [{}]: {}", pack.path().to_string_lossy(), e);
+                                    }
+                                }
+                            }
+                            info!("ChoosePack => Ready");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        Some(_) => {
+                            info!("pack chooser cancelled");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        None => None,
+                    }
+                }
+
+                ImportMerge(preview) => {
+                    match self.show_merge_preview(ui, preview) {
+                        Some(EditResult::Submit) => {
+                            match preview.borrow().merged().and_then(|merged| self.data.apply_merge(merged)) {
+                                Ok(reverse) => {
+                                    self.todo_undo.clear_done(); // as for any other change
+                                    self.todo_undo.complete(TodoKind::Change, reverse);
+                                    self.recovery.record(&self.data);
+                                    info!("imported data");
+                                }
+
+                                Err(e) => {
+                                    self.message = Some("Unable to import data".to_string());
+                                    error!("Error on merging imported data: {}", e);
+                                }
+                            }
+                            info!("ImportMerge => Ready");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        Some(_) => {
+                            info!("import cancelled");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        None => None,
+                    }
+                }
+
+                Load => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            // process file
+                            info!("selected file: {}", selected.to_string_lossy());
+
+                            match AppData::load_from_file(selected.as_path()) {
+                                Ok(data) => {
+                                    self.replace_data(data);
+                                    if self.settings.add_recent(&selected) {
+                                        self.save_settings();
+                                    }
+                                    info!("loaded data from {}", selected.to_string_lossy());
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
+                                    // let message = format!("{}, when loading file [{file}]", e);
+                                    let message = format!("Unable to load save file [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on file load for [{}]: {}", selected.to_string_lossy(), e);
+                                }
+                            }
+                        } else { info!("no load file selected - ignoring"); }
+                        info!("Loading => Ready");
+                        Some(Ready(RefCell::new(None)))
+                    } else { None }
+                }
+
+                RestoreBackup => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            info!("selected backup: {}", selected.to_string_lossy());
+
+                            match AppData::load_from_file(selected.as_path()) {
+                                Ok(data) => {
+                                    self.replace_data(data);
+                                    // saving a backup goes back to its campaign, which still holds the
+                                    // newer data until then; any other file is saved as it was loaded
+                                    if let Some(original) = original_of(&selected) {
+                                        self.data.set_loaded_from(Some(original));
+                                        self.data.mark_changed();
+                                    }
+                                    info!("restored data from {}", selected.to_string_lossy());
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to restore backup [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on backup restore for [{}]: {}", selected.to_string_lossy(), e);
+                                }
+                            }
+                        } else { info!("no backup file selected - ignoring"); }
+                        info!("RestoreBackup => Ready");
+                        Some(Ready(RefCell::new(None)))
+                    } else { None }
+                }
+
+                Compare => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            info!("selected file: {}", selected.to_string_lossy());
+
+                            match self.data.diff_with_file(selected.as_path()) {
+                                Ok(diff) => {
+                                    info!("Compare => ShowDiff");
+                                    Some(ShowDiff(diff))
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to compare with [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on comparing with [{}]: {}", selected.to_string_lossy(), e);
+                                    info!("Compare => Ready");
+                                    Some(Ready(RefCell::new(None)))
+                                }
+                            }
+                        } else {
+                            info!("no file to compare selected - ignoring");
+                            info!("Compare => Ready");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                    } else { None }
+                }
+
+                ShowDiff(diff) => {
+                    match self.show_diff(ui, diff) {
+                        Some(EditResult::Submit) => {
+                            self.child_windows.start_file_dialog(
+                                FileDialogType::Save,
+                                FileTarget::Markdown,
+                                self.project_directories.data_dir().to_path_buf(),
+                            );
+                            info!("ShowDiff => ExportDiff");
+                            Some(ExportDiff(diff.clone()))
+                        }
+                        Some(_) => {
+                            info!("ShowDiff => Ready");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        None => None,
+                    }
+                }
+
+                ExportDiff(diff) => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            info!("selected file: {}", selected.to_string_lossy());
+
+                            if let Err(e) = diff.export_markdown(selected.as_path()) {
+                                let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
+                                let message = format!("Unable to export changes to [{file}]");
+                                self.message = Some(message);
+                                error!("Error on changes export to [{}]: {}", selected.to_string_lossy(), e);
+                            }
+                        } else { info!("no changes export selected - ignoring"); }
+                        // back to the changes, which may still be wanted
+                        info!("ExportDiff => ShowDiff");
+                        Some(ShowDiff(diff.clone()))
+                    } else { None }
+                }
+
+                MergeWith => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            info!("selected file: {}", selected.to_string_lossy());
+
+                            match self.data.merge_with_file(selected.as_path()) {
+                                Ok(campaigns) => {
+                                    info!("MergeWith => MergeCampaigns");
+                                    Some(MergeCampaigns(RefCell::new(campaigns)))
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to merge with [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on merging with [{}]: {}", selected.to_string_lossy(), e);
+                                    info!("MergeWith => Ready");
+                                    Some(Ready(RefCell::new(None)))
+                                }
+                            }
+                        } else {
+                            info!("no campaign to merge selected - ignoring");
+                            info!("MergeWith => Ready");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                    } else { None }
+                }
+
+                MergeCampaigns(campaigns) => {
+                    match self.show_campaign_merge(ui, campaigns) {
+                        Some(EditResult::Submit) => {
+                            // the merged campaign is a new file, and the current one is left as it is
+                            self.child_windows.start_file_dialog(
+                                FileDialogType::Save,
+                                FileTarget::Internal,
+                                self.project_directories.data_dir().to_path_buf(),
+                            );
+                            info!("MergeCampaigns => SaveMerged");
+                            Some(SaveMerged(campaigns.borrow().clone()))
+                        }
+                        Some(_) => {
+                            info!("campaign merge cancelled");
+                            Some(Ready(RefCell::new(None)))
+                        }
+                        None => None,
+                    }
+                }
+
+                SaveMerged(campaigns) => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            info!("selected file: {}", selected.to_string_lossy());
+
+                            let saved = campaigns.merged()
+                                .and_then(AppData::from_json_value)
+                                .and_then(|mut data| data.save_to_file(selected.as_path()));
+                            match saved {
+                                Ok(()) => {
+                                    info!("saved merged campaigns to {}", selected.to_string_lossy());
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to save merged campaigns to [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on saving merged campaigns to [{}]: {}", selected.to_string_lossy(), e);
+                                }
+                            }
+                        } else { info!("no file for merged campaigns selected - ignoring"); }
+                        info!("SaveMerged => Ready");
+                        Some(Ready(RefCell::new(None)))
+                    } else { None }
+                }
+
+                UpdateFromVault => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank folder selected, indicating cancel
+                            info!("selected vault: {}", selected.to_string_lossy());
+
+                            match self.data.update_from_vault(selected.as_path()) {
+                                Ok(reverse) => {
+                                    self.todo_undo.clear_done(); // as for any other change
+                                    self.todo_undo.complete(TodoKind::Change, reverse);
+                                    self.recovery.record(&self.data);
+                                    info!("updated from vault {}", selected.to_string_lossy());
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no folder>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to update from vault [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on vault update from [{}]: {}", selected.to_string_lossy(), e);
+                                }
+                            }
+                        } else { info!("no vault selected - ignoring"); }
+                        info!("UpdateFromVault => Ready");
+                        Some(Ready(RefCell::new(None)))
+                    } else { None }
+                }
+
+                SaveAs => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            // process file
                             info!("selected file: {}", selected.to_string_lossy());
 
                             match self.data.save_to_file(selected.as_path()) {
                                 Ok(()) => {
                                     info!("saved data to {}", selected.to_string_lossy());
+                                    if self.settings.add_recent(&selected) {
+                                        self.save_settings();
+                                    }
                                     self.data.set_loaded_from(Some(selected));
+                                    self.recovery.clear();
+                                    self.continue_with = self.after_save.take();
                                 }
 
                                 Err(e) => {
@@ -651,6 +1637,7 @@ impl eframe::App for App {
                                 }
                             }
                         } else { info!("no save file selected - ignoring"); }
+                        self.after_save = None; // a failed or cancelled save stops what was waiting on it
                         info!("SaveAs => Ready");
                         Some(Ready(RefCell::new(None)))
                     } else { None }
@@ -665,6 +1652,11 @@ impl eframe::App for App {
                             match self.data.save_to_file(selected.as_path()) {
                                 Ok(()) => {
                                     info!("saved data to {}", selected.to_string_lossy());
+                                    if self.settings.add_recent(&selected) {
+                                        self.save_settings();
+                                    }
+                                    self.recovery.clear();
+                                    self.continue_with = self.after_save.take();
                                 }
 
                                 Err(e) => {
@@ -676,6 +1668,7 @@ impl eframe::App for App {
                                 }
                             }
                         } else { error!("data has no save file - unable to save"); }
+                        self.after_save = None; // a failed save stops what was waiting on it
                         info!("SaveTo => Ready");
                         Some(Ready(RefCell::new(None)))
                     } else { None }
@@ -687,10 +1680,11 @@ impl eframe::App for App {
                             // process file
                             info!("selected file: {}", selected.to_string_lossy());
 
-                            match self.data.import_from_file(selected.as_path()) {
-                                Ok(()) => {
-                                    self.main_view = MainView::default();
-                                    info!("imported data from {}", selected.to_string_lossy());
+                            // nothing is imported until the preview is accepted
+                            match self.data.merge_preview(selected.as_path()) {
+                                Ok(preview) => {
+                                    info!("Import => ImportMerge");
+                                    Some(ImportMerge(RefCell::new(preview)))
                                 }
 
                                 Err(e) => {
@@ -699,11 +1693,15 @@ impl eframe::App for App {
                                     let message = format!("Unable to import data from [{file}]");
                                     self.message = Some(message);
                                     error!("Error on data import from [{}]: {}", selected.to_string_lossy(), e);
+                                    info!("Import => Ready");
+                                    Some(Ready(RefCell::new(None)))
                                 }
                             }
-                        } else { info!("no import file selected - ignoring"); }
-                        info!("Import => Ready");
-                        Some(Ready(RefCell::new(None)))
+                        } else {
+                            info!("no import file selected - ignoring");
+                            info!("Import => Ready");
+                            Some(Ready(RefCell::new(None)))
+                        }
                     } else { None }
                 }
 
@@ -732,6 +1730,152 @@ impl eframe::App for App {
                     } else { None }
                 }
 
+                ExportMarkdown(layout) => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            info!("selected file: {}", selected.to_string_lossy());
+
+                            match self.data.export_to_markdown(selected.as_path(), *layout) {
+                                Ok(()) => {
+                                    info!("exported markdown to {}", selected.to_string_lossy());
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to export Markdown to [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on markdown export to [{}]: {}", selected.to_string_lossy(), e);
+                                }
+                            }
+                        } else { info!("no markdown export selected - ignoring"); }
+                        info!("ExportMarkdown => Ready");
+                        Some(Ready(RefCell::new(None)))
+                    } else { None }
+                }
+
+                ExportVault => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank folder selected, indicating cancel
+                            info!("selected vault: {}", selected.to_string_lossy());
+
+                            match self.data.export_to_vault(selected.as_path()) {
+                                Ok(()) => {
+                                    info!("exported vault to {}", selected.to_string_lossy());
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no folder>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to export vault to [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on vault export to [{}]: {}", selected.to_string_lossy(), e);
+                                }
+                            }
+                        } else { info!("no vault export selected - ignoring"); }
+                        info!("ExportVault => Ready");
+                        Some(Ready(RefCell::new(None)))
+                    } else { None }
+                }
+
+                ExportHtml => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank folder selected, indicating cancel
+                            info!("selected folder: {}", selected.to_string_lossy());
+
+                            match self.data.export_to_html(selected.as_path()) {
+                                Ok(()) => {
+                                    info!("exported html to {}", selected.to_string_lossy());
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no folder>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to export HTML to [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on html export to [{}]: {}", selected.to_string_lossy(), e);
+                                }
+                            }
+                        } else { info!("no html export selected - ignoring"); }
+                        info!("ExportHtml => Ready");
+                        Some(Ready(RefCell::new(None)))
+                    } else { None }
+                }
+
+                ExportCsv(kind) => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            info!("selected file: {}", selected.to_string_lossy());
+
+                            match self.data.export_to_csv(selected.as_path(), *kind) {
+                                Ok(()) => {
+                                    info!("exported {kind} csv to {}", selected.to_string_lossy());
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to export CSV to [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on csv export to [{}]: {}", selected.to_string_lossy(), e);
+                                }
+                            }
+                        } else { info!("no csv export selected - ignoring"); }
+                        info!("ExportCsv => Ready");
+                        Some(Ready(RefCell::new(None)))
+                    } else { None }
+                }
+
+                ExportWeb(format) => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            info!("selected file: {}", selected.to_string_lossy());
+
+                            match self.data.export_faction_web(selected.as_path(), &self.web_filter, *format) {
+                                Ok(()) => {
+                                    info!("exported faction web to {}", selected.to_string_lossy());
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to export faction web to [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on faction web export to [{}]: {}", selected.to_string_lossy(), e);
+                                }
+                            }
+                        } else { info!("no faction web export selected - ignoring"); }
+                        info!("ExportWeb => Ready");
+                        Some(Ready(RefCell::new(None)))
+                    } else { None }
+                }
+
+                ImportCsv(kind) => {
+                    if let Some(selected) = self.child_windows.selected_file() {
+                        if !selected.as_os_str().is_empty() {  // checks for blank file selected, indicating cancel
+                            info!("selected file: {}", selected.to_string_lossy());
+
+                            match self.data.import_from_csv(selected.as_path(), *kind) {
+                                Ok((report, reverse)) => {
+                                    if report.changed() {
+                                        self.todo_undo.clear_done(); // as for any other change
+                                        self.todo_undo.complete(TodoKind::Change, reverse);
+                                        self.recovery.record(&self.data);
+                                    }
+                                    if !report.errors.is_empty() {
+                                        self.message = Some(report.to_string());
+                                    }
+                                    info!("imported {kind} csv from {}: {report}", selected.to_string_lossy());
+                                }
+
+                                Err(e) => {
+                                    let file = selected.file_name().map_or(OsStr::new("<no file>").to_string_lossy(), |f| f.to_string_lossy());
+                                    let message = format!("Unable to import CSV from [{file}]");
+                                    self.message = Some(message);
+                                    error!("Error on csv import from [{}]: {}", selected.to_string_lossy(), e);
+                                }
+                            }
+                        } else { info!("no csv import selected - ignoring"); }
+                        info!("ImportCsv => Ready");
+                        Some(Ready(RefCell::new(None)))
+                    } else { None }
+                }
+
 
             }
         }).inner {
@@ -740,6 +1884,10 @@ impl eframe::App for App {
 
         self.run_todo();
 
+        if let Some(pending) = self.continue_with.take() {
+            self.continue_pending(ctx, pending);
+        }
+
         self.child_windows.show_windows(ctx);
     }
 }
@@ -779,21 +1927,377 @@ impl App {
                     info!("New {item} requested");
                     new_request = ViewRequest::NewItem;
                 }
+                if self.main_view == MainView::Factions {
+                    let toggle = if self.show_faction_graph {
+                        fl!("app_show_table")
+                    } else {
+                        fl!("app_show_graph")
+                    };
+                    if ui.add(Button::new(toggle)).clicked() {
+                        info!("Faction graph toggled");
+                        new_request = ViewRequest::ToggleGraph;
+                    }
+                }
             });
         });
 
         new_request
     }
 
+    fn show_confirm_delete(&self, ui: &mut Ui, target: DataIndex) -> Option<EditResult> {
+        let mut result = None;
+        let name = self.data.data_index_name(target).unwrap_or_default();
+        let references = self.data.references_to(target);
+
+        ui.vertical(|ui| {
+            ui.add_space(UI_PADDING);
+            ui.label(
+                RichText::new(fl!(
+                    "delete_itm_name",
+                    itm = data_index_item_name(target),
+                    name = name
+                ))
+                .heading()
+                .strong(),
+            );
+            ui.add_space(UI_PADDING);
+
+            if references.is_empty() {
+                ui.label(fl!("delete_no_references"));
+            } else {
+                ui.label(fl!("delete_references"));
+                ui.add_space(UI_PADDING / 2.);
+                for reference in &references {
+                    ui.label(format!(
+                        "    {} {}: {}",
+                        data_index_item_name(reference.holder()),
+                        reference.holder_name(),
+                        reference.fields().join(", ")
+                    ));
+                }
+            }
+
+            ui.add_space(UI_PADDING * 2.);
+            ui.horizontal(|ui| {
+                if ui
+                    .button(RichText::new(fl!("delete_confirm")).color(ERROR_FOREGROUND))
+                    .clicked()
+                {
+                    info!("delete confirmed");
+                    result = Some(EditResult::Submit);
+                }
+                ui.add_space(UI_PADDING);
+                if ui.button(fl!("delete_cancel")).clicked() {
+                    result = Some(EditResult::Ignore);
+                }
+            });
+        });
+
+        result
+    }
+
+    fn show_confirm_unsaved(&self, ui: &mut Ui, pending: &PendingAction) -> Option<UnsavedChoice> {
+        let mut result = None;
+
+        ui.vertical(|ui| {
+            ui.add_space(UI_PADDING);
+            let heading = match pending {
+                PendingAction::Exit => fl!("unsaved_before_exit"),
+                PendingAction::Restart | PendingAction::NewDoskvol | PendingAction::NewFromPack => {
+                    fl!("unsaved_before_restart")
+                }
+                PendingAction::Load
+                | PendingAction::RestoreBackup
+                | PendingAction::OpenRecent(_) => fl!("unsaved_before_load"),
+            };
+            ui.label(RichText::new(heading).heading().strong());
+            ui.add_space(UI_PADDING);
+            ui.label(fl!("unsaved_lost"));
+
+            ui.add_space(UI_PADDING * 2.);
+            ui.horizontal(|ui| {
+                if ui.button(fl!("unsaved_save")).clicked() {
+                    result = Some(UnsavedChoice::Save);
+                }
+                ui.add_space(UI_PADDING);
+                if ui
+                    .button(RichText::new(fl!("unsaved_discard")).color(ERROR_FOREGROUND))
+                    .clicked()
+                {
+                    result = Some(UnsavedChoice::Discard);
+                }
+                ui.add_space(UI_PADDING);
+                if ui.button(fl!("unsaved_cancel")).clicked() {
+                    result = Some(UnsavedChoice::Cancel);
+                }
+            });
+        });
+
+        result
+    }
+
+    fn show_offer_recovery(
+        &self,
+        ui: &mut Ui,
+        recovered: &RecoveredCampaign,
+    ) -> Option<EditResult> {
+        let mut result = None;
+
+        ui.vertical(|ui| {
+            ui.add_space(UI_PADDING);
+            ui.label(RichText::new(fl!("recovery_heading")).heading().strong());
+            ui.add_space(UI_PADDING);
+            match recovered.origin().as_deref().and_then(|f| f.file_stem()) {
+                Some(name) => ui.label(fl!(
+                    "recovery_campaign_name",
+                    name = name.to_string_lossy().to_string()
+                )),
+                None => ui.label(fl!("recovery_campaign_unsaved")),
+            };
+
+            ui.add_space(UI_PADDING * 2.);
+            ui.horizontal(|ui| {
+                if ui.button(fl!("recovery_restore")).clicked() {
+                    info!("recovery accepted");
+                    result = Some(EditResult::Submit);
+                }
+                ui.add_space(UI_PADDING);
+                if ui.button(fl!("recovery_discard")).clicked() {
+                    info!("recovery declined");
+                    result = Some(EditResult::Ignore);
+                }
+            });
+        });
+
+        result
+    }
+
+    fn show_pack_chooser(&self, ui: &mut Ui, chooser: &RefCell<PackChooser>) -> Option<EditResult> {
+        let mut result = None;
+
+        ui.vertical(|ui| {
+            ui.add_space(UI_PADDING);
+            ui.label(RichText::new(fl!("pack_heading")).heading().strong());
+            ui.add_space(UI_PADDING);
+
+            chooser.borrow_mut().show_edit(ui);
+
+            ui.add_space(UI_PADDING * 2.);
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        chooser.borrow().selected().is_some(),
+                        Button::new(fl!("pack_start")),
+                    )
+                    .clicked()
+                {
+                    info!("pack chosen");
+                    result = Some(EditResult::Submit);
+                }
+                ui.add_space(UI_PADDING);
+                if ui.button(fl!("pack_refresh")).clicked() {
+                    *chooser.borrow_mut() = PackChooser::new(self.project_directories.data_dir());
+                }
+                ui.add_space(UI_PADDING);
+                if ui.button(fl!("pack_cancel")).clicked() {
+                    result = Some(EditResult::Ignore);
+                }
+            });
+        });
+
+        result
+    }
+
+    fn show_campaign_merge(
+        &self,
+        ui: &mut Ui,
+        campaigns: &RefCell<CampaignMerge>,
+    ) -> Option<EditResult> {
+        let mut result = None;
+
+        ui.vertical(|ui| {
+            ui.add_space(UI_PADDING);
+            ui.label(
+                RichText::new(fl!("merge_campaigns_heading"))
+                    .heading()
+                    .strong(),
+            );
+            ui.add_space(UI_PADDING);
+
+            campaigns.borrow_mut().show_edit(ui);
+
+            ui.add_space(UI_PADDING * 2.);
+            ui.horizontal(|ui| {
+                if ui.button(fl!("merge_campaigns_save")).clicked() {
+                    info!("campaign merge accepted");
+                    result = Some(EditResult::Submit);
+                }
+                ui.add_space(UI_PADDING);
+                if ui.button(fl!("merge_campaigns_cancel")).clicked() {
+                    result = Some(EditResult::Ignore);
+                }
+            });
+        });
+
+        result
+    }
+
+    fn show_diff(&self, ui: &mut Ui, diff: &CampaignDiff) -> Option<EditResult> {
+        let mut result = None;
+
+        ui.vertical(|ui| {
+            ui.add_space(UI_PADDING);
+            ui.label(RichText::new(fl!("diff_heading")).heading().strong());
+            ui.add_space(UI_PADDING);
+
+            diff.show(ui);
+
+            ui.add_space(UI_PADDING * 2.);
+            ui.horizontal(|ui| {
+                if ui.button(fl!("diff_export")).clicked() {
+                    info!("changes export requested");
+                    result = Some(EditResult::Submit);
+                }
+                ui.add_space(UI_PADDING);
+                if ui.button(fl!("diff_close")).clicked() {
+                    result = Some(EditResult::Ignore);
+                }
+            });
+        });
+
+        result
+    }
+
+    fn show_merge_preview(
+        &self,
+        ui: &mut Ui,
+        preview: &RefCell<MergePreview>,
+    ) -> Option<EditResult> {
+        let mut result = None;
+
+        ui.vertical(|ui| {
+            ui.add_space(UI_PADDING);
+            ui.label(RichText::new(fl!("merge_heading")).heading().strong());
+            ui.add_space(UI_PADDING);
+
+            preview.borrow_mut().show_edit(ui);
+
+            let problem = preview.borrow().problem();
+            if let Some(problem) = &problem {
+                ui.add_space(UI_PADDING);
+                ui.label(RichText::new(problem).color(Color32::LIGHT_RED));
+            }
+
+            ui.add_space(UI_PADDING * 2.);
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(problem.is_none(), Button::new(fl!("merge_apply")))
+                    .clicked()
+                {
+                    info!("import accepted");
+                    result = Some(EditResult::Submit);
+                }
+                ui.add_space(UI_PADDING);
+                if ui.button(fl!("merge_cancel")).clicked() {
+                    result = Some(EditResult::Ignore);
+                }
+            });
+        });
+
+        result
+    }
+
+    fn show_faction_turn(&self, ui: &mut Ui, turn: &RefCell<FactionTurn>) -> Option<EditResult> {
+        let mut result = None;
+        let turn_log = self.data.turn_log();
+        let turn_number = turn_log.len() + 1;
+
+        ui.vertical(|ui| {
+            ui.add_space(UI_PADDING);
+            ui.label(
+                RichText::new(fl!("turn_heading", turn = turn_number))
+                    .heading()
+                    .strong(),
+            );
+            ui.add_space(UI_PADDING);
+
+            turn.borrow_mut().show_edit(ui);
+
+            ui.add_space(UI_PADDING * 2.);
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(!turn.borrow().is_empty(), Button::new(fl!("turn_apply")))
+                    .clicked()
+                {
+                    info!("faction turn accepted");
+                    result = Some(EditResult::Submit);
+                }
+                ui.add_space(UI_PADDING);
+                if ui.button(fl!("turn_reroll")).clicked() {
+                    info!("rerolling faction turn");
+                    *turn.borrow_mut() = FactionTurn::roll(&self.data, &mut rand::thread_rng());
+                }
+                ui.add_space(UI_PADDING);
+                if ui.button(fl!("turn_cancel")).clicked() {
+                    result = Some(EditResult::Ignore);
+                }
+            });
+
+            if !turn_log.is_empty() {
+                ui.add_space(UI_PADDING * 2.);
+                CollapsingHeader::new(fl!("turn_log_heading")).show(ui, |ui| {
+                    for entry in turn_log.iter().rev() {
+                        ui.label(RichText::new(fl!("turn_heading", turn = entry.turn())).strong());
+                        for turn_result in entry.results() {
+                            ui.label(format!("    {}", turn_result.summary()));
+                        }
+                    }
+                });
+            }
+        });
+
+        result
+    }
+
+    /// Which factions go into an export of the faction web
+    fn show_web_filter(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label(fl!("web_min_tier"));
+            ComboBox::from_id_salt("web_min_tier")
+                .selected_text(self.web_filter.min_tier.to_string())
+                .show_ui(ui, |ui| {
+                    for tier in (0..=usize::from(Tier::Tier5)).map(Tier::from) {
+                        ui.selectable_value(&mut self.web_filter.min_tier, tier, tier.to_string());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.label(fl!("web_turf_in"));
+            let any = fl!("web_any_district");
+            ComboBox::from_id_salt("web_turf_in")
+                .selected_text(self.web_filter.turf_in.clone().unwrap_or(any.clone()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.web_filter.turf_in, None, any);
+                    for district in self.data.district_list().names_sorted() {
+                        ui.selectable_value(
+                            &mut self.web_filter.turf_in,
+                            Some(district.clone()),
+                            district,
+                        );
+                    }
+                });
+        });
+    }
+
     fn run_todo(&mut self) {
-        if let Some(mut todo) = self.todo_undo.todo() {
-            info!("carrying out todo");
+        if let Some((kind, mut todo)) = self.todo_undo.todo() {
+            info!("carrying out todo ({kind:?})");
             let result = self.data.do_action(&mut todo);
             match result {
-                Ok(undo) => {
+                Ok(reverse) => {
                     info!("todo complete");
-                    self.todo_undo.add_undo(undo);
-                    // self.todo_undo.add_done(todo);  // todo: not sure this is what we need; is done for undo actions?
+                    self.todo_undo.complete(kind, reverse);
+                    self.recovery.record(&self.data);
                 }
 
                 Err(err) => {
@@ -814,6 +2318,7 @@ enum ViewRequest {
     None,
     NewView(MainView),
     NewItem, // uses current view
+    ToggleGraph,
 }
 
 // ===========================
@@ -824,6 +2329,29 @@ enum ViewRequest {
 pub enum EditResult {
     Submit,
     Ignore,
+    Delete,
+}
+
+// ===========================
+// Unsaved changes
+
+/// Something which would throw away unsaved changes, waiting for them to be saved or discarded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PendingAction {
+    Exit,
+    Restart,
+    NewDoskvol,
+    NewFromPack,
+    Load,
+    RestoreBackup,
+    OpenRecent(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnsavedChoice {
+    Save,
+    Discard,
+    Cancel,
 }
 
 // ===========================
@@ -836,13 +2364,38 @@ enum AppStatus {
     Starting,
     Ready(RefCell<Option<usize>>),
     ShowEditDistrict(Option<DistrictRef>, RefCell<District>),
-    ShowEditPerson(Option<PersonRef>, RefCell<Person>),
-    ShowEditFaction(Option<FactionRef>, RefCell<Faction>),
+    ShowEditPerson(Option<PersonRef>, RefCell<Person>, RefCell<MyRelationships>),
+    ShowEditFaction(
+        Option<FactionRef>,
+        RefCell<Faction>,
+        RefCell<MyRelationships>,
+    ),
+    ShowEditClock(Option<CampaignClockRef>, RefCell<CampaignClock>),
+    ConfirmDelete(DataIndex),
+    FactionTurn(RefCell<FactionTurn>),
+    OfferRecovery(RecoveredCampaign),
+    ChoosePack(RefCell<PackChooser>),
+    ConfirmUnsaved(PendingAction),
     Load,
+    RestoreBackup,
+    UpdateFromVault,
+    Compare,
+    ShowDiff(CampaignDiff),
+    ExportDiff(CampaignDiff),
+    MergeWith,
+    MergeCampaigns(RefCell<CampaignMerge>),
+    SaveMerged(CampaignMerge),
     SaveTo, // No file dialog, use existing save file name
     SaveAs, // use file dialog to get file name
     Import,
+    ImportMerge(RefCell<MergePreview>),
     Export,
+    ExportMarkdown(MarkdownLayout),
+    ExportVault,
+    ExportHtml,
+    ExportCsv(ItemKind),
+    ImportCsv(ItemKind),
+    ExportWeb(WebFormat),
 }
 
 impl Display for AppStatus {
@@ -879,59 +2432,60 @@ impl Display for AppStatus {
                         fl!("app_edit_itm", itm = item)
                     }
                 }
+                ShowEditClock(ind, ..) => {
+                    let item = fl!("main_item_clock");
+                    if ind.is_none() {
+                        fl!("app_create_itm", itm = item)
+                    } else {
+                        fl!("app_edit_itm", itm = item)
+                    }
+                }
+                ConfirmDelete(target) => {
+                    fl!("app_delete_itm", itm = data_index_item_name(*target))
+                }
+                FactionTurn(..) => fl!("app_faction_turn"),
+                OfferRecovery(..) => fl!("app_recovering"),
+                ChoosePack(..) => fl!("app_choosing_pack"),
+                ConfirmUnsaved(..) => fl!("app_unsaved"),
                 Load => fl!("app_loading"),
+                RestoreBackup => fl!("app_restoring"),
+                UpdateFromVault => fl!("app_importing"),
+                Compare => fl!("app_comparing"),
+                ShowDiff(..) => fl!("app_comparing"),
+                ExportDiff(..) => fl!("app_exporting"),
+                MergeWith => fl!("app_merging"),
+                MergeCampaigns(..) => fl!("app_merging"),
+                SaveMerged(..) => fl!("app_saving"),
                 SaveAs => fl!("app_saving"),
                 SaveTo => fl!("app_saving"),
                 Import => fl!("app_importing"),
+                ImportMerge(..) => fl!("app_importing"),
                 Export => fl!("app_exporting"),
+                ExportMarkdown(..) => fl!("app_exporting"),
+                ExportVault => fl!("app_exporting"),
+                ExportHtml => fl!("app_exporting"),
+                ExportCsv(..) => fl!("app_exporting"),
+                ExportWeb(..) => fl!("app_exporting"),
+                ImportCsv(..) => fl!("app_importing"),
             }
         )
     }
 }
 
 // ===========================
-// MainView
-
-#[allow(dead_code)]
-#[derive(Debug, Default, Clone, Copy, Sequence, PartialEq, Eq)]
-pub enum MainView {
-    #[default]
-    Factions,
-    Persons,
-    Districts,
-}
-
-impl MainView {
-    pub fn item_name(&self) -> String {
-        use MainView::*;
+// Additional functions
 
-        match self {
-            Factions => fl!("main_item_faction"),
-            Persons => fl!("main_item_person"),
-            Districts => fl!("main_item_district"),
-        }
+/// The (localized) name of the type of item the index refers to
+fn data_index_item_name(index: DataIndex) -> String {
+    match index {
+        DataIndex::DistrictIndex(_) => fl!("main_item_district"),
+        DataIndex::PersonIndex(_) => fl!("main_item_person"),
+        DataIndex::FactionIndex(_) => fl!("main_item_faction"),
+        DataIndex::ClockIndex(_) => fl!("main_item_clock"),
+        DataIndex::Nothing => String::new(),
     }
 }
 
-impl Display for MainView {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        use MainView::*;
-
-        write!(
-            f,
-            "{}",
-            match self {
-                Factions => fl!("main_factions"),
-                Persons => fl!("main_persons"),
-                Districts => fl!("main_districts"),
-            }
-        )
-    }
-}
-
-// ===========================
-// Additional functions
-
 fn configure_fonts(ctx: &CreationContext, zoom: f32) {
     let mut fonts = FontDefinitions::default();
     fonts.font_data.insert(
@@ -967,192 +2521,3 @@ fn configure_fonts(ctx: &CreationContext, zoom: f32) {
 
     ctx.egui_ctx.set_zoom_factor(zoom);
 }
-
-// ---------------------------
-// Load and Save to POT files
-const SAVE_FILE_ID: &[u8] = &[0x2b, 0x4a]; // magic number - is this endian neutral?
-
-// used in Settings
-pub fn save_to_pot<T>(file_path: &Path, data: &T) -> anyhow::Result<()>
-where
-    T: Serialize,
-{
-    use anyhow::Context;
-
-    if let Some(dir_path) = file_path.parent() {
-        create_dir_all(dir_path)?
-    }
-
-    if file_path.exists() {
-        fs::remove_file(file_path)?;
-    }
-
-    let file_handler = OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(file_path)
-        .with_context(|| {
-            format!(
-                "Failed to create save file [{}]",
-                file_path.to_string_lossy()
-            )
-        })?;
-
-    let mut buf = pot::to_vec(data)?;
-    buf.splice(0..0, SAVE_FILE_ID.iter().cloned());
-    let mut buf_writer = BufWriter::new(&file_handler);
-    buf_writer.write_all(buf.as_slice())?;
-    buf_writer.flush()?;
-    Ok(())
-}
-
-// used in Settings
-pub fn load_from_pot<T>(file_path: &Path) -> anyhow::Result<T>
-where
-    T: DeserializeOwned,
-{
-    use anyhow::Context;
-
-    let file_handler = OpenOptions::new()
-        .read(true)
-        .open(file_path)
-        .with_context(|| format!("Failed to open save file [{}]", file_path.to_string_lossy()))?;
-    let mut buf_reader = BufReader::new(&file_handler);
-    let mut check_id = [0u8, 0u8];
-    buf_reader.read_exact(&mut check_id)?;
-    if check_id != SAVE_FILE_ID {
-        return Err(anyhow!(
-            "File [{}] does not have expected SAVE_FILE_ID",
-            file_path.to_string_lossy()
-        ));
-    }
-    let data: T = pot::from_reader(buf_reader)?;
-    Ok(data)
-}
-
-pub fn save_to_save(
-    file_path: &Path,
-    save_version: u16,
-    mut buffer: Vec<u8>,
-) -> anyhow::Result<()> {
-    use anyhow::Context;
-
-    if let Some(dir_path) = file_path.parent() {
-        create_dir_all(dir_path)?
-    }
-
-    if file_path.exists() {
-        fs::remove_file(file_path)?;
-    }
-
-    let file_handler = OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(file_path)
-        .with_context(|| {
-            format!(
-                "Failed to create save file [{}]",
-                file_path.to_string_lossy()
-            )
-        })?;
-    let Ok((file_id, excess)) = unaligned::U16Le::from_bytes(SAVE_FILE_ID) else {
-        return Err(anyhow!("unable to convert header bytes"));
-    };
-    assert!(excess.is_empty());
-    let save_header = SaveFileHeader {
-        file_id: *file_id,
-        save_version: save_version.into(),
-    };
-    buffer.splice(0..0, save_header.as_bytes().iter().cloned());
-    let mut buf_writer = BufWriter::new(&file_handler);
-    buf_writer.write_all(buffer.as_slice())?;
-    buf_writer.flush()?;
-
-    Ok(())
-}
-
-pub fn load_from_save(file_path: &Path) -> anyhow::Result<(u16, BufReader<File>)> {
-    use anyhow::Context;
-
-    let file_handle = OpenOptions::new()
-        .read(true)
-        .open(file_path)
-        .with_context(|| format!("Failed to open save file [{}]", file_path.to_string_lossy()))?;
-
-    let mut buf_reader = BufReader::new(file_handle);
-    let mut check_header = [0u8, 0u8, 0u8, 0u8];
-    buf_reader.read_exact(&mut check_header)?;
-    let Ok((header, excess)) = SaveFileHeader::from_bytes(&check_header) else {
-        return Err(anyhow!("unable to convert header bytes"));
-    };
-    if header.file_id.as_bytes() != SAVE_FILE_ID {
-        return Err(anyhow!("File does not have expected SAVE_FILE_ID")); // file name? pass up?
-    }
-    assert!(excess.is_empty());
-    let save_version = header.save_version.into();
-    Ok((save_version, buf_reader))
-}
-
-// ------
-#[derive(Debug, BytesCast)]
-#[repr(C)]
-struct SaveFileHeader {
-    file_id: unaligned::U16Le,
-    save_version: unaligned::U16Le,
-}
-
-// ---------------------------
-// Load and Save to JSON files
-
-#[allow(dead_code)]
-pub fn save_to_json<T>(file_path: &Path, data: &T) -> anyhow::Result<()>
-where
-    T: Serialize,
-{
-    use anyhow::Context;
-
-    if let Some(dir_path) = file_path.parent() {
-        create_dir_all(dir_path)?
-    }
-
-    if file_path.exists() {
-        fs::remove_file(file_path)?;
-    }
-
-    let file_handler = OpenOptions::new()
-        .write(true)
-        .create_new(true)
-        .open(file_path)
-        .with_context(|| {
-            format!(
-                "Failed to create export file [{}]",
-                file_path.to_string_lossy()
-            )
-        })?;
-
-    let mut buf_writer = BufWriter::new(&file_handler);
-    serde_json::to_writer(&mut buf_writer, data)?;
-    buf_writer.flush()?;
-    Ok(())
-}
-
-#[allow(dead_code)]
-pub fn load_from_json<T>(file_path: &Path) -> anyhow::Result<T>
-where
-    T: DeserializeOwned,
-{
-    use anyhow::Context;
-
-    let file_handler = OpenOptions::new()
-        .read(true)
-        .open(file_path)
-        .with_context(|| {
-            format!(
-                "Failed to open import file [{}]",
-                file_path.to_string_lossy()
-            )
-        })?;
-    let buf_reader = BufReader::new(&file_handler);
-    let data: T = serde_json::from_reader(buf_reader)?;
-    Ok(data)
-}
@@ -1,20 +1,42 @@
 use std::{
     borrow::Borrow,
+    collections::{BTreeMap, BTreeSet},
     path::{Path, PathBuf},
 };
 
 use anyhow::{Ok, Result, anyhow};
-use log::{debug, error};
+use log::{debug, error, info};
 use serde::{Deserialize, Serialize};
 
 use crate::{
     action::{Action, ActionNode},
-    app::{MainView, load_from_json, load_from_save, save_to_json, save_to_save},
-    app_display::DisplayTable,
-    district::{District, DistrictStore},
-    faction::{Faction, FactionStore},
-    managed_list::{DistrictRef, FactionRef, ManagedList, Named, PersonRef},
-    person::{Person, PersonStore1, PersonStore2},
+    backup::make_backup,
+    campaign_clock::{CampaignClock, CampaignClockStore, ClockState},
+    campaign_diff::CampaignDiff,
+    campaign_merge::CampaignMerge,
+    csv_file::{self, CsvReport},
+    dataset,
+    display::{DisplayTable, MainView},
+    district::{District, DistrictStore, DistrictStore3},
+    entity_id::{EntityId, NameIds},
+    export_format::ItemKind,
+    faction::{Faction, FactionStore, FactionStore3},
+    faction_turn::TurnLogEntry,
+    faction_web::{self, WebFilter, WebFormat},
+    html,
+    localize::fl,
+    managed_list::{
+        CampaignClockRef, DistrictRef, FactionRef, GenericRef, ManagedList, Named, PersonRef,
+    },
+    markdown::{self, MarkdownCampaign, MarkdownLayout},
+    merge::{ConflictChoice, MergePreview},
+    obsidian::{self, VaultItems},
+    persistence::{load_from_json, load_from_save, save_to_json, save_to_save},
+    person::{Person, PersonStore1, PersonStore2, PersonStore3},
+    relationship::{
+        ActorPair, Relationship, RelationshipStore, Relationships, can_have_relationships,
+    },
+    setting_pack,
 };
 
 pub const SAVE_EXTENSION: &str = "bfsav";
@@ -28,6 +50,10 @@ pub struct AppData {
     persons: ManagedList<Person>,
     districts: ManagedList<District>,
     factions: ManagedList<Faction>,
+    clocks: ManagedList<CampaignClock>,
+    relationships: Relationships,
+    turn_log: Vec<TurnLogEntry>,
+    changed: bool, // differs from the file it was loaded from, or saved to
 }
 
 #[allow(dead_code)]
@@ -46,67 +72,133 @@ impl AppData {
             match action {
                 DistrictAdd(district) => {
                     if let Some(district_ref) = self.districts.add(district) {
-                        return_node.push_back(DistrictRemove(district_ref));
+                        return_node.push_front(DistrictRemove(district_ref));
                     } // silently ignore if can't be added
                 }
 
                 DistrictRemove(district_ref) => {
                     if let Some(district) = self.districts.remove(district_ref) {
-                        return_node.push_back(DistrictAdd(district));
+                        return_node.push_front(DistrictRestore(district_ref.clone(), district));
                     } // silently ignore if this wasn't in the list when we removed it
                 }
 
+                DistrictRestore(district_ref, district) => {
+                    if self.districts.restore(district_ref, district.clone()) {
+                        return_node.push_front(DistrictRemove(district_ref.clone()));
+                    } // silently ignore if it could not be restored
+                }
+
                 DistrictReplace(district_ref, district) => {
                     if let Some(old_district) =
                         self.districts.replace(district_ref, district.clone())
                     {
-                        return_node.push_back(DistrictReplace(district_ref.clone(), old_district));
+                        return_node.push_front(DistrictReplace(district_ref.clone(), old_district));
                     } // silently ignore if no replacement was possible?
                 }
 
                 PersonAdd(person) => {
                     if let Some(person_ref) = self.persons.add(person) {
-                        return_node.push_back(PersonRemove(person_ref));
+                        return_node.push_front(PersonRemove(person_ref));
                     } // silently ignore if can't be added
                 }
 
                 PersonRemove(person_ref) => {
                     if let Some(person) = self.persons.remove(person_ref) {
-                        return_node.push_back(PersonAdd(person));
+                        return_node.push_front(PersonRestore(person_ref.clone(), person));
                     } // silently ignore if this wasn't in the list when we removed it
                 }
 
+                PersonRestore(person_ref, person) => {
+                    if self.persons.restore(person_ref, person.clone()) {
+                        return_node.push_front(PersonRemove(person_ref.clone()));
+                    } // silently ignore if it could not be restored
+                }
+
                 PersonReplace(person_ref, person) => {
                     if let Some(old_person) = self.persons.replace(person_ref, person.clone()) {
-                        return_node.push_back(PersonReplace(person_ref.clone(), old_person));
+                        return_node.push_front(PersonReplace(person_ref.clone(), old_person));
                     } // silently ignore if no replacement was possible?
                 }
 
                 FactionAdd(faction) => {
                     if let Some(faction_ref) = self.factions.add(faction) {
-                        return_node.push_back(FactionRemove(faction_ref));
+                        return_node.push_front(FactionRemove(faction_ref));
                     } // silently ignore if can't be added
                 }
 
                 FactionRemove(faction_ref) => {
                     if let Some(faction) = self.factions.remove(faction_ref) {
-                        return_node.push_back(FactionAdd(faction));
+                        return_node.push_front(FactionRestore(faction_ref.clone(), faction));
                     } // silently ignore if this wasn't in the list when we removed it
                 }
 
+                FactionRestore(faction_ref, faction) => {
+                    if self.factions.restore(faction_ref, faction.clone()) {
+                        return_node.push_front(FactionRemove(faction_ref.clone()));
+                    } // silently ignore if it could not be restored
+                }
+
                 FactionReplace(faction_ref, faction) => {
                     if let Some(old_faction) = self.factions.replace(faction_ref, faction.clone()) {
-                        return_node.push_back(FactionReplace(faction_ref.clone(), old_faction));
+                        return_node.push_front(FactionReplace(faction_ref.clone(), old_faction));
+                    } // silently ignore if no replacement was possible?
+                }
+
+                ClockAdd(clock) => {
+                    if let Some(clock_ref) = self.clocks.add(clock) {
+                        return_node.push_front(ClockRemove(clock_ref));
+                    } // silently ignore if can't be added
+                }
+
+                ClockRemove(clock_ref) => {
+                    if let Some(clock) = self.clocks.remove(clock_ref) {
+                        return_node.push_front(ClockRestore(clock_ref.clone(), clock));
+                    } // silently ignore if this wasn't in the list when we removed it
+                }
+
+                ClockRestore(clock_ref, clock) => {
+                    if self.clocks.restore(clock_ref, clock.clone()) {
+                        return_node.push_front(ClockRemove(clock_ref.clone()));
+                    } // silently ignore if it could not be restored
+                }
+
+                ClockReplace(clock_ref, clock) => {
+                    if let Some(old_clock) = self.clocks.replace(clock_ref, clock.clone()) {
+                        return_node.push_front(ClockReplace(clock_ref.clone(), old_clock));
                     } // silently ignore if no replacement was possible?
                 }
+
+                TurnLogPush(entry) => {
+                    self.turn_log.push(entry.clone());
+                    return_node.push_front(TurnLogPop);
+                }
+
+                TurnLogPop => {
+                    if let Some(entry) = self.turn_log.pop() {
+                        return_node.push_front(TurnLogPush(entry));
+                    } // silently ignore if the log is empty
+                }
+
+                RelationshipSet(pair, relationship) => {
+                    let old_relationship = self.relationships.set(*pair, relationship.clone());
+                    if old_relationship != *relationship {
+                        return_node.push_front(RelationshipSet(*pair, old_relationship));
+                    }
+                }
             }
         }
-        // fill return node
+        if !return_node.is_empty() {
+            self.changed = true;
+        }
+        // the reverse is filled from the front, so it undoes the last action first
         Ok(return_node)
     }
 
     pub fn no_items(&self) -> bool {
-        self.districts.no_items() && self.persons.no_items() && self.factions.no_items()
+        self.districts.no_items()
+            && self.persons.no_items()
+            && self.factions.no_items()
+            && self.clocks.no_items()
     }
 
     // todo: precalc and cache this?
@@ -118,6 +210,16 @@ impl AppData {
         self.loaded_from = maybe_file;
     }
 
+    /// Whether there are changes which have not been saved
+    pub fn is_changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Marks the data as unsaved, when it did not come from its own file
+    pub fn mark_changed(&mut self) {
+        self.changed = true;
+    }
+
     pub fn get_loaded_from(&self) -> Option<PathBuf> {
         self.loaded_from.clone()
     }
@@ -132,6 +234,11 @@ impl AppData {
         DisplayTable::from(&self.factions)
     }
 
+    // todo: precalc and cache this?
+    pub fn clocks_display_table(&self) -> DisplayTable {
+        DisplayTable::from(&self.clocks)
+    }
+
     pub fn person_list(&self) -> &ManagedList<Person> {
         &self.persons
     }
@@ -144,6 +251,38 @@ impl AppData {
         &self.factions
     }
 
+    pub fn clock_list(&self) -> &ManagedList<CampaignClock> {
+        &self.clocks
+    }
+
+    pub fn turn_log(&self) -> &[TurnLogEntry] {
+        &self.turn_log
+    }
+
+    pub fn relationships(&self) -> &Relationships {
+        &self.relationships
+    }
+
+    /// Every item which can have relationships, with a name showing what kind of item it is
+    pub fn actor_names(&self) -> Vec<(DataIndex, String)> {
+        let faction_item = fl!("main_item_faction");
+        let person_item = fl!("main_item_person");
+        let mut actors: Vec<(DataIndex, String)> = self
+            .factions
+            .item_ref_list()
+            .iter()
+            .map(|(r, f)| (r.data_index(), format!("{} ({faction_item})", f.name())))
+            .chain(
+                self.persons
+                    .item_ref_list()
+                    .iter()
+                    .map(|(r, p)| (r.data_index(), format!("{} ({person_item})", p.name()))),
+            )
+            .collect();
+        actors.sort_by(|a, b| a.1.cmp(&b.1));
+        actors
+    }
+
     pub fn persons_names(&self) -> Vec<String> {
         self.persons.names_sorted()
     }
@@ -168,6 +307,10 @@ impl AppData {
         self.factions.set_sorting(index);
     }
 
+    pub fn set_clocks_sort(&mut self, index: usize) {
+        self.clocks.set_sorting(index);
+    }
+
     pub fn find_district(&self, name: &str) -> Option<DistrictRef> {
         self.districts.find(name)
     }
@@ -192,11 +335,66 @@ impl AppData {
         self.factions.fetch(index).cloned()
     }
 
+    pub fn find_clock(&self, name: &str) -> Option<CampaignClockRef> {
+        self.clocks.find(name)
+    }
+
+    pub fn clone_clock(&self, index: &CampaignClockRef) -> Option<CampaignClock> {
+        self.clocks.fetch(index).cloned()
+    }
+
+    /// Creates the node which replaces a clock, and if that finishes it, starts the clock
+    /// which follows it and stops any clock it was racing
+    pub fn clock_replace_actions(
+        &self,
+        clock_ref: &CampaignClockRef,
+        clock: CampaignClock,
+    ) -> ActionNode {
+        let was_finished = self
+            .clocks
+            .fetch(clock_ref)
+            .is_some_and(|c| c.is_finished());
+        let mut node = ActionNode::new();
+
+        if clock.is_finished() && !was_finished {
+            if let Some(next_ref) = clock.next()
+                && let Some(mut next) = self.clone_clock(next_ref)
+                && next.state() == ClockState::Waiting
+            {
+                info!("{} finished, starting {}", clock.name(), next.name());
+                next.set_state(ClockState::Running);
+                node.push_back(Action::ClockReplace(next_ref.clone(), next));
+            }
+            // a race is lost by the clock this one names, and by any clock which names this one
+            let finishing = clock_ref.data_index();
+            for (rival_ref, rival) in self.clocks.item_ref_list() {
+                let is_rival = clock
+                    .rival()
+                    .is_some_and(|r| r.is_data_index(rival_ref.data_index()))
+                    || rival.rival().is_some_and(|r| r.is_data_index(finishing));
+                if is_rival
+                    && !rival_ref.is_data_index(finishing)
+                    && !rival.is_finished()
+                    && rival.state() == ClockState::Running
+                {
+                    info!("{} finished, beating {}", clock.name(), rival.name());
+                    let mut rival = rival.clone();
+                    rival.set_state(ClockState::Stopped);
+                    node.push_back(Action::ClockReplace(rival_ref, rival));
+                }
+            }
+        }
+
+        node.push_front(Action::ClockReplace(clock_ref.clone(), clock));
+        node
+    }
+
     pub fn view_size(&self, view: MainView) -> usize {
         match view {
             MainView::Factions => self.factions.item_count(),
             MainView::Persons => self.persons.item_count(),
             MainView::Districts => self.districts.item_count(),
+            MainView::Clocks => self.clocks.item_count(),
         }
     }
 
@@ -206,17 +404,175 @@ impl AppData {
             DataIndex::DistrictIndex(i) => self.district_list().fetch_with_index(i).is_some(),
             DataIndex::PersonIndex(i) => self.person_list().fetch_with_index(i).is_some(),
             DataIndex::FactionIndex(i) => self.faction_list().fetch_with_index(i).is_some(),
+            DataIndex::ClockIndex(i) => self.clock_list().fetch_with_index(i).is_some(),
+        }
+    }
+
+    /// Returns the id of the item at the data index, if it is present
+    pub fn data_index_id(&self, index: DataIndex) -> Option<EntityId> {
+        match index {
+            DataIndex::Nothing => None,
+            DataIndex::DistrictIndex(_) => self.districts.find_data_index(index)?.id(),
+            DataIndex::PersonIndex(_) => self.persons.find_data_index(index)?.id(),
+            DataIndex::FactionIndex(_) => self.factions.find_data_index(index)?.id(),
+            DataIndex::ClockIndex(_) => self.clocks.find_data_index(index)?.id(),
+        }
+    }
+
+    /// Returns the name of the item at the data index, if it is present
+    pub fn data_index_name(&self, index: DataIndex) -> Option<String> {
+        match index {
+            DataIndex::Nothing => None,
+            DataIndex::DistrictIndex(_) => self.districts.find_data_index(index)?.name(),
+            DataIndex::PersonIndex(_) => self.persons.find_data_index(index)?.name(),
+            DataIndex::FactionIndex(_) => self.factions.find_data_index(index)?.name(),
+            DataIndex::ClockIndex(_) => self.clocks.find_data_index(index)?.name(),
+        }
+    }
+
+    /// Lists every item which refers to the target, along with the fields holding the reference
+    pub fn references_to(&self, target: DataIndex) -> Vec<ItemReference> {
+        let mut references = Vec::new();
+
+        for (holder, faction) in self.factions.item_ref_list() {
+            let fields = faction.reference_fields(target);
+            if !fields.is_empty() && !holder.is_data_index(target) {
+                references.push(ItemReference::new(
+                    holder.data_index(),
+                    faction.name(),
+                    fields,
+                ));
+            }
+        }
+
+        for (holder, district) in self.districts.item_ref_list() {
+            let fields = district.reference_fields(target);
+            if !fields.is_empty() && !holder.is_data_index(target) {
+                references.push(ItemReference::new(
+                    holder.data_index(),
+                    district.name(),
+                    fields,
+                ));
+            }
+        }
+
+        for (holder, person) in self.persons.item_ref_list() {
+            let fields = person.reference_fields(target);
+            if !fields.is_empty() && !holder.is_data_index(target) {
+                references.push(ItemReference::new(
+                    holder.data_index(),
+                    person.name(),
+                    fields,
+                ));
+            }
+        }
+
+        for (holder, clock) in self.clocks.item_ref_list() {
+            let fields = clock.reference_fields(target);
+            if !fields.is_empty() && !holder.is_data_index(target) {
+                references.push(ItemReference::new(
+                    holder.data_index(),
+                    clock.name(),
+                    fields,
+                ));
+            }
         }
+
+        let relationship_field = fl!("relationships_heading");
+        for pair in self.relationships.pairs_with(target) {
+            let Some(other) = pair.pair_with(target) else {
+                continue;
+            };
+            if let Some(reference) = references.iter_mut().find(|r| r.holder == other) {
+                reference.fields.push(relationship_field.clone());
+            } else if let Some(other_name) = self.data_index_name(other) {
+                references.push(ItemReference::new(
+                    other,
+                    &other_name,
+                    vec![relationship_field.clone()],
+                ));
+            }
+        }
+
+        references
     }
 
-    /// This saves all data to a save file
+    /// Creates the node which clears every reference to the target, and then removes it
+    pub fn remove_item_actions(&self, target: DataIndex) -> Option<ActionNode> {
+        let mut node = ActionNode::new();
+
+        for (holder, faction) in self.factions.item_ref_list() {
+            let mut faction = faction.clone();
+            if !holder.is_data_index(target) && faction.clear_references(target) {
+                node.push_back(Action::FactionReplace(holder, faction));
+            }
+        }
+
+        for (holder, district) in self.districts.item_ref_list() {
+            let mut district = district.clone();
+            if !holder.is_data_index(target) && district.clear_references(target) {
+                node.push_back(Action::DistrictReplace(holder, district));
+            }
+        }
+
+        for (holder, person) in self.persons.item_ref_list() {
+            let mut person = person.clone();
+            if !holder.is_data_index(target) && person.clear_references(target) {
+                node.push_back(Action::PersonReplace(holder, person));
+            }
+        }
+
+        for (holder, clock) in self.clocks.item_ref_list() {
+            let mut clock = clock.clone();
+            if !holder.is_data_index(target) && clock.clear_references(target) {
+                node.push_back(Action::ClockReplace(holder, clock));
+            }
+        }
+
+        for pair in self.relationships.pairs_with(target) {
+            node.push_back(Action::RelationshipSet(pair, None));
+        }
+
+        node.push_back(match target {
+            DataIndex::Nothing => return None,
+            DataIndex::DistrictIndex(_) => {
+                Action::DistrictRemove(self.districts.find_data_index(target)?)
+            }
+            DataIndex::PersonIndex(_) => {
+                Action::PersonRemove(self.persons.find_data_index(target)?)
+            }
+            DataIndex::FactionIndex(_) => {
+                Action::FactionRemove(self.factions.find_data_index(target)?)
+            }
+            DataIndex::ClockIndex(_) => Action::ClockRemove(self.clocks.find_data_index(target)?),
+        });
+
+        Some(node)
+    }
+
+    /// This saves all data to a save file, backing up the file it replaces
     pub fn save_to_file(&mut self, file_path: &Path) -> Result<()> {
-        save_data_to_file(file_path, self)
+        save_data_to_file(file_path, self)?;
+        self.changed = false;
+        Ok(())
+    }
+
+    /// The save version and contents of a save file, without writing it
+    pub fn save_snapshot(&self) -> Result<(u16, Vec<u8>)> {
+        let save_data: SaveData6 = self.into();
+        if save_data.validate() {
+            Ok((
+                save_data.save_version,
+                pot::to_vec::<SaveData6>(&save_data)?,
+            ))
+        } else {
+            Err(anyhow!("unable to validate save data - not saved"))
+        }
     }
 
     /// This exports all data to a JSON file
     pub fn export_to_file(&self, file_path: &Path) -> Result<()> {
-        let save_data: SaveData2 = self.into();
+        let save_data: SaveData6 = self.into();
         if !save_data.validate() {
             error!(
                 "unable to validate data to export ({}), version: {}",
@@ -232,20 +588,110 @@ impl AppData {
         save_to_json(&file_path.with_extension(JSON_EXTENSION), &save_data)
     }
 
+    /// The campaign's name in an export, from the file it was loaded from
+    fn export_title(&self) -> String {
+        self.loaded_from
+            .as_deref()
+            .and_then(Path::file_stem)
+            .map_or_else(markdown::default_title, |s| s.to_string_lossy().to_string())
+    }
+
+    /// This exports every faction, person and district as Markdown, with references as links
+    pub fn export_to_markdown(&self, path: &Path, layout: MarkdownLayout) -> Result<()> {
+        let save_data: SaveData2 = self.into();
+        let title = self.export_title();
+        let campaign = MarkdownCampaign {
+            title: &title,
+            persons: &save_data.persons,
+            districts: &save_data.districts,
+            factions: &save_data.factions,
+        };
+        markdown::export(&campaign, path, layout)
+    }
+
+    /// This exports a folder of linked pages, which can be browsed without the editor
+    pub fn export_to_html(&self, path: &Path) -> Result<()> {
+        html::export(self, &self.export_title(), path)
+    }
+
+    /// This exports the factions which pass the filter, with their allies, enemies, HQs and leaders,
+    /// as a Graphviz graph or as a picture
+    pub fn export_faction_web(
+        &self,
+        path: &Path,
+        filter: &WebFilter,
+        format: WebFormat,
+    ) -> Result<()> {
+        faction_web::export(self, &self.export_title(), filter, format, path)
+    }
+
+    /// This exports one kind of item as CSV, a row per item and a column per field
+    pub fn export_to_csv(&self, path: &Path, kind: ItemKind) -> Result<()> {
+        csv_file::export(&self.to_json_value()?, kind, path)
+    }
+
+    /// This creates or updates items of one kind from the rows of a CSV file, matched by name.
+    /// Rows which cannot be used are reported, and the rest are still imported, returning the
+    /// node which reverses the import as one step
+    pub fn import_from_csv(
+        &mut self,
+        path: &Path,
+        kind: ItemKind,
+    ) -> Result<(CsvReport, ActionNode)> {
+        let mut value = self.to_json_value()?;
+        let report = csv_file::import(&mut value, kind, path)?;
+        let reverse = if report.changed() {
+            self.apply_merge(value)?
+        } else {
+            ActionNode::new()
+        };
+        Ok((report, reverse))
+    }
+
+    /// This exports every faction, person and district as a note in an Obsidian vault
+    pub fn export_to_vault(&self, vault: &Path) -> Result<()> {
+        let save_data: SaveData2 = self.into();
+        obsidian::export(
+            &VaultItems {
+                persons: save_data.persons,
+                districts: save_data.districts,
+                factions: save_data.factions,
+            },
+            vault,
+        )
+    }
+
+    /// This updates the items named in the notes of an Obsidian vault, and adds any which are
+    /// new, resolving their wikilinks as names, as the older save files do. Ids, relationships,
+    /// campaign clocks and the turn log are kept, and the node which reverses it is returned
+    pub fn update_from_vault(&mut self, vault: &Path) -> Result<ActionNode> {
+        let items = obsidian::import(vault)?;
+        let save_data = SaveData2 {
+            save_schema: SAVE_SCHEMA.to_string(),
+            save_version: SAVE2_VERSION,
+            persons: items.persons,
+            districts: items.districts,
+            factions: items.factions,
+        };
+        let mut incoming = AppData::default();
+        incoming.load_data(save_data)?;
+
+        let mut preview = MergePreview::new(self.to_json_value()?, incoming.to_json_value()?);
+        for item in preview.items_mut() {
+            item.choice = ConflictChoice::TakeIncoming;
+        }
+        self.apply_merge(preview.merged()?)
+    }
+
     /// This adds the loaded data to the current data
     pub fn import_from_file(&mut self, file_path: &Path) -> Result<()> {
-        let import_data: SaveData2 = {
-            // try 2
-            match load_from_json::<SaveData2>(&file_path.with_extension(JSON_EXTENSION)) {
-                Result::Ok(save2) => save2,
-                Err(_) => {
-                    // try 1
-                    let save1: SaveData1 =
-                        load_from_json(&file_path.with_extension(JSON_EXTENSION))?;
-                    save1.into()
-                }
-            }
-        };
+        let value = load_from_json(&file_path.with_extension(JSON_EXTENSION))?;
+        self.import_from_json(value, &file_path.to_string_lossy())
+    }
+
+    /// This adds data in any version of the export format to the current data
+    fn import_from_json(&mut self, value: serde_json::Value, source: &str) -> Result<()> {
+        let import_data = save_data_from_json(value)?;
 
         debug!(
             "imported {} people, {} districts, {} factions",
@@ -255,47 +701,293 @@ impl AppData {
         );
         if !import_data.validate() {
             error!(
-                "unable to validate imported data ({}), version: {}",
-                file_path.to_string_lossy(),
+                "unable to validate imported data ({source}), version: {}",
                 import_data.save_version
             );
             return Err(anyhow!(
-                "unable to validate imported data ({}), version: {}",
-                file_path.to_string_lossy(),
+                "unable to validate imported data ({source}), version: {}",
                 import_data.save_version
             ));
         }
         self.load_data(import_data)
     }
 
+    /// This previews adding the data in the file to the current data, item by item
+    pub fn merge_preview(&self, file_path: &Path) -> Result<MergePreview> {
+        let mut incoming = AppData::default();
+        incoming.import_from_file(file_path)?;
+        Ok(MergePreview::new(
+            self.to_json_value()?,
+            incoming.to_json_value()?,
+        ))
+    }
+
+    /// This brings the current data up to the merged data, which keeps the ids of the current
+    /// items, returning the node which reverses it all as one step
+    pub fn apply_merge(&mut self, merged: serde_json::Value) -> Result<ActionNode> {
+        let save_data = save_data_from_json(merged)?;
+        if !save_data.validate() {
+            return Err(anyhow!(
+                "unable to validate merged data, version: {}",
+                save_data.save_version
+            ));
+        }
+
+        // new items are added bare first, so that every reference can be found
+        let mut add: ActionNode = save_data
+            .districts
+            .iter()
+            .filter(|d| self.districts.find_id(d.id).is_none())
+            .map(|d| Action::DistrictAdd(d.into()))
+            .chain(
+                save_data
+                    .persons
+                    .iter()
+                    .filter(|p| self.persons.find_id(p.id).is_none())
+                    .map(|p| Action::PersonAdd(p.into())),
+            )
+            .chain(
+                save_data
+                    .factions
+                    .iter()
+                    .filter(|f| self.factions.find_id(f.id).is_none())
+                    .map(|f| Action::FactionAdd(f.into())),
+            )
+            .chain(
+                save_data
+                    .clocks
+                    .iter()
+                    .filter(|c| self.clocks.find_id(c.id).is_none())
+                    .map(|c| Action::ClockAdd(c.into())),
+            )
+            .collect();
+        let mut reverse = self.do_action(&mut add)?;
+
+        let district = |id: &EntityId| self.districts.find_id(*id);
+        let person = |id: &EntityId| self.persons.find_id(*id);
+        let faction = |id: &EntityId| self.factions.find_id(*id);
+        let clock = |id: &EntityId| self.clocks.find_id(*id);
+
+        let mut replace = ActionNode::new();
+        for d in &save_data.districts {
+            let mut item: District = d.into();
+            item.set_notable(d.notable.iter().filter_map(person).collect());
+            if let Some(item_ref) = district(&d.id)
+                && self.districts.fetch(&item_ref) != Some(&item)
+            {
+                replace.push_back(Action::DistrictReplace(item_ref, item));
+            }
+        }
+        for p in &save_data.persons {
+            let mut item: Person = p.into();
+            item.set_found_in(p.found_in.as_ref().and_then(district));
+            if let Some(item_ref) = person(&p.id)
+                && self.persons.fetch(&item_ref) != Some(&item)
+            {
+                replace.push_back(Action::PersonReplace(item_ref, item));
+            }
+        }
+        for f in &save_data.factions {
+            let mut item: Faction = f.into();
+            item.set_hq(f.hq.as_ref().and_then(district));
+            item.set_turf(f.turf.iter().filter_map(district).collect());
+            item.set_leader(f.leader.as_ref().and_then(person));
+            item.set_notable(f.notable.iter().filter_map(person).collect());
+            item.set_allies(f.allies.iter().filter_map(faction).collect());
+            item.set_enemies(f.enemies.iter().filter_map(faction).collect());
+            if let Some(item_ref) = faction(&f.id)
+                && self.factions.fetch(&item_ref) != Some(&item)
+            {
+                replace.push_back(Action::FactionReplace(item_ref, item));
+            }
+        }
+        for c in &save_data.clocks {
+            let mut item: CampaignClock = c.into();
+            item.set_links(
+                c.factions.iter().filter_map(faction).collect(),
+                c.persons.iter().filter_map(person).collect(),
+                c.districts.iter().filter_map(district).collect(),
+            );
+            item.set_next(c.next.as_ref().and_then(clock));
+            item.set_rival(c.rival.as_ref().and_then(clock));
+            if let Some(item_ref) = clock(&c.id)
+                && self.clocks.fetch(&item_ref) != Some(&item)
+            {
+                replace.push_back(Action::ClockReplace(item_ref, item));
+            }
+        }
+
+        let actor_index = |id: &EntityId| {
+            faction(id)
+                .map(|r| r.data_index())
+                .or_else(|| person(id).map(|r| r.data_index()))
+        };
+        for r in &save_data.relationships {
+            if let (Some(first), Some(second)) = (actor_index(&r.first), actor_index(&r.second))
+                && first != second
+            {
+                let pair = ActorPair::new(first, second);
+                let relationship: Relationship = r.into();
+                if self.relationships.get(&pair) != Some(&relationship) {
+                    replace.push_back(Action::RelationshipSet(pair, Some(relationship)));
+                }
+            }
+        }
+        replace.extend(
+            save_data
+                .turn_log
+                .into_iter()
+                .skip(self.turn_log.len())
+                .map(Action::TurnLogPush),
+        );
+
+        for action in self.do_action(&mut replace)?.into_iter().rev() {
+            reverse.push_front(action);
+        }
+        Ok(reverse)
+    }
+
+    /// This prepares to merge the campaign in the file into this one, matching items by name
+    pub fn merge_with_file(&self, file_path: &Path) -> Result<CampaignMerge> {
+        let other = AppData::read_from_file(file_path)?;
+        let other_title = file_path
+            .file_stem()
+            .map_or_else(|| file_path.to_string_lossy(), |n| n.to_string_lossy());
+        Ok(CampaignMerge::new(
+            &self.export_title(),
+            self.to_json_value()?,
+            &other_title,
+            other.to_json_value()?,
+        ))
+    }
+
+    /// This creates a new campaign from one of the datasets bundled with the app
+    pub fn from_dataset(name: &str) -> Result<AppData> {
+        AppData::new_from_json(dataset::load(name)?, name)
+    }
+
+    /// This creates a new campaign from a setting pack, a folder or zip holding an export
+    pub fn from_pack(path: &Path) -> Result<AppData> {
+        AppData::new_from_json(setting_pack::read_export(path)?, &path.to_string_lossy())
+    }
+
+    fn new_from_json(value: serde_json::Value, source: &str) -> Result<AppData> {
+        let mut data = AppData::default();
+        data.import_from_json(value, source)?;
+        data.changed = false; // nothing has been done to it yet
+        Ok(data)
+    }
+
+    /// The data in the export format, for tools which work on it as JSON
+    pub fn to_json_value(&self) -> Result<serde_json::Value> {
+        let save_data: SaveData6 = self.into();
+        Ok(serde_json::to_value(save_data)?)
+    }
+
+    /// Creates data from any version of the export format, failing if it does not validate,
+    /// or if any of its items could not be loaded
+    pub fn from_json_value(value: serde_json::Value) -> Result<AppData> {
+        let save_data = save_data_from_json(value)?;
+        if !save_data.validate() {
+            return Err(anyhow!(
+                "unable to validate data, version: {}",
+                save_data.save_version
+            ));
+        }
+        let expected = [
+            ("districts", MainView::Districts, save_data.districts.len()),
+            ("persons", MainView::Persons, save_data.persons.len()),
+            ("factions", MainView::Factions, save_data.factions.len()),
+            ("clocks", MainView::Clocks, save_data.clocks.len()),
+        ];
+        let data: AppData = save_data.into();
+        // an item is skipped when another of its kind already has its name
+        for (key, view, count) in expected {
+            let loaded = data.view_size(view);
+            if loaded != count {
+                return Err(anyhow!(
+                    "only {loaded} of {count} {key} could be loaded, as names must be unique"
+                ));
+            }
+        }
+        Ok(data)
+    }
+
     /// This creates a new AppData after loading data from the file path
     pub fn load_from_file(file_path: &Path) -> Result<AppData> {
         save_data_from_file(file_path) // .with_extension(DATA_EXTENSION)
     }
 
-    fn load_data(&mut self, save_data: impl Into<SaveData2>) -> Result<()> {
+    /// This reads a save file, or a file in the export format if it ends in .json
+    pub fn read_from_file(file_path: &Path) -> Result<AppData> {
+        if file_path.extension().is_some_and(|e| e == JSON_EXTENSION) {
+            let mut data = AppData::default();
+            data.import_from_file(file_path)?;
+            Ok(data)
+        } else {
+            AppData::load_from_file(file_path)
+        }
+    }
+
+    /// This lists what would be lost in loading the file, such as references to items
+    /// which are not in it, or items whose names are already taken
+    pub fn check_file(file_path: &Path) -> Result<Vec<String>> {
+        let save_data = if file_path.extension().is_some_and(|e| e == JSON_EXTENSION) {
+            save_data_from_json(load_from_json(file_path)?)?
+        } else {
+            read_save_file(file_path)?
+        };
+        Ok(save_data.problems())
+    }
+
+    /// This lists what has changed since the campaign in the file, such as an earlier backup
+    pub fn diff_with_file(&self, file_path: &Path) -> Result<CampaignDiff> {
+        let before = AppData::read_from_file(file_path)?;
+        let before_title = file_path
+            .file_name()
+            .map_or_else(|| file_path.to_string_lossy(), |n| n.to_string_lossy());
+        Ok(CampaignDiff::new(
+            &before_title,
+            &before.to_json_value()?,
+            &self.export_title(),
+            &self.to_json_value()?,
+        ))
+    }
+
+    // Items are added by name, so an item whose name is already present is linked to
+    // the existing item, rather than being added again
+    fn load_data(&mut self, save_data: impl Into<SaveData6>) -> Result<()> {
         // !! Not using return??
-        let save_data: SaveData2 = save_data.into();
-        let mut post_districts: Vec<(String, Vec<String>)> = Vec::new();
+        let save_data: SaveData6 = save_data.into();
+
         let mut district_add = save_data
             .districts
-            .into_iter()
-            .map(|d| {
-                let (district, notable) = self.district_from_store(d);
-                post_districts.push((district.name().to_string(), notable));
-                Action::DistrictAdd(district)
-            })
+            .iter()
+            .map(|d| Action::DistrictAdd(d.into()))
             .collect();
 
         if let Err(err) = self.do_action(&mut district_add) {
             error!("unable to add districts: {err}");
         }
 
+        let district_ids: BTreeMap<EntityId, DistrictRef> = save_data
+            .districts
+            .iter()
+            .filter_map(|d| self.districts.find(d.name()).map(|r| (d.id, r)))
+            .collect();
+
         let mut person_add = save_data
             .persons
-            .into_iter()
+            .iter()
             .map(|p| {
-                let person = self.person_from_store(p);
+                let mut person: Person = p.into();
+                if let Some(found_in) = &p.found_in {
+                    person.set_found_in(loaded_ref(
+                        &district_ids,
+                        found_in,
+                        &format!("found_in when loading person {}", p.name()),
+                    ));
+                }
                 Action::PersonAdd(person)
             })
             .collect();
@@ -304,36 +996,68 @@ impl AppData {
             error!("unable to add persons: {err}");
         }
 
+        let person_ids: BTreeMap<EntityId, PersonRef> = save_data
+            .persons
+            .iter()
+            .filter_map(|p| self.persons.find(p.name()).map(|r| (p.id, r)))
+            .collect();
+
         // do district references to persons; no undo
-        let mut district_replace: ActionNode = post_districts.into_iter().filter_map(|(district_name, notable)| {
-            if let Some(dist_ref) = self.districts.find(&district_name) {
-                if let Some(dist) = self.districts.fetch(&dist_ref) {
-                    let notables: Vec<PersonRef> = notable.into_iter().filter_map(|p| {
-                        let p_ref = self.persons.find(&p);
-                        if p_ref.is_none() { error!("unable to find person {p} as notable when loading district {district_name}"); }
-                        p_ref
-                    }).collect();
-                    if !notables.is_empty() {
-                        let mut district = dist.clone();
-                        district.set_notable(notables);
-                        Some(Action::DistrictReplace(dist_ref, district))
-                    } else { None }
-
-                } else { None }
-            } else { None }
-        }).collect();
+        let mut district_replace: ActionNode = save_data
+            .districts
+            .iter()
+            .filter_map(|d| {
+                let dist_ref = district_ids.get(&d.id)?;
+                let dist = self.districts.fetch(dist_ref)?;
+                let notables: Vec<PersonRef> = d
+                    .notable
+                    .iter()
+                    .filter_map(|p| {
+                        loaded_ref(
+                            &person_ids,
+                            p,
+                            &format!("notable when loading district {}", d.name()),
+                        )
+                    })
+                    .collect();
+                if !notables.is_empty() {
+                    let mut district = dist.clone();
+                    district.set_notable(notables);
+                    Some(Action::DistrictReplace(dist_ref.clone(), district))
+                } else {
+                    None
+                }
+            })
+            .collect();
 
         if let Err(err) = self.do_action(&mut district_replace) {
             error!("unable to replace districts with notables: {err}");
         }
 
-        let mut post_factions: Vec<(String, Vec<String>, Vec<String>)> = Vec::new();
         let mut faction_add = save_data
             .factions
-            .into_iter()
+            .iter()
             .map(|f| {
-                let (faction, allies, enemies) = self.faction_from_store(f);
-                post_factions.push((faction.name().to_string(), allies, enemies));
+                let context = |field: &str| format!("{field} when loading faction {}", f.name());
+                let mut faction: Faction = f.into();
+                if let Some(hq) = &f.hq {
+                    faction.set_hq(loaded_ref(&district_ids, hq, &context("hq")));
+                }
+                faction.set_turf(
+                    f.turf
+                        .iter()
+                        .filter_map(|d| loaded_ref(&district_ids, d, &context("turf")))
+                        .collect(),
+                );
+                if let Some(leader) = &f.leader {
+                    faction.set_leader(loaded_ref(&person_ids, leader, &context("leader")));
+                }
+                faction.set_notable(
+                    f.notable
+                        .iter()
+                        .filter_map(|p| loaded_ref(&person_ids, p, &context("notable")))
+                        .collect(),
+                );
                 Action::FactionAdd(faction)
             })
             .collect();
@@ -342,53 +1066,158 @@ impl AppData {
             error!("unable to add factions: {err}");
         }
 
+        let faction_ids: BTreeMap<EntityId, FactionRef> = save_data
+            .factions
+            .iter()
+            .filter_map(|f| self.factions.find(f.name()).map(|r| (f.id, r)))
+            .collect();
+
         // do faction references to factions
         // note these do not have undo
-        let mut faction_replace: ActionNode = post_factions.into_iter().filter_map(|(faction_name, allies, enemies)| {
-            if let Some(fac_ref) = self.factions.find(&faction_name) {
-                if let Some(fac) = self.factions.fetch(&fac_ref) {
-                    let allies: Vec<FactionRef> = allies.into_iter().filter_map(|f| {
-                        let f_ref = self.factions.find(&f);
-                        if f_ref.is_none() { error!("unable to find faction {f} as ally when loading faction {faction_name}"); }
-                        f_ref
-                    }).collect();
-                    let enemies: Vec<FactionRef> = enemies.into_iter().filter_map(|f| {
-                        let f_ref = self.factions.find(&f);
-                        if f_ref.is_none() { error!("unable to find faction {f} as enemy when loading faction {faction_name}"); }
-                        f_ref
-                    }).collect();
-                    if !allies.is_empty() || !enemies.is_empty() {
-                        let mut faction = fac.clone();
-                        if !allies.is_empty() { faction.set_allies(allies); }
-                        if !enemies.is_empty() { faction.set_enemies(enemies); }
-                        Some(Action::FactionReplace(fac_ref, faction))
-                    } else { None }
-                } else { None }
-            } else { None }
-        }).collect();
+        let mut faction_replace: ActionNode = save_data
+            .factions
+            .iter()
+            .filter_map(|f| {
+                let context = |field: &str| format!("{field} when loading faction {}", f.name());
+                let fac_ref = faction_ids.get(&f.id)?;
+                let fac = self.factions.fetch(fac_ref)?;
+                let allies: Vec<FactionRef> = f
+                    .allies
+                    .iter()
+                    .filter_map(|a| loaded_ref(&faction_ids, a, &context("ally")))
+                    .collect();
+                let enemies: Vec<FactionRef> = f
+                    .enemies
+                    .iter()
+                    .filter_map(|e| loaded_ref(&faction_ids, e, &context("enemy")))
+                    .collect();
+                if !allies.is_empty() || !enemies.is_empty() {
+                    let mut faction = fac.clone();
+                    if !allies.is_empty() {
+                        faction.set_allies(allies);
+                    }
+                    if !enemies.is_empty() {
+                        faction.set_enemies(enemies);
+                    }
+                    Some(Action::FactionReplace(fac_ref.clone(), faction))
+                } else {
+                    None
+                }
+            })
+            .collect();
 
         if let Err(err) = self.do_action(&mut faction_replace) {
             error!("unable to replace factions with allies and enemies: {err}");
         }
 
+        let mut clock_add = save_data
+            .clocks
+            .iter()
+            .map(|c| {
+                let context = |field: &str| format!("{field} when loading clock {}", c.name());
+                let mut clock: CampaignClock = c.into();
+                clock.set_links(
+                    c.factions
+                        .iter()
+                        .filter_map(|f| loaded_ref(&faction_ids, f, &context("faction")))
+                        .collect(),
+                    c.persons
+                        .iter()
+                        .filter_map(|p| loaded_ref(&person_ids, p, &context("person")))
+                        .collect(),
+                    c.districts
+                        .iter()
+                        .filter_map(|d| loaded_ref(&district_ids, d, &context("district")))
+                        .collect(),
+                );
+                Action::ClockAdd(clock)
+            })
+            .collect();
+
+        if let Err(err) = self.do_action(&mut clock_add) {
+            error!("unable to add clocks: {err}");
+        }
+
+        let clock_ids: BTreeMap<EntityId, CampaignClockRef> = save_data
+            .clocks
+            .iter()
+            .filter_map(|c| self.clocks.find(c.name()).map(|r| (c.id, r)))
+            .collect();
+
+        // do clock references to clocks
+        let mut clock_replace: ActionNode = save_data
+            .clocks
+            .iter()
+            .filter(|c| c.next.is_some() || c.rival.is_some())
+            .filter_map(|c| {
+                let context = |field: &str| format!("{field} when loading clock {}", c.name());
+                let clock_ref = clock_ids.get(&c.id)?;
+                let mut clock = self.clocks.fetch(clock_ref)?.clone();
+                if let Some(next) = &c.next {
+                    clock.set_next(loaded_ref(&clock_ids, next, &context("next")));
+                }
+                if let Some(rival) = &c.rival {
+                    clock.set_rival(loaded_ref(&clock_ids, rival, &context("rival")));
+                }
+                Some(Action::ClockReplace(clock_ref.clone(), clock))
+            })
+            .collect();
+
+        if let Err(err) = self.do_action(&mut clock_replace) {
+            error!("unable to replace clocks with next and rival clocks: {err}");
+        }
+
+        let actor_index = |id: &EntityId| {
+            faction_ids
+                .get(id)
+                .map(|r| r.data_index())
+                .or_else(|| person_ids.get(id).map(|r| r.data_index()))
+        };
+        let mut relationship_set: ActionNode = save_data
+            .relationships
+            .iter()
+            .filter_map(|r| {
+                let (Some(first), Some(second)) = (actor_index(&r.first), actor_index(&r.second))
+                else {
+                    error!(
+                        "unable to find {} and {} when loading relationship",
+                        r.first, r.second
+                    );
+                    return None;
+                };
+                if first == second {
+                    error!("ignoring relationship of {} with itself", r.first);
+                    return None;
+                }
+                Some(Action::RelationshipSet(
+                    ActorPair::new(first, second),
+                    Some(r.into()),
+                ))
+            })
+            .collect();
+
+        if let Err(err) = self.do_action(&mut relationship_set) {
+            error!("unable to set relationships: {err}");
+        }
+
+        // an import continues the log of the current data
+        let mut log_push: ActionNode = save_data
+            .turn_log
+            .into_iter()
+            .map(Action::TurnLogPush)
+            .collect();
+
+        if let Err(err) = self.do_action(&mut log_push) {
+            error!("unable to add the faction turn log: {err}");
+        }
+
         Ok(())
     }
 
     // todo: actually we'd want to add this to the current data, and use the file name (_file_path: &Path)
     pub fn test_import_from_json(&mut self) -> Result<()> {
         let data = include_str!("../test_data/test1.json");
-
-        let import: SaveData2 = {
-            // try 2
-            match serde_json::from_str::<SaveData2>(data) {
-                Result::Ok(save2) => save2,
-                Err(_) => {
-                    // try 1
-                    let save1: SaveData1 = serde_json::from_str(data)?;
-                    save1.into()
-                }
-            }
-        };
+        let import = save_data_from_json(serde_json::from_str(data)?)?;
 
         debug!(
             "imported {} people, {} districts, {} factions",
@@ -398,120 +1227,93 @@ impl AppData {
         );
         self.load_data(import)
     }
+}
 
-    fn person_from_store(&self, p_store: PersonStore2) -> Person {
-        let mut person: Person = (&p_store).into();
-        // todo: convert references
-        if let Some(found_in) = p_store.found_in {
-            let found_in_ref = self.districts.find(&found_in);
-            if found_in_ref.is_none() {
-                error!(
-                    "unable to find district {} as found_in when loading person {}",
-                    found_in,
-                    person.name()
-                );
-            }
-            person.set_found_in(found_in_ref);
-        }
-
-        person
+/// Finds the reference loaded for a stored id, logging it if it is missing
+fn loaded_ref<T: Clone + Named>(
+    loaded: &BTreeMap<EntityId, GenericRef<T>>,
+    id: &EntityId,
+    context: &str,
+) -> Option<GenericRef<T>> {
+    let found = loaded.get(id).cloned();
+    if found.is_none() {
+        error!("unable to find {id} as {context}");
     }
+    found
+}
 
-    /// Returns list of notable Person Strings
-    fn district_from_store(&self, d_store: DistrictStore) -> (District, Vec<String>) {
-        let district: District = (&d_store).into();
-        // todo: convert references
-
-        (district, d_store.notable)
+/// Reads any version of the export format, converting it to the current version once
+/// its schema and version are known to be ours
+fn save_data_from_json(value: serde_json::Value) -> Result<SaveData6> {
+    let header: SaveHeader = serde_json::from_value(value.clone())?;
+    if header.save_schema != SAVE_SCHEMA {
+        error!("invalid export file schema {}", header.save_schema);
+        return Err(anyhow!(
+            "invalid export file schema '{}'",
+            header.save_schema
+        ));
     }
-
-    fn faction_from_store(&self, f_store: FactionStore) -> (Faction, Vec<String>, Vec<String>) {
-        let mut faction: Faction = (&f_store).into();
-        // todo: convert references
-        // hq (option district)
-        if let Some(hq) = f_store.hq {
-            let hq_ref = self.districts.find(&hq);
-            if hq_ref.is_none() {
-                error!(
-                    "unable to find district {} as hq when loading faction {}",
-                    hq,
-                    faction.name()
-                );
-            }
-            faction.set_hq(hq_ref);
-        }
-
-        // turf (vec district)
-        let turf = f_store
-            .turf
-            .into_iter()
-            .filter_map(|d| {
-                let d_ref = self.districts.find(&d);
-                if d_ref.is_none() {
-                    error!(
-                        "unable to find district {} as turf when loading faction {}",
-                        d,
-                        faction.name()
-                    );
-                }
-                d_ref
-            })
-            .collect();
-        faction.set_turf(turf);
-
-        // leader (option person)
-        if let Some(leader) = f_store.leader {
-            let leader_ref = self.persons.find(&leader);
-            if leader_ref.is_none() {
-                error!(
-                    "unable to find person {} as leader when loading faction {}",
-                    leader,
-                    faction.name()
-                );
-            }
-            faction.set_leader(leader_ref);
+    let data = match header.save_version {
+        SAVE6_VERSION => serde_json::from_value::<SaveData6>(value)?,
+        SAVE5_VERSION => serde_json::from_value::<SaveData5>(value)?.into(),
+        SAVE4_VERSION => serde_json::from_value::<SaveData4>(value)?.into(),
+        SAVE3_VERSION => serde_json::from_value::<SaveData3>(value)?.into(),
+        SAVE2_VERSION => serde_json::from_value::<SaveData2>(value)?.into(),
+        SAVE1_VERSION => serde_json::from_value::<SaveData1>(value)?.into(),
+        _ => {
+            error!("invalid export file version {}", header.save_version);
+            return Err(anyhow!(
+                "invalid export file version {}",
+                header.save_version
+            ));
         }
+    };
+    Ok(data)
+}
 
-        // notable (vec person)
-        let notable = f_store
-            .notable
-            .into_iter()
-            .filter_map(|p| {
-                let p_ref = self.persons.find(&p);
-                if p_ref.is_none() {
-                    error!(
-                        "unable to find person {} as notable when loading faction {}",
-                        p,
-                        faction.name()
-                    );
-                }
-                p_ref
-            })
-            .collect();
-        faction.set_notable(notable);
-
-        // allies (vec faction)
-        // enemies (vec faction)
-
-        (faction, f_store.allies, f_store.enemies)
+/// Converts the data read from a file to the current version, once its own schema and
+/// version have been checked, as the conversions expect data they can use
+fn upgrade<T: Into<SaveData6>>(data: T, validate: fn(&T) -> bool) -> Result<SaveData6> {
+    if validate(&data) {
+        Ok(data.into())
+    } else {
+        error!("unable to validate save data before converting it");
+        Err(anyhow!("unable to validate save data"))
     }
 }
 
+/// Backs up the file only once the data has been checked, so that saves which fail
+/// validation do not push good backups out
 fn save_data_to_file(file_path: &Path, data: &AppData) -> Result<()> {
-    let save_data: SaveData2 = data.into();
-    if save_data.validate() {
-        let buffer = pot::to_vec::<SaveData2>(&save_data)?;
-        save_to_save(file_path, save_data.save_version, buffer)
-    } else {
-        Err(anyhow!("unable to validate save data - not saved"))
+    let (save_version, buffer) = data.save_snapshot()?;
+    // a failed backup should not stop the save itself
+    if let Err(e) = make_backup(file_path) {
+        error!(
+            "unable to back up [{}] before saving: {e}",
+            file_path.to_string_lossy()
+        );
     }
+    save_to_save(file_path, save_version, buffer)
 }
 
 fn save_data_from_file(file_path: &Path) -> Result<AppData> {
+    let data = read_save_file(file_path)?;
+    let mut ret: AppData = data.into();
+    ret.loaded_from = Some(file_path.to_path_buf());
+    ret.changed = false; // loading goes through do_action, but matches the file
+    Ok(ret)
+}
+
+/// Reads any version of a save file, converting it to the current version
+fn read_save_file(file_path: &Path) -> Result<SaveData6> {
     let data = match load_from_save(file_path) {
         Result::Ok((save_version, buffer)) => match save_version {
-            SAVE2_VERSION => pot::from_reader::<SaveData2, _>(buffer)?,
-            SAVE1_VERSION => pot::from_reader::<SaveData1, _>(buffer)?.into(),
+            SAVE6_VERSION => pot::from_reader::<SaveData6, _>(buffer)?,
+            SAVE5_VERSION => upgrade(pot::from_reader(buffer)?, SaveData5::validate)?,
+            SAVE4_VERSION => upgrade(pot::from_reader(buffer)?, SaveData4::validate)?,
+            SAVE3_VERSION => upgrade(pot::from_reader(buffer)?, SaveData3::validate)?,
+            SAVE2_VERSION => upgrade(pot::from_reader(buffer)?, SaveData2::validate)?,
+            SAVE1_VERSION => upgrade(pot::from_reader(buffer)?, SaveData1::validate)?,
             _ => {
                 error!("invalid save file version {save_version}");
                 return Err(anyhow!("invalid save file version {save_version}"));
@@ -525,10 +1327,7 @@ fn save_data_from_file(file_path: &Path) -> Result<AppData> {
     };
 
     if data.validate() {
-        // todo: should we validate the loaded savedata, rather than the data to convert?
-        let mut ret: AppData = data.into();
-        ret.loaded_from = Some(file_path.to_path_buf());
-        Ok(ret)
+        Ok(data)
     } else {
         error!("unable to validate loaded save data");
         Err(anyhow!("unable to validate save data"))
@@ -542,6 +1341,384 @@ const SAVE_SCHEMA: &str = "BladesFactionsData";
 // and a set of save data formats that do the loading?
 //
 
+/// Just enough of any save version to find out whether it is ours, and which version it is
+#[derive(Debug, Deserialize)]
+struct SaveHeader {
+    save_schema: String,
+    save_version: u16,
+}
+
+// ====================
+// SaveData6
+const SAVE6_VERSION: u16 = 6;
+
+// As version 5, with the log of faction turns
+#[derive(Debug, Serialize, Deserialize)]
+struct SaveData6 {
+    save_schema: String,
+    save_version: u16,
+    persons: Vec<PersonStore3>,
+    districts: Vec<DistrictStore3>,
+    factions: Vec<FactionStore3>,
+    clocks: Vec<CampaignClockStore>,
+    relationships: Vec<RelationshipStore>,
+    turn_log: Vec<TurnLogEntry>,
+}
+
+impl SaveData6 {
+    fn validate(&self) -> bool {
+        self.save_schema == SAVE_SCHEMA && self.save_version == SAVE6_VERSION
+    }
+
+    /// Everything which loading would drop: items whose names are already taken,
+    /// references to ids which are not in the data, and relationships with oneself
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut unique = |kind: &str, names: Vec<&str>| {
+            let mut seen = BTreeSet::new();
+            for name in names {
+                if !seen.insert(name) {
+                    problems.push(format!("more than one {kind} is named {name}"));
+                }
+            }
+        };
+        unique(
+            "district",
+            self.districts.iter().map(|d| d.name()).collect(),
+        );
+        unique("person", self.persons.iter().map(|p| p.name()).collect());
+        unique("faction", self.factions.iter().map(|f| f.name()).collect());
+        unique("clock", self.clocks.iter().map(|c| c.name()).collect());
+
+        let districts: BTreeSet<EntityId> = self.districts.iter().map(|d| d.id).collect();
+        let persons: BTreeSet<EntityId> = self.persons.iter().map(|p| p.id).collect();
+        let factions: BTreeSet<EntityId> = self.factions.iter().map(|f| f.id).collect();
+        let clocks: BTreeSet<EntityId> = self.clocks.iter().map(|c| c.id).collect();
+        let mut resolve = |ids: &BTreeSet<EntityId>, refs: Vec<&EntityId>, context: String| {
+            for id in refs.into_iter().filter(|id| !ids.contains(id)) {
+                problems.push(format!("{context} refers to {id}, which is missing"));
+            }
+        };
+        for d in &self.districts {
+            let context = |field: &str| format!("the {field} of district {}", d.name());
+            resolve(&persons, d.notable.iter().collect(), context("notable"));
+        }
+        for p in &self.persons {
+            let context = |field: &str| format!("the {field} of person {}", p.name());
+            resolve(&districts, p.found_in.iter().collect(), context("found_in"));
+        }
+        for f in &self.factions {
+            let context = |field: &str| format!("the {field} of faction {}", f.name());
+            resolve(&districts, f.hq.iter().collect(), context("hq"));
+            resolve(&districts, f.turf.iter().collect(), context("turf"));
+            resolve(&persons, f.leader.iter().collect(), context("leader"));
+            resolve(&persons, f.notable.iter().collect(), context("notable"));
+            resolve(&factions, f.allies.iter().collect(), context("allies"));
+            resolve(&factions, f.enemies.iter().collect(), context("enemies"));
+        }
+        for c in &self.clocks {
+            let context = |field: &str| format!("the {field} of clock {}", c.name());
+            resolve(&factions, c.factions.iter().collect(), context("factions"));
+            resolve(&persons, c.persons.iter().collect(), context("persons"));
+            resolve(
+                &districts,
+                c.districts.iter().collect(),
+                context("districts"),
+            );
+            resolve(&clocks, c.next.iter().collect(), context("next"));
+            resolve(&clocks, c.rival.iter().collect(), context("rival"));
+        }
+        let actors: BTreeSet<EntityId> = factions.union(&persons).copied().collect();
+        for r in &self.relationships {
+            let context = format!("a relationship of {} and {}", r.first, r.second);
+            resolve(&actors, vec![&r.first, &r.second], context);
+        }
+        for r in self.relationships.iter().filter(|r| r.first == r.second) {
+            problems.push(format!("{} has a relationship with itself", r.first));
+        }
+        problems
+    }
+}
+
+impl From<SaveData6> for AppData {
+    fn from(save_data: SaveData6) -> Self {
+        let mut app_data = AppData::default();
+        if let Err(e) = app_data.load_data(save_data) {
+            error!("unable to load save version 6 data: {e}");
+        }
+        app_data
+    }
+}
+
+impl From<&AppData> for SaveData6 {
+    fn from(input_data: &AppData) -> Self {
+        let save_data5 = SaveData5::from(input_data);
+        SaveData6 {
+            save_schema: save_data5.save_schema,
+            save_version: SAVE6_VERSION,
+            persons: save_data5.persons,
+            districts: save_data5.districts,
+            factions: save_data5.factions,
+            clocks: save_data5.clocks,
+            relationships: save_data5.relationships,
+            turn_log: input_data.turn_log.clone(),
+        }
+    }
+}
+
+impl From<SaveData5> for SaveData6 {
+    fn from(save_data5: SaveData5) -> Self {
+        SaveData6 {
+            save_schema: save_data5.save_schema,
+            save_version: SAVE6_VERSION,
+            persons: save_data5.persons,
+            districts: save_data5.districts,
+            factions: save_data5.factions,
+            clocks: save_data5.clocks,
+            relationships: save_data5.relationships,
+            turn_log: Vec::new(),
+        }
+    }
+}
+
+// Older versions are converted one version at a time, up to the current version
+impl From<SaveData4> for SaveData6 {
+    fn from(save_data4: SaveData4) -> Self {
+        SaveData5::from(save_data4).into()
+    }
+}
+
+impl From<SaveData3> for SaveData6 {
+    fn from(save_data3: SaveData3) -> Self {
+        SaveData4::from(save_data3).into()
+    }
+}
+
+impl From<SaveData2> for SaveData6 {
+    fn from(save_data2: SaveData2) -> Self {
+        SaveData3::from(save_data2).into()
+    }
+}
+
+impl From<SaveData1> for SaveData6 {
+    fn from(save_data1: SaveData1) -> Self {
+        SaveData2::from(save_data1).into()
+    }
+}
+
+// ====================
+// SaveData5
+const SAVE5_VERSION: u16 = 5;
+
+// As version 4, with the clocks which belong to the campaign rather than a faction
+#[derive(Debug, Serialize, Deserialize)]
+struct SaveData5 {
+    save_schema: String,
+    save_version: u16,
+    persons: Vec<PersonStore3>,
+    districts: Vec<DistrictStore3>,
+    factions: Vec<FactionStore3>,
+    clocks: Vec<CampaignClockStore>,
+    relationships: Vec<RelationshipStore>,
+}
+
+impl SaveData5 {
+    fn validate(&self) -> bool {
+        self.save_schema == SAVE_SCHEMA && self.save_version == SAVE5_VERSION
+    }
+}
+
+impl From<SaveData5> for AppData {
+    fn from(save_data: SaveData5) -> Self {
+        let mut app_data = AppData::default();
+        if let Err(e) = app_data.load_data(save_data) {
+            error!("unable to load save version 5 data: {e}");
+        }
+        app_data
+    }
+}
+
+impl From<&AppData> for SaveData5 {
+    fn from(input_data: &AppData) -> Self {
+        let save_data4 = SaveData4::from(input_data);
+        SaveData5 {
+            save_schema: save_data4.save_schema,
+            save_version: SAVE5_VERSION,
+            persons: save_data4.persons,
+            districts: save_data4.districts,
+            factions: save_data4.factions,
+            clocks: input_data.clocks.borrow().into(),
+            relationships: save_data4.relationships,
+        }
+    }
+}
+
+impl From<SaveData4> for SaveData5 {
+    fn from(save_data4: SaveData4) -> Self {
+        SaveData5 {
+            save_schema: save_data4.save_schema,
+            save_version: SAVE5_VERSION,
+            persons: save_data4.persons,
+            districts: save_data4.districts,
+            factions: save_data4.factions,
+            clocks: Vec::new(),
+            relationships: save_data4.relationships,
+        }
+    }
+}
+
+// ====================
+// SaveData4
+const SAVE4_VERSION: u16 = 4;
+
+// As version 3, with the relationships between factions and persons
+#[derive(Debug, Serialize, Deserialize)]
+struct SaveData4 {
+    save_schema: String,
+    save_version: u16,
+    persons: Vec<PersonStore3>,
+    districts: Vec<DistrictStore3>,
+    factions: Vec<FactionStore3>,
+    relationships: Vec<RelationshipStore>,
+}
+
+impl SaveData4 {
+    fn validate(&self) -> bool {
+        self.save_schema == SAVE_SCHEMA && self.save_version == SAVE4_VERSION
+    }
+}
+
+impl From<SaveData4> for AppData {
+    fn from(save_data: SaveData4) -> Self {
+        let mut app_data = AppData::default();
+        if let Err(e) = app_data.load_data(save_data) {
+            error!("unable to load save version 4 data: {e}");
+        }
+        app_data
+    }
+}
+
+impl From<&AppData> for SaveData4 {
+    fn from(input_data: &AppData) -> Self {
+        let relationships = input_data
+            .relationships
+            .iter()
+            .filter_map(|(pair, relationship)| {
+                let (first, second) = pair.actors();
+                if !can_have_relationships(first) || !can_have_relationships(second) {
+                    error!("not saving relationship between {first:?} and {second:?}");
+                    return None;
+                }
+                match (
+                    input_data.data_index_id(first),
+                    input_data.data_index_id(second),
+                ) {
+                    (Some(first), Some(second)) => {
+                        Some(RelationshipStore::new(first, second, relationship))
+                    }
+                    _ => {
+                        error!("not saving relationship between missing {first:?} and {second:?}");
+                        None
+                    }
+                }
+            })
+            .collect();
+        SaveData4 {
+            save_schema: SAVE_SCHEMA.to_string(),
+            save_version: SAVE4_VERSION,
+            persons: input_data.persons.borrow().into(),
+            districts: input_data.districts.borrow().into(),
+            factions: input_data.factions.borrow().into(),
+            relationships,
+        }
+    }
+}
+
+impl From<SaveData3> for SaveData4 {
+    fn from(save_data3: SaveData3) -> Self {
+        SaveData4 {
+            save_schema: save_data3.save_schema,
+            save_version: SAVE4_VERSION,
+            persons: save_data3.persons,
+            districts: save_data3.districts,
+            factions: save_data3.factions,
+            relationships: Vec::new(),
+        }
+    }
+}
+
+// ====================
+// SaveData3
+const SAVE3_VERSION: u16 = 3;
+
+// Every item has a persistent id, and references are stored by id
+#[derive(Debug, Serialize, Deserialize)]
+struct SaveData3 {
+    save_schema: String,
+    save_version: u16,
+    persons: Vec<PersonStore3>,
+    districts: Vec<DistrictStore3>,
+    factions: Vec<FactionStore3>,
+}
+
+impl SaveData3 {
+    fn validate(&self) -> bool {
+        self.save_schema == SAVE_SCHEMA && self.save_version == SAVE3_VERSION
+    }
+}
+
+impl From<SaveData3> for AppData {
+    fn from(save_data: SaveData3) -> Self {
+        let mut app_data = AppData::default();
+        if let Err(e) = app_data.load_data(save_data) {
+            error!("unable to load save version 3 data: {e}");
+        }
+        app_data
+    }
+}
+
+impl From<&AppData> for SaveData3 {
+    fn from(input_data: &AppData) -> Self {
+        SaveData3 {
+            save_schema: SAVE_SCHEMA.to_string(),
+            save_version: SAVE3_VERSION,
+            persons: input_data.persons.borrow().into(),
+            districts: input_data.districts.borrow().into(),
+            factions: input_data.factions.borrow().into(),
+        }
+    }
+}
+
+impl From<SaveData2> for SaveData3 {
+    fn from(save_data2: SaveData2) -> Self {
+        // give every name an id, then references can be converted to those ids
+        let ids = NameIds::new(
+            save_data2.districts.iter().map(|d| d.name().to_string()),
+            save_data2.persons.iter().map(|p| p.name().to_string()),
+            save_data2.factions.iter().map(|f| f.name().to_string()),
+        );
+        SaveData3 {
+            save_schema: save_data2.save_schema,
+            save_version: SAVE3_VERSION,
+            persons: save_data2
+                .persons
+                .into_iter()
+                .map(|p| PersonStore3::from_named(p, &ids))
+                .collect(),
+            districts: save_data2
+                .districts
+                .into_iter()
+                .map(|d| DistrictStore3::from_named(d, &ids))
+                .collect(),
+            factions: save_data2
+                .factions
+                .into_iter()
+                .map(|f| FactionStore3::from_named(f, &ids))
+                .collect(),
+        }
+    }
+}
+
 // ====================
 // SaveData2
 const SAVE2_VERSION: u16 = 2;
@@ -585,10 +1762,9 @@ impl From<&AppData> for SaveData2 {
 
 impl From<SaveData1> for SaveData2 {
     fn from(save_data1: SaveData1) -> Self {
-        assert!(save_data1.validate()); // This is too extreme, but we want to check the version and scheme before loading the data
         SaveData2 {
             save_schema: save_data1.save_schema,
-            save_version: save_data1.save_version,
+            save_version: SAVE2_VERSION,
             persons: save_data1.persons.into_iter().map(|p| p.into()).collect(),
             districts: save_data1.districts,
             factions: save_data1.factions,
@@ -637,6 +1813,39 @@ impl From<&AppData> for SaveData1 {
     }
 }
 
+// ----------------------------------------
+// ItemReference
+
+/// One item which refers to another, naming the fields which hold the reference
+#[derive(Debug, Clone)]
+pub struct ItemReference {
+    holder: DataIndex,
+    holder_name: String,
+    fields: Vec<String>,
+}
+
+impl ItemReference {
+    fn new(holder: DataIndex, holder_name: &str, fields: Vec<String>) -> Self {
+        ItemReference {
+            holder,
+            holder_name: holder_name.to_string(),
+            fields,
+        }
+    }
+
+    pub fn holder(&self) -> DataIndex {
+        self.holder
+    }
+
+    pub fn holder_name(&self) -> &str {
+        &self.holder_name
+    }
+
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+}
+
 // ----------------------------------------
 
 // Is it true that while the index can change, the relative order will remain the same?
@@ -648,6 +1857,7 @@ pub enum DataIndex {
     DistrictIndex(usize),
     PersonIndex(usize),
     FactionIndex(usize),
+    ClockIndex(usize),
 }
 
 impl DataIndex {
@@ -655,9 +1865,331 @@ impl DataIndex {
         match self {
             DataIndex::DistrictIndex(i)
             | DataIndex::FactionIndex(i)
-            | DataIndex::PersonIndex(i) => Some(*i),
+            | DataIndex::PersonIndex(i)
+            | DataIndex::ClockIndex(i) => Some(*i),
 
             _ => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        action::{Action, ActionNode},
+        app_data::{AppData, SAVE2_VERSION, SaveData2, SaveData6, save_data_from_json},
+        campaign_clock::{CampaignClock, ClockState},
+        clock::Clock,
+        display::MainView,
+        entity_id::EntityId,
+        export_format::ItemKind,
+        persistence::{save_to_save, test_dir},
+        relationship::{ActorPair, Attitude, Relationship},
+        todo::{TodoKind, TodoUndo},
+    };
+
+    const NAMED_SAVE: &str = r#"{
+        "save_schema": "BladesFactionsData",
+        "save_version": 2,
+        "persons": [
+            { "name": "Lyssa", "summary": "", "found_in": "Crow's Foot", "description": "", "personality": [], "notes": "" }
+        ],
+        "districts": [
+            { "name": "Crow's Foot", "description": "", "wealth": "One", "safety": "One", "crime": "Four", "occult": "One", "notable": ["Lyssa"], "notes": "" }
+        ],
+        "factions": [
+            { "name": "The Crows", "description": "", "tier": "Tier2", "hq": "Crow's Foot", "turf": ["Crow's Foot"], "leader": "Lyssa", "notable": [], "assets": "", "notes": "", "allies": [], "enemies": ["The Hive"], "general": "", "clocks": [] },
+            { "name": "The Hive", "description": "", "tier": "Tier4", "hq": null, "turf": [], "leader": null, "notable": [], "assets": "", "notes": "", "allies": [], "enemies": ["The Crows"], "general": "", "clocks": [] }
+        ]
+    }"#;
+
+    fn load_named() -> AppData {
+        let mut data = AppData::default();
+        let save = save_data_from_json(serde_json::from_str(NAMED_SAVE).unwrap()).unwrap();
+        assert!(save.validate());
+        data.load_data(save).expect("unable to load named save");
+        data
+    }
+
+    #[test]
+    fn named_save_converts_to_ids() {
+        let data = load_named();
+        let save: SaveData6 = (&data).into();
+
+        let crows_foot = save
+            .districts
+            .iter()
+            .find(|d| d.name() == "Crow's Foot")
+            .unwrap();
+        let lyssa = save.persons.iter().find(|p| p.name() == "Lyssa").unwrap();
+        let crows = save
+            .factions
+            .iter()
+            .find(|f| f.name() == "The Crows")
+            .unwrap();
+        let hive = save
+            .factions
+            .iter()
+            .find(|f| f.name() == "The Hive")
+            .unwrap();
+
+        assert!(!crows_foot.id.is_nil());
+        assert_eq!(lyssa.found_in, Some(crows_foot.id));
+        assert_eq!(crows_foot.notable, vec![lyssa.id]);
+        assert_eq!(crows.hq, Some(crows_foot.id));
+        assert_eq!(crows.turf, vec![crows_foot.id]);
+        assert_eq!(crows.leader, Some(lyssa.id));
+        assert_eq!(crows.enemies, vec![hive.id]);
+        assert_eq!(hive.enemies, vec![crows.id]);
+    }
+
+    #[test]
+    fn older_data_of_another_schema_is_an_error() {
+        let mut save: SaveData2 = serde_json::from_str(NAMED_SAVE).unwrap();
+        save.save_schema = "SomeOtherData".to_string();
+        let dir = test_dir("data");
+        let file = dir.join("other.bfsav");
+        save_to_save(&file, SAVE2_VERSION, pot::to_vec(&save).unwrap()).unwrap();
+
+        assert!(AppData::load_from_file(&file).is_err());
+        assert!(AppData::from_json_value(serde_json::to_value(&save).unwrap()).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn problems_list_what_loading_drops() {
+        let data = load_named();
+        let mut save = SaveData6::from(&data);
+        assert!(save.problems().is_empty());
+
+        save.factions[0].hq = Some(EntityId::new());
+        let district = serde_json::to_value(&save.districts[0]).unwrap();
+        save.districts
+            .push(serde_json::from_value(district).unwrap());
+        assert_eq!(save.problems().len(), 2);
+    }
+
+    #[test]
+    fn links_survive_rename_in_export() {
+        let data = load_named();
+        let mut json = serde_json::to_value(SaveData6::from(&data)).unwrap();
+
+        // rename the district in the export, as another tool might
+        for district in json["districts"].as_array_mut().unwrap() {
+            if district["name"] == "Crow's Foot" {
+                district["name"] = "Crows Foot".into();
+            }
+        }
+
+        let mut reloaded = AppData::default();
+        reloaded
+            .load_data(save_data_from_json(json).unwrap())
+            .unwrap();
+        let renamed = reloaded.find_district("Crows Foot").unwrap();
+        let crows_ref = reloaded.find_faction("The Crows").unwrap();
+        let crows = reloaded.clone_faction(&crows_ref).unwrap();
+        assert_eq!(crows.reference_fields(renamed.data_index()).len(), 2); // hq and turf
+        let lyssa_ref = reloaded.find_person("Lyssa").unwrap();
+        let lyssa = reloaded.clone_person(&lyssa_ref).unwrap();
+        assert_eq!(lyssa.reference_fields(renamed.data_index()).len(), 1);
+        assert_eq!(
+            renamed.id(),
+            data.find_district("Crow's Foot").unwrap().id()
+        );
+    }
+
+    #[test]
+    fn relationships_save_and_clear_on_delete() {
+        let mut data = load_named();
+        let crows = data.find_faction("The Crows").unwrap().data_index();
+        let lyssa = data.find_person("Lyssa").unwrap().data_index();
+        let pair = ActorPair::new(lyssa, crows);
+        let relationship = Relationship::new(Attitude::Minus2, "owes them coin");
+        data.do_action(&mut Action::RelationshipSet(pair, Some(relationship.clone())).into())
+            .unwrap();
+
+        let json = serde_json::to_value(SaveData6::from(&data)).unwrap();
+        let mut reloaded = AppData::default();
+        reloaded
+            .load_data(save_data_from_json(json).unwrap())
+            .unwrap();
+        let reloaded_pair = ActorPair::new(
+            reloaded.find_person("Lyssa").unwrap().data_index(),
+            reloaded.find_faction("The Crows").unwrap().data_index(),
+        );
+        assert_eq!(
+            reloaded.relationships().get(&reloaded_pair),
+            Some(&relationship)
+        );
+
+        let mut delete = data.remove_item_actions(lyssa).unwrap();
+        let mut undo = data.do_action(&mut delete).unwrap();
+        assert!(data.relationships().is_empty());
+        data.do_action(&mut undo).unwrap();
+        assert_eq!(data.relationships().get(&pair), Some(&relationship));
+    }
+
+    #[test]
+    fn csv_import_is_one_undo_step() {
+        let mut data = load_named();
+        let before = data.to_json_value().unwrap();
+        let dir = test_dir("data");
+        fs::create_dir_all(&dir).unwrap();
+        let csv = dir.join("districts.csv");
+        fs::write(&csv, "name,wealth\nCrow's Foot,Three\nNightmarket,Two\n").unwrap();
+
+        let (report, mut reverse) = data.import_from_csv(&csv, ItemKind::District).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((report.created, report.updated), (1, 1));
+        assert_eq!(data.view_size(MainView::Districts), 2);
+        data.do_action(&mut reverse).unwrap();
+        assert_eq!(data.to_json_value().unwrap(), before);
+    }
+
+    #[test]
+    fn vault_updates_items_and_keeps_the_rest() {
+        let mut data = load_named();
+        let crows = data.find_faction("The Crows").unwrap().data_index();
+        let lyssa = data.find_person("Lyssa").unwrap().data_index();
+        let relationship = Relationship::new(Attitude::Plus1, "old friends");
+        let mut setup: ActionNode = [
+            Action::RelationshipSet(ActorPair::new(lyssa, crows), Some(relationship)),
+            Action::ClockAdd(CampaignClock::new("Escape", Clock::new_clock_4("", ""))),
+        ]
+        .into();
+        data.do_action(&mut setup).unwrap();
+        let before = data.to_json_value().unwrap();
+
+        let vault = test_dir("vault");
+        data.export_to_vault(&vault).unwrap();
+        let note = vault.join("Factions").join("The Crows.md");
+        let text = fs::read_to_string(&note).unwrap();
+        fs::write(&note, text.replace("tier: 2", "tier: 3")).unwrap();
+        let mut reverse = data.update_from_vault(&vault).unwrap();
+        fs::remove_dir_all(&vault).unwrap();
+
+        let after = data.to_json_value().unwrap();
+        assert_eq!(after["factions"][0]["id"], before["factions"][0]["id"]);
+        assert_eq!(after["factions"][0]["tier"], "Tier3");
+        assert_eq!(after["relationships"], before["relationships"]);
+        assert_eq!(after["clocks"], before["clocks"]);
+        data.do_action(&mut reverse).unwrap();
+        assert_eq!(
+            data.to_json_value().unwrap()["factions"],
+            before["factions"]
+        );
+    }
+
+    #[test]
+    fn undo_and_redo_a_node() {
+        let mut data = load_named();
+        let crows_ref = data.find_faction("The Crows").unwrap();
+        let clock_count = |data: &AppData| data.clone_faction(&crows_ref).unwrap().clocks().len();
+
+        // two changes to the same faction in one node must undo back to the first
+        let mut once = data.clone_faction(&crows_ref).unwrap();
+        once.clocks_mut().push(Clock::new_clock_4("Expand", ""));
+        let mut twice = once.clone();
+        twice.clocks_mut().push(Clock::new_clock_6("Revenge", ""));
+        let mut history = TodoUndo::default();
+        history.add_todo(
+            [
+                Action::FactionReplace(crows_ref.clone(), once),
+                Action::FactionReplace(crows_ref.clone(), twice),
+            ]
+            .into(),
+        );
+
+        let run = |data: &mut AppData, history: &mut TodoUndo| {
+            let (kind, mut node) = history.todo().unwrap();
+            let reverse = data.do_action(&mut node).unwrap();
+            history.complete(kind, reverse);
+            kind
+        };
+        assert_eq!(run(&mut data, &mut history), TodoKind::Change);
+        assert_eq!(clock_count(&data), 2);
+
+        assert!(history.undo());
+        assert_eq!(run(&mut data, &mut history), TodoKind::Undo);
+        assert_eq!(clock_count(&data), 0);
+        assert!(!history.can_undo());
+
+        assert!(history.redo());
+        assert_eq!(run(&mut data, &mut history), TodoKind::Redo);
+        assert_eq!(clock_count(&data), 2);
+        assert!(history.can_undo() && !history.can_redo());
+    }
+
+    #[test]
+    fn finished_clock_starts_next_and_stops_rival() {
+        let mut data = AppData::default();
+        let mut waiting = CampaignClock::new("Reinforcements", Clock::new_clock_4("", ""));
+        waiting.set_state(ClockState::Waiting);
+        let mut setup: ActionNode = [
+            Action::ClockAdd(CampaignClock::new("Escape", Clock::new_clock_4("", ""))),
+            Action::ClockAdd(CampaignClock::new("Alarm", Clock::new_clock_4("", ""))),
+            Action::ClockAdd(waiting),
+        ]
+        .into();
+        data.do_action(&mut setup).unwrap();
+
+        let escape_ref = data.find_clock("Escape").unwrap();
+        let mut escape = data.clone_clock(&escape_ref).unwrap();
+        escape.set_next(data.find_clock("Reinforcements"));
+        escape.set_rival(data.find_clock("Alarm"));
+        while !escape.clock_mut().tick() {}
+
+        let mut node = data.clock_replace_actions(&escape_ref, escape);
+        assert_eq!(node.len(), 3);
+        data.do_action(&mut node).unwrap();
+
+        let state = |data: &AppData, name: &str| {
+            data.clone_clock(&data.find_clock(name).unwrap())
+                .unwrap()
+                .state()
+        };
+        assert_eq!(state(&data, "Reinforcements"), ClockState::Running);
+        assert_eq!(state(&data, "Alarm"), ClockState::Stopped);
+
+        // a clock which names the finishing one as its rival loses the race too
+        let mut setup: ActionNode = [
+            Action::ClockAdd(CampaignClock::new("Pursuit", Clock::new_clock_4("", ""))),
+            Action::ClockAdd(CampaignClock::new("Hideout", Clock::new_clock_4("", ""))),
+        ]
+        .into();
+        data.do_action(&mut setup).unwrap();
+        let pursuit_ref = data.find_clock("Pursuit").unwrap();
+        let mut pursuit = data.clone_clock(&pursuit_ref).unwrap();
+        pursuit.set_rival(data.find_clock("Hideout"));
+        let mut node = data.clock_replace_actions(&pursuit_ref, pursuit);
+        data.do_action(&mut node).unwrap();
+
+        let hideout_ref = data.find_clock("Hideout").unwrap();
+        let mut hideout = data.clone_clock(&hideout_ref).unwrap();
+        while !hideout.clock_mut().tick() {}
+        let mut node = data.clock_replace_actions(&hideout_ref, hideout);
+        assert_eq!(node.len(), 2);
+        data.do_action(&mut node).unwrap();
+        assert_eq!(state(&data, "Pursuit"), ClockState::Stopped);
+
+        // the links survive a save
+        let json = serde_json::to_value(SaveData6::from(&data)).unwrap();
+        let mut reloaded = AppData::default();
+        reloaded
+            .load_data(save_data_from_json(json).unwrap())
+            .unwrap();
+        let escape = reloaded
+            .clone_clock(&reloaded.find_clock("Escape").unwrap())
+            .unwrap();
+        assert_eq!(
+            escape.next().and_then(|r| r.name()),
+            Some("Reinforcements".to_string())
+        );
+        assert_eq!(
+            escape.rival().and_then(|r| r.name()),
+            Some("Alarm".to_string())
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::slice::Iter;
+use std::cell::RefCell;
 
 use eframe::egui::{
     Color32, ComboBox, Frame, Key, Label, Margin, Modifiers, RichText, Sense, Stroke, Ui,
@@ -8,146 +8,36 @@ use log::{debug, info};
 
 use crate::{
     app::EditResult,
-    app_data::{AppData, DataIndex},
+    app_data::AppData,
+    display::{ColumnWidth, DisplayTable},
     localize::fl,
-    managed_list::{GenericRef, GenericRefList, ManagedList, Named, StringList},
-    sorting::Sorting,
+    managed_list::{GenericRef, GenericRefList, ManagedList, Named, NewStringStatus, StringList},
+    relationship::MyRelationships,
 };
 
-#[allow(dead_code)]
-#[derive(Clone)]
-pub struct DisplayLine {
-    fields: Vec<String>,
-    id: DataIndex,
-}
-
-#[allow(dead_code)]
-impl DisplayLine {
-    pub fn id(&self) -> &String {
-        assert!(!self.fields.is_empty()); // this should be impossible
-        &self.fields[0]
-    }
-
-    pub fn num_fields(&self) -> usize {
-        self.fields.len()
-    }
-
-    pub fn field(&self, number: usize) -> &str {
-        self.fields
-            .get(number)
-            .expect("unable to find promised index in display line")
-            .as_ref()
-    }
-
-    pub fn field_iter(&self) -> Iter<'_, String> {
-        self.fields.iter()
-    }
-}
-
-fn displayline_from_item_ref<T: Named + Clone>(item: &T, index: &GenericRef<T>) -> DisplayLine {
-    DisplayLine {
-        fields: item.display_fields(),
-        id: index.data_index(),
-    }
-}
-
 // -------------------
-#[derive(Clone)]
-pub struct DisplayTable {
-    lines: Vec<DisplayLine>,
-    columns: Vec<ColumnInfo>,
-    sorting: Sorting,
-}
-
-#[allow(dead_code)]
-impl DisplayTable {
-    pub fn lines_iter(&self) -> impl Iterator<Item = &DisplayLine> {
-        self.lines.iter()
-    }
-
-    pub fn line(&self, index: usize) -> &DisplayLine {
-        assert!(index < self.lines.len());
-        &self.lines[index]
-    }
-
-    pub fn lines_len(&self) -> usize {
-        self.lines.len()
-    }
-
-    pub fn column_definitions_iter(&self) -> impl Iterator<Item = Column> {
-        self.columns.iter().map(|i| {
-            use ColumnWidth::*;
-            match i.width {
-                Extended => Column::remainder(),
-                Standard(width) => Column::auto().at_least(width),
-            }
-        })
-    }
-
-    pub fn headings_iter(&self) -> impl Iterator<Item = RichText> {
-        self.columns.iter().enumerate().map(|(i, c)| {
-            let heading_text = c.heading.clone();
-            if i == self.sorting.sort_field() {
-                heading_text.underline()
-            } else {
-                heading_text
-            }
-        })
-    }
-
-    pub fn number_columns(&self) -> usize {
-        self.columns.len()
-    }
-
-    pub fn sorting(&self) -> &Sorting {
-        &self.sorting
-    }
+// DisplayTable columns, as egui sees them
+
+pub fn column_definitions_iter(table: &DisplayTable) -> impl Iterator<Item = Column> + '_ {
+    table.column_widths_iter().map(|width| {
+        use ColumnWidth::*;
+        match width {
+            Extended => Column::remainder(),
+            Standard(width) => Column::auto().at_least(width),
+        }
+    })
 }
 
-const MIN_COL_WIDTH: f32 = 40.0;
-const NAME_COL_WIDTH: f32 = 120.0;
-
-impl<T: Named + Clone> From<&ManagedList<T>> for DisplayTable {
-    fn from(list: &ManagedList<T>) -> Self {
-        let item_list = list.item_ref_list();
-        let mut lines: Vec<DisplayLine> = item_list
-            .iter()
-            .map(|(index, item)| displayline_from_item_ref(*item, index))
-            .collect();
-        let sorting = list.get_sorting();
-        let sort_fn = |a: &DisplayLine, b: &DisplayLine| {
-            a.fields[sorting.sort_field()].cmp(&b.fields[sorting.sort_field()])
-        };
-        lines.sort_by(sort_fn);
-        if sorting.sort_reversed() {
-            lines.reverse();
-        }
-        let headings = T::display_headings();
-        assert!(!headings.is_empty());
-        let last_heading = headings.len() - 1;
-        let columns = headings
-            .into_iter()
-            .enumerate()
-            .map(|(i, h)| {
-                use ColumnWidth::*;
-                ColumnInfo {
-                    width: if i == last_heading {
-                        Extended
-                    } else if i == 0 {
-                        Standard(NAME_COL_WIDTH)
-                    } else {
-                        Standard(MIN_COL_WIDTH)
-                    },
-                    heading: h,
-                }
-            })
-            .collect();
-        DisplayTable {
-            lines,
-            columns,
-            sorting,
+/// The column headings, with the sorted column underlined
+pub fn headings_iter(table: &DisplayTable) -> impl Iterator<Item = RichText> + '_ {
+    table.headings_iter().map(|(heading, sorted)| {
+        let heading_text = RichText::new(heading);
+        if sorted {
+            heading_text.underline()
+        } else {
+            heading_text
         }
-    }
+    })
 }
 
 // -------------------
@@ -160,6 +50,7 @@ pub struct ShowEditInfo<'a> {
     create_new: bool,
     // could have reference lists??
     app_data: &'a AppData,
+    relationships: Option<&'a RefCell<MyRelationships>>,
 }
 
 #[allow(dead_code)]
@@ -175,6 +66,15 @@ impl<'a> ShowEditInfo<'a> {
             differs_from,
             create_new,
             app_data,
+            relationships: None,
+        }
+    }
+
+    /// Adds the working copy of the item's relationships, for items which can have them
+    pub fn with_relationships(self, relationships: &'a RefCell<MyRelationships>) -> Self {
+        ShowEditInfo {
+            relationships: Some(relationships),
+            ..self
         }
     }
 
@@ -197,6 +97,17 @@ impl<'a> ShowEditInfo<'a> {
     pub fn app_data(&self) -> &AppData {
         self.app_data
     }
+
+    /// Shows the relationships section, if there are relationships to edit
+    pub fn show_edit_relationships(&self, ui: &mut Ui) {
+        if let Some(relationships) = self.relationships
+            && !self.create_new
+        {
+            ui.add_space(FIELD_VERTICAL_SPACE);
+            ui.label(RichText::new(fl!("relationships_heading")).small().weak());
+            relationships.borrow_mut().show_edit(self.app_data, ui);
+        }
+    }
 }
 
 // ----------------------
@@ -249,6 +160,16 @@ pub fn show_edit_frame(
                         result = Some(EditResult::Submit);
                     }
                 }
+                if !item_info.create_new() {
+                    ui.add_space(60.0);
+                    if ui
+                        .button(RichText::new(fl!("edit_delete")).color(Color32::DARK_RED))
+                        .clicked()
+                    {
+                        info!("delete {debug_name} requested");
+                        result = Some(EditResult::Delete);
+                    }
+                }
             });
             ui.add_space(HEAD_SPACE);
 
@@ -496,29 +417,3 @@ pub fn show_edit_stringlist_italics(name: &str, this_list: &mut StringList, ui:
         }
     });
 }
-
-// ----------------------
-// NewItemStatus
-#[derive(Debug, Clone, Default, PartialEq)]
-pub enum NewStringStatus {
-    #[default]
-    NoItem,
-    Requested, // could include a timer here of some sort
-    Showing(String),
-}
-
-// -----------------------
-// Column Width
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum ColumnWidth {
-    Extended,
-    Standard(f32),
-}
-
-// -----------------------
-// Column Info
-#[derive(Debug, Clone)]
-pub struct ColumnInfo {
-    width: ColumnWidth,
-    heading: RichText,
-}
@@ -1,19 +1,25 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Ok, Result, anyhow};
-use log::info;
+use log::{debug, info};
 use serde::{Deserialize, Serialize};
 
-use crate::app::{load_from_pot, save_to_pot};
+use crate::persistence::{load_from_pot, save_to_pot};
 
 const SETTINGS_NAME: &str = "settings";
 const SETTINGS_EXTENSION: &str = "pot";
+/// How many save files are remembered in the recent list
+const RECENT_FILES: usize = 8;
 
 // TODO: Should include settings panel
 
 #[derive(Debug, Clone)]
 pub struct AppSettings {
     theme: eframe::egui::Theme,
+    /// The save files most recently loaded or saved, the latest first
+    recent_files: Vec<PathBuf>,
+    /// Whether the latest of the recent files is loaded when the app starts
+    reopen_last: bool,
 }
 
 // todo: load on start
@@ -24,11 +30,45 @@ impl AppSettings {
         self.theme
     }
 
+    pub fn recent_files(&self) -> &[PathBuf] {
+        &self.recent_files
+    }
+
+    /// Puts the file at the top of the recent list, returning true if the list changed
+    pub fn add_recent(&mut self, file_path: &Path) -> bool {
+        if self.recent_files.first().is_some_and(|f| f == file_path) {
+            return false;
+        }
+        self.recent_files.retain(|f| f != file_path);
+        self.recent_files.insert(0, file_path.to_path_buf());
+        self.recent_files.truncate(RECENT_FILES);
+        true
+    }
+
+    pub fn clear_recent(&mut self) {
+        self.recent_files.clear();
+    }
+
+    /// Drops any recent files which no longer exist, returning them
+    pub fn prune_recent(&mut self) -> Vec<PathBuf> {
+        let (kept, missing) = self.recent_files.drain(..).partition(|f| f.is_file());
+        self.recent_files = kept;
+        missing
+    }
+
+    pub fn reopen_last(&self) -> bool {
+        self.reopen_last
+    }
+
+    pub fn set_reopen_last(&mut self, reopen_last: bool) {
+        self.reopen_last = reopen_last;
+    }
+
     pub fn save_to_file(&self, config_path: &Path) -> anyhow::Result<()> {
         let file_path = config_path
             .with_file_name(SETTINGS_NAME)
             .with_extension(SETTINGS_EXTENSION);
-        let settings: SaveSettings1 = self.into();
+        let settings: SaveSettings2 = self.into();
         info!("Saving settings to file in {}", config_path.display());
         save_to_pot(&file_path, &settings)
     }
@@ -47,13 +87,25 @@ impl Default for AppSettings {
     fn default() -> Self {
         AppSettings {
             theme: eframe::egui::Theme::Light,
+            recent_files: Vec::new(),
+            reopen_last: false,
         }
     }
 }
 
 fn load_settings(file_path: &Path) -> Result<AppSettings> {
-    // load save settings 1
-    let data = load_from_pot::<SaveSettings1>(file_path)?;
+    // the settings file has no header of its own, so try the latest version first
+    let data = match load_from_pot::<SaveSettings2>(file_path) {
+        Result::Ok(data) => data,
+        Err(err) => {
+            debug!("settings are not version {SAVE2_VERSION}: {err}");
+            let old = load_from_pot::<SaveSettings1>(file_path)?;
+            if !old.validate() {
+                return Err(anyhow!("unable to validate saved settings"));
+            }
+            old.into()
+        }
+    };
     if data.validate() {
         // convert to AppSettings
         let ret = data.into();
@@ -79,19 +131,53 @@ impl SaveSettings1 {
     }
 }
 
-impl From<SaveSettings1> for AppSettings {
+impl From<SaveSettings1> for SaveSettings2 {
     fn from(value: SaveSettings1) -> Self {
+        SaveSettings2 {
+            save_version: SAVE2_VERSION,
+            theme: value.theme,
+            recent_files: Vec::new(),
+            reopen_last: false,
+        }
+    }
+}
+
+// ====================
+// SaveSettings2
+const SAVE2_VERSION: u16 = 2;
+
+// As version 1, with the recent files
+#[derive(Debug, Serialize, Deserialize)]
+struct SaveSettings2 {
+    save_version: u16,
+    theme: Theme,
+    recent_files: Vec<PathBuf>,
+    reopen_last: bool,
+}
+
+impl SaveSettings2 {
+    fn validate(&self) -> bool {
+        self.save_version == SAVE2_VERSION
+    }
+}
+
+impl From<SaveSettings2> for AppSettings {
+    fn from(value: SaveSettings2) -> Self {
         AppSettings {
             theme: value.theme.into(),
+            recent_files: value.recent_files,
+            reopen_last: value.reopen_last,
         }
     }
 }
 
-impl From<&AppSettings> for SaveSettings1 {
+impl From<&AppSettings> for SaveSettings2 {
     fn from(value: &AppSettings) -> Self {
-        SaveSettings1 {
-            save_version: SAVE1_VERSION,
+        SaveSettings2 {
+            save_version: SAVE2_VERSION,
             theme: value.theme.into(),
+            recent_files: value.recent_files.clone(),
+            reopen_last: value.reopen_last,
         }
     }
 }
@@ -123,3 +209,60 @@ impl From<Theme> for eframe::egui::Theme {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use eframe::egui::Theme;
+
+    use crate::{
+        app_settings::{AppSettings, RECENT_FILES, SAVE1_VERSION, SaveSettings1},
+        persistence::{save_to_pot, test_dir},
+    };
+
+    #[test]
+    fn recent_files_are_kept_latest_first_and_pruned() {
+        let config_dir = test_dir("settings");
+        fs::create_dir_all(&config_dir).unwrap();
+        let files: Vec<_> = (0..=RECENT_FILES)
+            .map(|i| config_dir.join(format!("campaign{i}.bfsav")))
+            .collect();
+        for file in &files {
+            fs::write(file, b"").unwrap();
+        }
+
+        let mut settings = AppSettings::default();
+        for file in &files {
+            assert!(settings.add_recent(file));
+        }
+        assert!(settings.add_recent(&files[3]));
+        assert!(!settings.add_recent(&files[3]));
+        assert_eq!(settings.recent_files().len(), RECENT_FILES);
+        assert_eq!(settings.recent_files()[0], files[3]);
+        assert!(!settings.recent_files().contains(&files[0]));
+
+        fs::remove_file(&files[5]).unwrap();
+        assert_eq!(settings.prune_recent(), vec![files[5].clone()]);
+        assert_eq!(settings.recent_files().len(), RECENT_FILES - 1);
+
+        // the settings file sits beside the config folder
+        settings.set_reopen_last(true);
+        let config_path = config_dir.join("config");
+        settings.save_to_file(&config_path).unwrap();
+        let loaded = AppSettings::load_from_file(&config_path).unwrap();
+        assert!(loaded.reopen_last());
+        assert_eq!(loaded.recent_files(), settings.recent_files());
+
+        // older settings have no recent files
+        let old = SaveSettings1 {
+            save_version: SAVE1_VERSION,
+            theme: Theme::Dark.into(),
+        };
+        save_to_pot(&config_dir.join("settings.pot"), &old).unwrap();
+        let loaded = AppSettings::load_from_file(&config_path).unwrap();
+        assert!(loaded.recent_files().is_empty());
+        assert!(!loaded.reopen_last());
+        fs::remove_dir_all(&config_dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    fs::{self, create_dir_all},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use log::{debug, info};
+
+/// Backups are kept in this directory, beside the save file
+pub const BACKUP_DIR: &str = "backups";
+
+/// How many backups are kept of each save file
+pub const BACKUP_COUNT: usize = 5;
+
+/// The directory which holds the backups of a save file
+pub fn backup_dir(save_path: &Path) -> PathBuf {
+    save_path
+        .parent()
+        .map_or_else(|| PathBuf::from(BACKUP_DIR), |dir| dir.join(BACKUP_DIR))
+}
+
+/// Copies the current save file to a timestamped backup, before it is replaced,
+/// and removes the oldest backups beyond [`BACKUP_COUNT`]
+pub fn make_backup(save_path: &Path) -> Result<Option<PathBuf>> {
+    if !save_path.exists() {
+        return Ok(None);
+    }
+    let (Some(stem), Some(extension)) = (save_path.file_stem(), save_path.extension()) else {
+        return Ok(None);
+    };
+
+    let dir = backup_dir(save_path);
+    create_dir_all(&dir)?;
+    let timestamp = backup_timestamp(SystemTime::now());
+    let backup_path = dir.join(format!(
+        "{}.{timestamp}.{}",
+        stem.to_string_lossy(),
+        extension.to_string_lossy()
+    ));
+    fs::copy(save_path, &backup_path)?;
+    info!("backed up save to {}", backup_path.to_string_lossy());
+
+    let backups = list_backups(save_path);
+    if backups.len() > BACKUP_COUNT {
+        for old in &backups[BACKUP_COUNT..] {
+            debug!("removing old backup {}", old.to_string_lossy());
+            fs::remove_file(old)?;
+        }
+    }
+    Ok(Some(backup_path))
+}
+
+/// The backups of a save file, newest first
+pub fn list_backups(save_path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(backup_dir(save_path)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension() == save_path.extension()
+                && original_of(path).as_deref() == Some(save_path)
+        })
+        .collect();
+    // the timestamps sort in time order
+    backups.sort();
+    backups.reverse();
+    backups
+}
+
+/// The save file that a backup was taken from, or None if the file is not a backup
+pub fn original_of(backup_path: &Path) -> Option<PathBuf> {
+    let backup_dir = backup_path.parent()?;
+    if backup_dir.file_name()? != BACKUP_DIR {
+        return None;
+    }
+    let stem = backup_path.file_stem()?.to_string_lossy().to_string();
+    let (original_stem, timestamp) = stem.rsplit_once('.')?;
+    if !is_backup_timestamp(timestamp) {
+        return None;
+    }
+    let save_dir = backup_dir.parent()?;
+    let mut original = save_dir.join(original_stem);
+    if let Some(extension) = backup_path.extension() {
+        original.set_extension(extension);
+    }
+    Some(original)
+}
+
+// colons and dots are not welcome in file names
+fn backup_timestamp(time: SystemTime) -> String {
+    humantime::format_rfc3339_millis(time)
+        .to_string()
+        .replace([':', '.'], "-")
+}
+
+/// Whether the text is a timestamp as written by [`backup_timestamp`]
+fn is_backup_timestamp(text: &str) -> bool {
+    // put back the colons and the dot, at the places they were taken from
+    let mut rfc3339: Vec<char> = text.chars().collect();
+    for (index, separator) in [(13, ':'), (16, ':'), (19, '.')] {
+        match rfc3339.get_mut(index) {
+            Some(c) if *c == '-' => *c = separator,
+            _ => return false,
+        }
+    }
+    humantime::parse_rfc3339(&rfc3339.into_iter().collect::<String>()).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        backup::{BACKUP_COUNT, BACKUP_DIR, list_backups, make_backup, original_of},
+        persistence::test_dir,
+    };
+
+    #[test]
+    fn backups_are_kept_and_pruned() {
+        let dir = test_dir("backup");
+        fs::create_dir_all(&dir).unwrap();
+        let save = dir.join("campaign.bfsav");
+        assert_eq!(make_backup(&save).unwrap(), None);
+
+        for n in 0..BACKUP_COUNT + 2 {
+            fs::write(&save, [n as u8]).unwrap();
+            let backup = make_backup(&save).unwrap().unwrap();
+            assert_eq!(original_of(&backup).unwrap(), save);
+            // the millisecond timestamps must differ
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+
+        let backups = list_backups(&save);
+        assert_eq!(backups.len(), BACKUP_COUNT);
+        assert_eq!(fs::read(&backups[0]).unwrap(), [(BACKUP_COUNT + 1) as u8]);
+
+        // only files in the backup folder, named with a timestamp, are backups
+        assert_eq!(original_of(&dir.join("my.campaign.bfsav")), None);
+        assert_eq!(
+            original_of(&dir.join(BACKUP_DIR).join("my.campaign.bfsav")),
+            None
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,534 @@
+use std::fmt::Display;
+
+#[cfg(feature = "gui")]
+use eframe::egui::{Color32, ComboBox, RichText, TextEdit, TextStyle, Ui};
+use enum_iterator::Sequence;
+#[cfg(feature = "gui")]
+use enum_iterator::all;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
+use crate::{
+    app::EditResult,
+    app_display::{
+        DESCRIPTION_ROWS, FIELD_HORIZONTAL_SPACE, FIELD_VERTICAL_SPACE, ShowEdit, ShowEditInfo,
+        show_edit_frame, show_edit_item, show_edit_list,
+    },
+};
+use crate::{
+    app_data::DataIndex,
+    clock::Clock,
+    display::{ItemPage, MainView},
+    entity_id::EntityId,
+    localize::fl,
+    managed_list::{
+        CampaignClockRef, DistrictRef, DistrictRefList, FactionRef, FactionRefList, GenericRef,
+        Named, PersonRef, PersonRefList,
+    },
+};
+
+/// A clock which belongs to the campaign, rather than to a faction
+#[allow(dead_code)]
+#[derive(Default, Clone, PartialEq)]
+pub struct CampaignClock {
+    id: EntityId,
+    name: String,
+    kind: ClockKind,
+    state: ClockState,
+    clock: Clock,
+    description: String,
+    factions: FactionRefList,
+    persons: PersonRefList,
+    districts: DistrictRefList,
+    next: Option<CampaignClockRef>,  // started when this one finishes
+    rival: Option<CampaignClockRef>, // racing this one; the first to finish stops the other
+}
+
+#[allow(dead_code)]
+impl CampaignClock {
+    pub fn new(name: &str, clock: Clock) -> Self {
+        CampaignClock {
+            name: name.to_string(),
+            clock,
+            ..Default::default()
+        }
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn clock_mut(&mut self) -> &mut Clock {
+        &mut self.clock
+    }
+
+    pub fn state(&self) -> ClockState {
+        self.state
+    }
+
+    pub fn set_state(&mut self, state: ClockState) {
+        self.state = state;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.clock.is_finished()
+    }
+
+    pub fn next(&self) -> Option<&CampaignClockRef> {
+        self.next.as_ref()
+    }
+
+    pub fn rival(&self) -> Option<&CampaignClockRef> {
+        self.rival.as_ref()
+    }
+
+    pub fn set_links(
+        &mut self,
+        factions: Vec<FactionRef>,
+        persons: Vec<PersonRef>,
+        districts: Vec<DistrictRef>,
+    ) {
+        if !self.factions.list().is_empty()
+            || !self.persons.list().is_empty()
+            || !self.districts.list().is_empty()
+        {
+            warn!("replacing links of {} when they are not empty", self.name);
+        }
+        self.factions = FactionRefList::from_list(factions);
+        self.persons = PersonRefList::from_list(persons);
+        self.districts = DistrictRefList::from_list(districts);
+    }
+
+    pub fn set_next(&mut self, next: Option<CampaignClockRef>) {
+        self.next = next;
+    }
+
+    pub fn set_rival(&mut self, rival: Option<CampaignClockRef>) {
+        self.rival = rival;
+    }
+
+    /// Returns the headings of every field which refers to the target
+    pub fn reference_fields(&self, target: DataIndex) -> Vec<String> {
+        let mut fields = Vec::new();
+        if self.factions.contains_data_index(target) {
+            fields.push(fl!("main_factions"));
+        }
+        if self.persons.contains_data_index(target) {
+            fields.push(fl!("main_persons"));
+        }
+        if self.districts.contains_data_index(target) {
+            fields.push(fl!("main_districts"));
+        }
+        if is_ref_to(&self.next, target) {
+            fields.push(fl!("clock_next_heading"));
+        }
+        if is_ref_to(&self.rival, target) {
+            fields.push(fl!("clock_rival_heading"));
+        }
+        fields
+    }
+
+    /// Removes every reference to the target, returning whether anything changed
+    pub fn clear_references(&mut self, target: DataIndex) -> bool {
+        let mut changed = self.factions.remove_data_index(target);
+        changed |= self.persons.remove_data_index(target);
+        changed |= self.districts.remove_data_index(target);
+        if is_ref_to(&self.next, target) {
+            self.next = None;
+            changed = true;
+        }
+        if is_ref_to(&self.rival, target) {
+            self.rival = None;
+            changed = true;
+        }
+        changed
+    }
+
+    /// Writes every field, with the clocks and items it is linked to as links to their pages
+    pub fn write_page(&self, page: &mut impl ItemPage) {
+        let (ticked, parts) = self.clock.status();
+        page.field(&fl!("clock_kind_heading"), &self.kind.to_string());
+        page.field(&fl!("clock_progress_heading"), &format!("{ticked}/{parts}"));
+        page.field(
+            &fl!("clock_state_heading"),
+            &if self.is_finished() {
+                fl!("clock_finished")
+            } else {
+                self.state.to_string()
+            },
+        );
+        page.link_field(
+            &fl!("clock_next_heading"),
+            MainView::Clocks,
+            self.next.as_ref().and_then(|r| r.name()).as_deref(),
+        );
+        page.link_field(
+            &fl!("clock_rival_heading"),
+            MainView::Clocks,
+            self.rival.as_ref().and_then(|r| r.name()).as_deref(),
+        );
+        let factions = ref_names(self.factions.list());
+        let persons = ref_names(self.persons.list());
+        let districts = ref_names(self.districts.list());
+        page.links_field(
+            &MainView::Factions.to_string(),
+            MainView::Factions,
+            &factions,
+        );
+        page.links_field(&MainView::Persons.to_string(), MainView::Persons, &persons);
+        page.links_field(
+            &MainView::Districts.to_string(),
+            MainView::Districts,
+            &districts,
+        );
+        page.section(&fl!("description_heading"), &self.description);
+    }
+
+    /// The names of everything this clock is linked to
+    fn link_names(&self) -> String {
+        let faction_names = self.factions.list().iter().filter_map(|r| r.name());
+        let person_names = self.persons.list().iter().filter_map(|r| r.name());
+        let district_names = self.districts.list().iter().filter_map(|r| r.name());
+        faction_names
+            .chain(person_names)
+            .chain(district_names)
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+fn ref_names<T: Clone + Named>(list: &[GenericRef<T>]) -> Vec<String> {
+    list.iter().filter_map(|r| r.name()).collect()
+}
+
+fn is_ref_to<T: Clone + Named>(item: &Option<GenericRef<T>>, target: DataIndex) -> bool {
+    item.as_ref().is_some_and(|r| r.is_data_index(target))
+}
+
+// ---------------------------
+impl Named for CampaignClock {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: EntityId) {
+        self.id = id;
+    }
+
+    fn display_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn make_data_index(index: usize) -> DataIndex {
+        DataIndex::ClockIndex(index)
+    }
+
+    fn fetch_data_index(index: DataIndex) -> Option<usize> {
+        match index {
+            DataIndex::ClockIndex(ind) => Some(ind),
+            _ => None,
+        }
+    }
+
+    // MUST agree with display_headings
+    fn display_fields(&self) -> Vec<String> {
+        let (ticked, parts) = self.clock.status();
+        vec![
+            self.name.clone(),
+            self.kind.to_string(),
+            format!("{ticked}/{parts}"),
+            if self.is_finished() {
+                fl!("clock_finished")
+            } else {
+                self.state.to_string()
+            },
+            self.next
+                .as_ref()
+                .and_then(|r| r.name())
+                .unwrap_or_default(),
+            self.rival
+                .as_ref()
+                .and_then(|r| r.name())
+                .unwrap_or_default(),
+            self.link_names(),
+        ]
+    }
+
+    // MUST agree with display_fields
+    fn display_headings() -> Vec<String> {
+        vec![
+            fl!("name_heading"),
+            fl!("clock_kind_heading"),
+            fl!("clock_progress_heading"),
+            fl!("clock_state_heading"),
+            fl!("clock_next_heading"),
+            fl!("clock_rival_heading"),
+            fl!("clock_links_heading"),
+        ]
+    }
+}
+
+#[cfg(feature = "gui")]
+impl ShowEdit for CampaignClock {
+    fn show_edit(&mut self, ui: &mut Ui, item_info: ShowEditInfo) -> Option<EditResult> {
+        show_edit_frame(ui, fl!("main_item_clock"), "clock", item_info, |ui| {
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    ui.vertical(|ui| {
+                        ui.label(RichText::new(fl!("name_heading")).small().weak());
+                        ui.horizontal(|ui| {
+                            ui.add(TextEdit::singleline(&mut self.name).font(TextStyle::Heading));
+                            if item_info.name_collision() {
+                                let no_text = RichText::new("X").color(Color32::RED).strong();
+                                ui.label(no_text);
+                            }
+                        });
+                    });
+
+                    ui.add_space(FIELD_HORIZONTAL_SPACE);
+                    ui.vertical(|ui| {
+                        ui.label(RichText::new(fl!("clock_kind_heading")).small().weak());
+                        self.kind.show_edit("kind", ui);
+                    });
+
+                    ui.add_space(FIELD_HORIZONTAL_SPACE);
+                    ui.vertical(|ui| {
+                        ui.label(RichText::new(fl!("clock_state_heading")).small().weak());
+                        self.state.show_edit("state", ui);
+                    });
+                });
+
+                ui.add_space(FIELD_VERTICAL_SPACE);
+                ui.horizontal(|ui| {
+                    // only a running clock can be ticked
+                    let running = self.state == ClockState::Running;
+                    ui.add_enabled_ui(running, |ui| self.clock.show_pie(ui));
+                    self.clock.show_edit_size("parts", ui);
+                });
+
+                ui.add_space(FIELD_VERTICAL_SPACE);
+                ui.label(RichText::new(fl!("description_heading")).small().weak());
+                ui.add(
+                    TextEdit::multiline(&mut self.description)
+                        .desired_width(ui.available_width())
+                        .desired_rows(DESCRIPTION_ROWS),
+                );
+
+                ui.add_space(FIELD_VERTICAL_SPACE);
+                ui.horizontal(|ui| {
+                    ui.vertical(|ui| {
+                        ui.label(RichText::new(fl!("clock_next_heading")).small().weak());
+                        show_edit_item(
+                            "next",
+                            &mut self.next,
+                            item_info.app_data().clock_list(),
+                            ui,
+                        );
+                    });
+
+                    ui.add_space(FIELD_HORIZONTAL_SPACE);
+                    ui.vertical(|ui| {
+                        ui.label(RichText::new(fl!("clock_rival_heading")).small().weak());
+                        show_edit_item(
+                            "rival",
+                            &mut self.rival,
+                            item_info.app_data().clock_list(),
+                            ui,
+                        );
+                    });
+                });
+
+                ui.add_space(FIELD_VERTICAL_SPACE);
+                ui.label(RichText::new(fl!("main_factions")).small().weak());
+                show_edit_list(
+                    "factions",
+                    &mut self.factions,
+                    item_info.app_data().faction_list(),
+                    ui,
+                );
+
+                ui.add_space(FIELD_VERTICAL_SPACE);
+                ui.label(RichText::new(fl!("main_persons")).small().weak());
+                show_edit_list(
+                    "persons",
+                    &mut self.persons,
+                    item_info.app_data().person_list(),
+                    ui,
+                );
+
+                ui.add_space(FIELD_VERTICAL_SPACE);
+                ui.label(RichText::new(fl!("main_districts")).small().weak());
+                show_edit_list(
+                    "districts",
+                    &mut self.districts,
+                    item_info.app_data().district_list(),
+                    ui,
+                );
+            });
+        })
+    }
+}
+
+// -----------------------------
+// ClockKind
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence, Serialize, Deserialize)]
+pub enum ClockKind {
+    #[default]
+    Project,
+    Countdown,
+    Danger,
+    Other,
+}
+
+#[cfg(feature = "gui")]
+impl ClockKind {
+    pub fn show_edit(&mut self, name: &str, ui: &mut Ui) {
+        show_edit_choice(name, self, ui);
+    }
+}
+
+impl Display for ClockKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ClockKind::*;
+
+        write!(
+            f,
+            "{}",
+            match self {
+                Project => fl!("clock_kind_project"),
+                Countdown => fl!("clock_kind_countdown"),
+                Danger => fl!("clock_kind_danger"),
+                Other => fl!("clock_kind_other"),
+            }
+        )
+    }
+}
+
+// -----------------------------
+// ClockState
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence, Serialize, Deserialize)]
+pub enum ClockState {
+    #[default]
+    Running,
+    Waiting, // for another clock to finish
+    Stopped, // lost a race, or abandoned
+}
+
+#[cfg(feature = "gui")]
+impl ClockState {
+    pub fn show_edit(&mut self, name: &str, ui: &mut Ui) {
+        show_edit_choice(name, self, ui);
+    }
+}
+
+impl Display for ClockState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use ClockState::*;
+
+        write!(
+            f,
+            "{}",
+            match self {
+                Running => fl!("clock_state_running"),
+                Waiting => fl!("clock_state_waiting"),
+                Stopped => fl!("clock_state_stopped"),
+            }
+        )
+    }
+}
+
+#[cfg(feature = "gui")]
+fn show_edit_choice<T: Sequence + Display + PartialEq + Copy>(
+    name: &str,
+    value: &mut T,
+    ui: &mut Ui,
+) {
+    let choices: Vec<T> = all::<T>().collect();
+    let mut selected = choices.iter().position(|c| c == value).unwrap_or_default();
+    ComboBox::from_id_salt(name)
+        .show_index(ui, &mut selected, choices.len(), |i| choices[i].to_string());
+    *value = choices[selected];
+}
+
+// -----------------------------
+// Stored
+
+#[allow(dead_code)]
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CampaignClockStore {
+    pub id: EntityId,
+    name: String,
+    kind: ClockKind,
+    state: ClockState,
+    clock: Clock,
+    description: String,
+    pub factions: Vec<EntityId>,
+    pub persons: Vec<EntityId>,
+    pub districts: Vec<EntityId>,
+    pub next: Option<EntityId>,
+    pub rival: Option<EntityId>,
+}
+
+impl CampaignClockStore {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl From<&CampaignClock> for CampaignClockStore {
+    fn from(from_clock: &CampaignClock) -> Self {
+        CampaignClockStore {
+            id: from_clock.id,
+            name: from_clock.name.clone(),
+            kind: from_clock.kind,
+            state: from_clock.state,
+            clock: from_clock.clock.clone(),
+            description: from_clock.description.clone(),
+            factions: from_clock
+                .factions
+                .list()
+                .iter()
+                .filter_map(|i| i.id())
+                .collect(),
+            persons: from_clock
+                .persons
+                .list()
+                .iter()
+                .filter_map(|i| i.id())
+                .collect(),
+            districts: from_clock
+                .districts
+                .list()
+                .iter()
+                .filter_map(|i| i.id())
+                .collect(),
+            next: from_clock.next.as_ref().and_then(|i| i.id()),
+            rival: from_clock.rival.as_ref().and_then(|i| i.id()),
+        }
+    }
+}
+
+impl From<&CampaignClockStore> for CampaignClock {
+    fn from(from_store: &CampaignClockStore) -> Self {
+        CampaignClock {
+            id: from_store.id,
+            name: from_store.name.clone(),
+            kind: from_store.kind,
+            state: from_store.state,
+            clock: from_store.clock.clone(),
+            description: from_store.description.clone(),
+            factions: FactionRefList::default(), // added after creation
+            persons: PersonRefList::default(),   // added after creation
+            districts: DistrictRefList::default(), // added after creation
+            next: None,                          // added after creation
+            rival: None,                         // added after creation
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use std::{fmt::Write as _, path::Path};
+
+use anyhow::Result;
+#[cfg(feature = "gui")]
+use eframe::egui::{CollapsingHeader, Color32, RichText, ScrollArea, Ui};
+use log::info;
+use serde_json::Value;
+
+#[cfg(feature = "gui")]
+use crate::{app::UI_PADDING, localize::fl};
+use crate::{
+    display::MainView,
+    export_format::{ItemKind, field_text, items, join_list, split_list},
+    markdown::{MARKDOWN_EXTENSION, escape},
+    persistence::save_to_text,
+};
+
+/// The kinds of item which are compared, in the order they are listed
+const DIFF_KINDS: [(ItemKind, MainView); 3] = [
+    (ItemKind::Faction, MainView::Factions),
+    (ItemKind::Person, MainView::Persons),
+    (ItemKind::District, MainView::Districts),
+];
+
+/// A field which is not the same in both campaigns, as text with references by name
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+    /// Lists, such as turf or allies, are compared entry by entry
+    list: bool,
+}
+
+impl FieldChange {
+    /// The entries of a list which are only in the later campaign
+    pub fn added(&self) -> Vec<String> {
+        let before = split_list(&self.before);
+        split_list(&self.after)
+            .into_iter()
+            .filter(|a| !before.contains(a))
+            .collect()
+    }
+
+    /// The entries of a list which are only in the earlier campaign
+    pub fn removed(&self) -> Vec<String> {
+        let after = split_list(&self.after);
+        split_list(&self.before)
+            .into_iter()
+            .filter(|b| !after.contains(b))
+            .collect()
+    }
+
+    /// A line describing the change, such as "Tier2 → Tier3" or "+ Crow's Foot; − Dunslough"
+    pub fn summary(&self) -> String {
+        if self.list {
+            let added = self.added();
+            let removed = self.removed();
+            let mut parts = Vec::new();
+            if !added.is_empty() {
+                parts.push(format!("+ {}", join_list(added)));
+            }
+            if !removed.is_empty() {
+                parts.push(format!("− {}", join_list(removed)));
+            }
+            if !parts.is_empty() {
+                return parts.join("; ");
+            }
+            // only the order changed
+        }
+        format!("{} → {}", quoted(&self.before), quoted(&self.after))
+    }
+}
+
+fn quoted(text: &str) -> String {
+    if text.is_empty() {
+        "(none)".to_string()
+    } else if text.contains('\n') {
+        format!("\"{}…\"", text.lines().next().unwrap_or_default())
+    } else {
+        format!("\"{text}\"")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemChange {
+    Added,
+    Removed,
+    Modified(Vec<FieldChange>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemDiff {
+    pub kind: ItemKind,
+    /// The later name, when an item has been renamed
+    pub name: String,
+    pub change: ItemChange,
+}
+
+/// What changed between two campaigns, item by item.
+/// Items are matched by id, so a renamed item is modified rather than removed and added;
+/// anything left is matched by name, for campaigns which were imported rather than saved
+#[derive(Debug, Clone)]
+pub struct CampaignDiff {
+    before_title: String,
+    after_title: String,
+    items: Vec<ItemDiff>,
+}
+
+impl CampaignDiff {
+    /// Compares two campaigns in the export format
+    pub fn new(before_title: &str, before: &Value, after_title: &str, after: &Value) -> Self {
+        let mut diffs = Vec::new();
+        for (kind, _) in DIFF_KINDS {
+            let before_items = items(before, kind);
+            let after_items = items(after, kind);
+            let mut matched: Vec<Option<usize>> = vec![None; before_items.len()];
+            let mut taken = vec![false; after_items.len()];
+            for same in [
+                |a: &Value, b: &Value| a["id"] == b["id"],
+                |a: &Value, b: &Value| a["name"] == b["name"],
+            ] {
+                for (before_index, item) in before_items.iter().enumerate() {
+                    if matched[before_index].is_some() {
+                        continue;
+                    }
+                    let found = after_items
+                        .iter()
+                        .enumerate()
+                        .position(|(i, other)| !taken[i] && same(item, other));
+                    if let Some(after_index) = found {
+                        matched[before_index] = Some(after_index);
+                        taken[after_index] = true;
+                    }
+                }
+            }
+
+            let mut kind_diffs = Vec::new();
+            for (before_index, after_index) in matched.iter().enumerate() {
+                let change = match after_index {
+                    None => Some(ItemChange::Removed),
+                    Some(after_index) => {
+                        let changes =
+                            field_changes(kind, before, before_index, after, *after_index);
+                        (!changes.is_empty()).then_some(ItemChange::Modified(changes))
+                    }
+                };
+                if let Some(change) = change {
+                    let name = match after_index {
+                        Some(after_index) => field_text(after, kind, *after_index, "name"),
+                        None => field_text(before, kind, before_index, "name"),
+                    };
+                    kind_diffs.push(ItemDiff { kind, name, change });
+                }
+            }
+            for (after_index, _) in taken.iter().enumerate().filter(|(_, t)| !**t) {
+                kind_diffs.push(ItemDiff {
+                    kind,
+                    name: field_text(after, kind, after_index, "name"),
+                    change: ItemChange::Added,
+                });
+            }
+            kind_diffs.sort_by(|a, b| a.name.cmp(&b.name));
+            diffs.extend(kind_diffs);
+        }
+
+        CampaignDiff {
+            before_title: before_title.to_string(),
+            after_title: after_title.to_string(),
+            items: diffs,
+        }
+    }
+
+    pub fn items(&self) -> &[ItemDiff] {
+        &self.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// How many items were added, removed and modified
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let count = |f: fn(&ItemChange) -> bool| self.items.iter().filter(|i| f(&i.change)).count();
+        (
+            count(|c| *c == ItemChange::Added),
+            count(|c| *c == ItemChange::Removed),
+            count(|c| matches!(c, ItemChange::Modified(_))),
+        )
+    }
+
+    /// The changes as a Markdown document, with a section for each kind of item
+    pub fn to_markdown(&self) -> String {
+        let mut text = format!(
+            "# {} → {}\n",
+            escape(&self.before_title),
+            escape(&self.after_title)
+        );
+        if self.is_empty() {
+            text.push_str("\nNo factions, people or districts have changed.\n");
+            return text;
+        }
+        for (kind, view) in DIFF_KINDS {
+            let kind_items: Vec<&ItemDiff> = self.items.iter().filter(|i| i.kind == kind).collect();
+            if kind_items.is_empty() {
+                continue;
+            }
+            let _ = writeln!(text, "\n## {view}\n");
+            for item in kind_items {
+                let name = escape(&item.name);
+                match &item.change {
+                    ItemChange::Added => {
+                        let _ = writeln!(text, "- **{name}** was added");
+                    }
+                    ItemChange::Removed => {
+                        let _ = writeln!(text, "- **{name}** was removed");
+                    }
+                    ItemChange::Modified(changes) => {
+                        let _ = writeln!(text, "- **{name}** was changed");
+                        for change in changes {
+                            let _ = writeln!(
+                                text,
+                                "    - {}: {}",
+                                change.field,
+                                escape(&change.summary())
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        text
+    }
+
+    pub fn export_markdown(&self, path: &Path) -> Result<()> {
+        save_to_text(
+            &path.with_extension(MARKDOWN_EXTENSION),
+            &self.to_markdown(),
+        )?;
+        info!("exported campaign changes to {}", path.to_string_lossy());
+        Ok(())
+    }
+}
+
+/// Every field of the items which differs, apart from the id
+fn field_changes(
+    kind: ItemKind,
+    before: &Value,
+    before_index: usize,
+    after: &Value,
+    after_index: usize,
+) -> Vec<FieldChange> {
+    let Some(fields) = items(after, kind)[after_index].as_object() else {
+        return Vec::new();
+    };
+    fields
+        .iter()
+        .filter(|(field, _)| *field != "id")
+        .filter_map(|(field, value)| {
+            let before_text = field_text(before, kind, before_index, field);
+            let after_text = field_text(after, kind, after_index, field);
+            (before_text != after_text).then(|| FieldChange {
+                field: field.clone(),
+                before: before_text,
+                after: after_text,
+                list: value
+                    .as_array()
+                    .is_some_and(|list| list.iter().all(|v| !v.is_object())),
+            })
+        })
+        .collect()
+}
+
+#[cfg(feature = "gui")]
+impl CampaignDiff {
+    /// Lists the changes, with the fields of each modified item under it
+    pub fn show(&self, ui: &mut Ui) {
+        const LIST_HEIGHT: f32 = 400.0;
+
+        let (added, removed, modified) = self.counts();
+        ui.label(fl!(
+            "diff_between",
+            before = self.before_title.clone(),
+            after = self.after_title.clone()
+        ));
+        ui.label(fl!(
+            "diff_counts",
+            added = added,
+            removed = removed,
+            modified = modified
+        ));
+        ui.add_space(UI_PADDING);
+        if self.is_empty() {
+            ui.label(RichText::new(fl!("diff_none")).italics());
+            return;
+        }
+
+        ScrollArea::vertical()
+            .max_height(LIST_HEIGHT)
+            .show(ui, |ui| {
+                for (kind, view) in DIFF_KINDS {
+                    let kind_items: Vec<&ItemDiff> =
+                        self.items.iter().filter(|i| i.kind == kind).collect();
+                    if kind_items.is_empty() {
+                        continue;
+                    }
+                    CollapsingHeader::new(format!("{view} ({})", kind_items.len()))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for item in kind_items {
+                                match &item.change {
+                                    ItemChange::Added => {
+                                        ui.label(
+                                            RichText::new(fl!(
+                                                "diff_added",
+                                                name = item.name.clone()
+                                            ))
+                                            .color(Color32::LIGHT_GREEN),
+                                        );
+                                    }
+                                    ItemChange::Removed => {
+                                        ui.label(
+                                            RichText::new(fl!(
+                                                "diff_removed",
+                                                name = item.name.clone()
+                                            ))
+                                            .color(Color32::LIGHT_RED),
+                                        );
+                                    }
+                                    ItemChange::Modified(changes) => {
+                                        ui.label(
+                                            RichText::new(fl!(
+                                                "diff_modified",
+                                                name = item.name.clone()
+                                            ))
+                                            .strong(),
+                                        );
+                                        for change in changes {
+                                            ui.label(format!(
+                                                "    {}: {}",
+                                                change.field,
+                                                change.summary()
+                                            ));
+                                        }
+                                    }
+                                }
+                            }
+                        });
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        app_data::AppData,
+        campaign_diff::{CampaignDiff, ItemChange},
+        export_format::{ItemKind, add_test_item, find_item, set_field},
+    };
+
+    #[test]
+    fn items_are_matched_by_id_and_fields_compared_by_name() {
+        let mut before = AppData::default().to_json_value().unwrap();
+        add_test_item(&mut before, ItemKind::District, "Crow's Foot", &[]);
+        add_test_item(&mut before, ItemKind::District, "Dunslough", &[]);
+        let fields = [("turf", "Dunslough"), ("tier", "Tier2")];
+        add_test_item(&mut before, ItemKind::Faction, "The Crows", &fields);
+        add_test_item(&mut before, ItemKind::Person, "Roric", &[]);
+
+        let mut after = before.clone();
+        let crows = find_item(&after, ItemKind::Faction, "The Crows").unwrap();
+        set_field(
+            &mut after,
+            ItemKind::Faction,
+            crows,
+            "name",
+            "The Crows of Doskvol",
+        )
+        .unwrap();
+        set_field(&mut after, ItemKind::Faction, crows, "tier", "Tier3").unwrap();
+        set_field(&mut after, ItemKind::Faction, crows, "turf", "Crow's Foot").unwrap();
+        after["persons"].as_array_mut().unwrap().clear();
+        add_test_item(&mut after, ItemKind::Person, "Lyssa", &[]);
+
+        let diff = CampaignDiff::new("before", &before, "after", &after);
+        assert_eq!(diff.counts(), (1, 1, 1));
+        let crows = &diff.items()[0];
+        assert_eq!(crows.name, "The Crows of Doskvol");
+        let ItemChange::Modified(changes) = &crows.change else {
+            panic!("the crows were renamed, not replaced");
+        };
+        let summaries: Vec<(&str, String)> = changes
+            .iter()
+            .map(|c| (c.field.as_str(), c.summary()))
+            .collect();
+        assert!(summaries.contains(&("tier", "\"Tier2\" → \"Tier3\"".to_string())));
+        assert!(summaries.contains(&("turf", "+ Crow's Foot; − Dunslough".to_string())));
+
+        let markdown = diff.to_markdown();
+        assert!(markdown.contains("**Lyssa** was added"));
+        assert!(markdown.contains("**Roric** was removed"));
+        assert!(CampaignDiff::new("a", &before, "b", &before).is_empty());
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Result, anyhow};
+#[cfg(feature = "gui")]
+use eframe::egui::{Grid, RichText, ScrollArea, Ui};
+use serde_json::Value;
+
+#[cfg(feature = "gui")]
+use crate::{app::UI_PADDING, localize::fl};
+use crate::{
+    entity_id::EntityId,
+    export_format::{ItemKind, items, reference_kind},
+    merge::{
+        CLOCKS, IdMap, MERGE_KINDS, add_clocks, add_relationships, link_clocks, map_ids, push,
+    },
+};
+
+/// The fields which are free text, and kept from both items when they differ
+const TEXT_FIELDS: [&str; 5] = ["description", "summary", "assets", "notes", "general"];
+
+/// How alike two names must be for them to be offered as the same item
+const SIMILAR_NAMES: f32 = 0.75;
+
+/// Two items, one from each campaign, which are taken to be the same
+#[derive(Debug, Clone)]
+pub struct ItemMatch {
+    pub kind: ItemKind,
+    pub first_name: String,
+    pub second_name: String,
+    first_index: usize,
+    second_index: usize,
+    /// How alike the names are, from 0 to 1; exact matches are 1
+    pub similarity: f32,
+    /// Exact matches are always used, similar ones only once they are confirmed
+    pub confirmed: bool,
+}
+
+impl ItemMatch {
+    pub fn is_exact(&self) -> bool {
+        self.first_name == self.second_name
+    }
+}
+
+/// Two campaigns to be combined into one, such as those of two crews in the same city.
+/// Items with the same name are unified, and so are items with similar names once confirmed.
+/// The first campaign is kept as it is, with anything new from the second added to it
+#[derive(Debug, Clone)]
+pub struct CampaignMerge {
+    first_title: String,
+    first: Value,
+    second_title: String,
+    second: Value,
+    matches: Vec<ItemMatch>,
+}
+
+impl CampaignMerge {
+    pub fn new(first_title: &str, first: Value, second_title: &str, second: Value) -> Self {
+        let mut matches = Vec::new();
+        for kind in MERGE_KINDS {
+            let first_names = names(&first, kind);
+            let second_names = names(&second, kind);
+            let mut first_taken = vec![false; first_names.len()];
+            let mut second_taken = vec![false; second_names.len()];
+
+            // every pair which is alike enough, used once each: the exact ones first, as
+            // names which only look alike can score as well, then the most alike
+            let mut pairs = Vec::new();
+            for (first_index, first_name) in first_names.iter().enumerate() {
+                for (second_index, second_name) in second_names.iter().enumerate() {
+                    let exact = first_name == second_name;
+                    let similarity = if exact {
+                        1.0
+                    } else {
+                        similarity(first_name, second_name)
+                    };
+                    if similarity >= SIMILAR_NAMES {
+                        pairs.push((exact, similarity, first_index, second_index));
+                    }
+                }
+            }
+            pairs.sort_by(|a, b| b.0.cmp(&a.0).then(b.1.total_cmp(&a.1)));
+            for (_, similarity, first_index, second_index) in pairs {
+                if first_taken[first_index] || second_taken[second_index] {
+                    continue;
+                }
+                first_taken[first_index] = true;
+                second_taken[second_index] = true;
+                let first_name = first_names[first_index].clone();
+                let second_name = second_names[second_index].clone();
+                matches.push(ItemMatch {
+                    kind,
+                    confirmed: first_name == second_name,
+                    first_name,
+                    second_name,
+                    first_index,
+                    second_index,
+                    similarity,
+                });
+            }
+        }
+        CampaignMerge {
+            first_title: first_title.to_string(),
+            first,
+            second_title: second_title.to_string(),
+            second,
+            matches,
+        }
+    }
+
+    pub fn matches(&self) -> &[ItemMatch] {
+        &self.matches
+    }
+
+    /// Confirms every similar match, as when there is no one to ask
+    pub fn confirm_all(&mut self) {
+        for item_match in &mut self.matches {
+            item_match.confirmed = true;
+        }
+    }
+
+    /// The combined campaign, in the export format.
+    /// Reference lists are unions, and text which differs is kept from both, marked with
+    /// where it came from. The faction turn log is that of the first campaign
+    pub fn merged(&self) -> Result<Value> {
+        let mut merged = self.first.clone();
+        let mut ids = IdMap::new();
+
+        let mut targets = Vec::new();
+        for kind in MERGE_KINDS {
+            let key = kind.key();
+            for (second_index, item) in items(&self.second, kind).iter().enumerate() {
+                let found = self
+                    .matches
+                    .iter()
+                    .find(|m| m.confirmed && m.kind == kind && m.second_index == second_index);
+                let target = match found {
+                    Some(item_match) => item_match.first_index,
+                    None => {
+                        let mut new_item = kind.template()?;
+                        new_item["id"] = Value::String(EntityId::new().to_string());
+                        new_item["name"] = item["name"].clone();
+                        push(&mut merged, key, new_item)?
+                    }
+                };
+                ids.insert(
+                    (key, item["id"].to_string()),
+                    merged[key][target]["id"].clone(),
+                );
+                targets.push((kind, second_index, target, found.is_some()));
+            }
+        }
+        let new_clocks = add_clocks(&mut merged, &self.second, &mut ids)?;
+
+        for (kind, second_index, target, unified) in targets {
+            let key = kind.key();
+            let item = &items(&self.second, kind)[second_index];
+            let Some(fields) = item.as_object() else {
+                continue;
+            };
+            for (field, value) in fields.iter().filter(|(f, _)| *f != "id" && *f != "name") {
+                let value = match reference_kind(kind, field) {
+                    Some(target_kind) => map_ids(&ids, target_kind.key(), value),
+                    None => value.clone(),
+                };
+                let merged_value = if unified {
+                    self.merge_field(field, &merged[key][target][field], value)
+                } else {
+                    value
+                };
+                merged[key][target][field] = merged_value;
+            }
+        }
+
+        link_clocks(&mut merged, &new_clocks, &ids);
+        add_relationships(&mut merged, &self.second, &ids)?;
+
+        // loading keeps only one item of each name, so the rest would be lost
+        for key in MERGE_KINDS.map(|k| k.key()).into_iter().chain([CLOCKS]) {
+            let mut names = BTreeSet::new();
+            for item in merged[key].as_array().into_iter().flatten() {
+                if !names.insert(item["name"].to_string()) {
+                    return Err(anyhow!(
+                        "more than one of the merged {key} would be named {}",
+                        item["name"]
+                    ));
+                }
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Combines a field of two items which are the same; single values such as the tier,
+    /// or the HQ when both have one, are kept from the first campaign
+    fn merge_field(&self, field: &str, first: &Value, second: Value) -> Value {
+        match (first, &second) {
+            (Value::String(first_text), Value::String(second_text))
+                if TEXT_FIELDS.contains(&field) =>
+            {
+                let first_text = first_text.trim();
+                let second_text = second_text.trim();
+                if second_text.is_empty() || first_text == second_text {
+                    first.clone()
+                } else if first_text.is_empty() {
+                    second
+                } else {
+                    Value::String(format!(
+                        "[{}]\n{first_text}\n\n[{}]\n{second_text}",
+                        self.first_title, self.second_title
+                    ))
+                }
+            }
+            // references and plain text are joined, entry by entry
+            (Value::Array(first_list), Value::Array(second_list))
+                if first_list.iter().chain(second_list).all(Value::is_string) =>
+            {
+                let mut list = first_list.clone();
+                for entry in second_list {
+                    if !list.contains(entry) {
+                        list.push(entry.clone());
+                    }
+                }
+                Value::Array(list)
+            }
+            // clocks are matched by name, keeping whichever has been ticked further
+            (Value::Array(first_list), Value::Array(second_list)) => {
+                let mut list = first_list.clone();
+                for entry in second_list {
+                    match list.iter_mut().find(|e| e["name"] == entry["name"]) {
+                        Some(existing)
+                            if entry["ticked"].as_u64() > existing["ticked"].as_u64() =>
+                        {
+                            *existing = entry.clone();
+                        }
+                        Some(_) => {}
+                        None => list.push(entry.clone()),
+                    }
+                }
+                Value::Array(list)
+            }
+            (Value::Null, _) => second,
+            (Value::String(first_text), _) if first_text.is_empty() => second,
+            _ => first.clone(),
+        }
+    }
+}
+
+fn names(value: &Value, kind: ItemKind) -> Vec<String> {
+    items(value, kind)
+        .iter()
+        .map(|item| item["name"].as_str().unwrap_or_default().to_string())
+        .collect()
+}
+
+/// How alike two names are, from 0 to 1, ignoring case, punctuation and a leading "the"
+pub fn similarity(a: &str, b: &str) -> f32 {
+    let a = simplified(a);
+    let b = simplified(b);
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f32 / longest as f32
+}
+
+fn simplified(name: &str) -> Vec<char> {
+    let lower: String = name
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .collect();
+    let words: Vec<&str> = lower.split_whitespace().collect();
+    let words = match words.split_first() {
+        Some((&"the", rest)) if !rest.is_empty() => rest,
+        _ => &words[..],
+    };
+    words.join(" ").chars().collect()
+}
+
+/// The number of single character changes which turn one into the other
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != cb);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(feature = "gui")]
+impl CampaignMerge {
+    /// Lists the exact matches, and the similar ones to be confirmed
+    pub fn show_edit(&mut self, ui: &mut Ui) {
+        const LIST_HEIGHT: f32 = 360.0;
+
+        ui.label(fl!(
+            "merge_campaigns_between",
+            first = self.first_title.clone(),
+            second = self.second_title.clone()
+        ));
+        let exact = self.matches.iter().filter(|m| m.is_exact()).count();
+        ui.label(fl!("merge_campaigns_exact", count = exact));
+        ui.add_space(UI_PADDING);
+
+        if exact == self.matches.len() {
+            ui.label(RichText::new(fl!("merge_campaigns_no_similar")).italics());
+            return;
+        }
+        ui.label(fl!("merge_campaigns_similar"));
+        ScrollArea::vertical()
+            .max_height(LIST_HEIGHT)
+            .show(ui, |ui| {
+                Grid::new("merge_campaigns_similar")
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for item_match in self.matches.iter_mut().filter(|m| !m.is_exact()) {
+                            ui.checkbox(&mut item_match.confirmed, &item_match.first_name);
+                            ui.label(&item_match.second_name);
+                            ui.label(item_match.kind.to_string());
+                            ui.label(format!("{:.0}%", item_match.similarity * 100.));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        app_data::AppData,
+        campaign_merge::{CampaignMerge, similarity},
+        export_format::{ItemKind, field_text, find_item, items, test_campaign},
+    };
+
+    const DISTRICTS: [&str; 3] = ["Crow's Foot", "Dunslough", "Six Towers"];
+
+    #[test]
+    fn matching_items_are_unified_and_lists_joined() {
+        assert!(similarity("The Lampblacks", "Lampblacks") > 0.99);
+        assert!(similarity("The Crows", "The Hive") < 0.5);
+
+        let first = test_campaign(
+            &DISTRICTS,
+            &[
+                ("The Crows", &[("turf", "Crow's Foot"), ("notes", "bold")]),
+                ("The Lampblacks", &[("turf", "Crow's Foot")]),
+            ],
+        );
+        let second = test_campaign(
+            &DISTRICTS,
+            &[
+                ("The Crows", &[("turf", "Dunslough"), ("notes", "cautious")]),
+                (
+                    "Lampblacks",
+                    &[("turf", "Six Towers"), ("notes", "in debt")],
+                ),
+                ("The Hive", &[]),
+            ],
+        );
+        let mut campaigns = CampaignMerge::new("first", first.clone(), "second", second.clone());
+        let exact = campaigns.matches().iter().filter(|m| m.is_exact()).count();
+        // the districts, and the crows
+        assert_eq!(exact, 4);
+        assert_eq!(campaigns.matches().len(), 5);
+
+        let merged = campaigns.merged().unwrap();
+        assert_eq!(items(&merged, ItemKind::Faction).len(), 4);
+        let crows = find_item(&merged, ItemKind::Faction, "The Crows").unwrap();
+        assert_eq!(
+            field_text(&merged, ItemKind::Faction, crows, "turf"),
+            "Crow's Foot, Dunslough"
+        );
+        assert_eq!(
+            field_text(&merged, ItemKind::Faction, crows, "notes"),
+            "[first]\nbold\n\n[second]\ncautious"
+        );
+
+        campaigns.confirm_all();
+        let merged = campaigns.merged().unwrap();
+        assert_eq!(items(&merged, ItemKind::Faction).len(), 3);
+        assert_eq!(items(&merged, ItemKind::District).len(), 3);
+        let lampblacks = find_item(&merged, ItemKind::Faction, "The Lampblacks").unwrap();
+        assert_eq!(
+            field_text(&merged, ItemKind::Faction, lampblacks, "notes"),
+            "in debt"
+        );
+        assert!(AppData::from_json_value(merged).is_ok());
+    }
+
+    #[test]
+    fn exact_names_are_matched_before_similar_ones() {
+        let first = test_campaign(
+            &DISTRICTS,
+            &[("Crows", &[("turf", "Crow's Foot"), ("notes", "bold")])],
+        );
+        let second = test_campaign(
+            &DISTRICTS,
+            &[("The Crows", &[]), ("Crows", &[("turf", "Dunslough")])],
+        );
+        let campaigns = CampaignMerge::new("first", first, "second", second.clone());
+        let merged = campaigns.merged().unwrap();
+        let crows = find_item(&merged, ItemKind::Faction, "Crows").unwrap();
+        assert_eq!(
+            field_text(&merged, ItemKind::Faction, crows, "turf"),
+            "Crow's Foot, Dunslough"
+        );
+        assert!(AppData::from_json_value(merged).is_ok());
+
+        // the same clock on both sides is kept once, as far as either has ticked it
+        let mut slower = test_campaign(&[], &[("The Crows", &[])]);
+        let mut faster = slower.clone();
+        let clock = |ticked: u8| serde_json::json!([{"name": "Expand", "description": "", "parts": 4, "ticked": ticked}]);
+        slower["factions"][0]["clocks"] = clock(1);
+        faster["factions"][0]["clocks"] = clock(2);
+        let merged = CampaignMerge::new("first", slower, "second", faster)
+            .merged()
+            .unwrap();
+        assert_eq!(merged["factions"][0]["clocks"], clock(2));
+
+        // a name used twice cannot be merged without losing one of them
+        let repeated = test_campaign(&DISTRICTS, &[("The Hive", &[]), ("The Hive", &[])]);
+        let campaigns = CampaignMerge::new("first", second, "second", repeated);
+        assert!(campaigns.merged().is_err());
+    }
+}
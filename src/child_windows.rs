@@ -11,7 +11,10 @@ use crate::{
     APP_NAME,
     app::{CHANGE_NOTES, FONT_NOTES, HELP_TEXT, UI_PADDING},
     app_data::{JSON_EXTENSION, SAVE_EXTENSION},
+    csv_file::CSV_EXTENSION,
+    faction_web::{DOT_EXTENSION, SVG_EXTENSION},
     localize::fl,
+    markdown::MARKDOWN_EXTENSION,
 };
 
 // TODO: add settings panel?
@@ -20,6 +23,10 @@ pub struct ChildWindows {
     show_about: Arc<RwLock<bool>>,
     file_dialog_internal: FileDialogControl,
     file_dialog_export: FileDialogControl,
+    file_dialog_markdown: FileDialogControl,
+    file_dialog_csv: FileDialogControl,
+    file_dialog_dot: FileDialogControl,
+    file_dialog_svg: FileDialogControl,
     selected_file: Arc<RwLock<Option<PathBuf>>>,
 }
 
@@ -29,6 +36,10 @@ impl Default for ChildWindows {
             show_about: Arc::default(),
             file_dialog_internal: FileDialogControl::new(FileTarget::Internal),
             file_dialog_export: FileDialogControl::new(FileTarget::Export),
+            file_dialog_markdown: FileDialogControl::new(FileTarget::Markdown),
+            file_dialog_csv: FileDialogControl::new(FileTarget::Csv),
+            file_dialog_dot: FileDialogControl::new(FileTarget::Dot),
+            file_dialog_svg: FileDialogControl::new(FileTarget::Svg),
             selected_file: Arc::default(),
         }
     }
@@ -51,6 +62,10 @@ impl ChildWindows {
         let dialog_control = match target_type {
             FileTarget::Internal => &mut self.file_dialog_internal,
             FileTarget::Export => &mut self.file_dialog_export,
+            FileTarget::Markdown => &mut self.file_dialog_markdown,
+            FileTarget::Csv => &mut self.file_dialog_csv,
+            FileTarget::Dot => &mut self.file_dialog_dot,
+            FileTarget::Svg => &mut self.file_dialog_svg,
         };
 
         if let Err(e) = create_dir_all(initial_directory.clone()) {
@@ -70,6 +85,7 @@ impl ChildWindows {
         match dialog_type {
             FileDialogType::Load => dialog_control.dialog.pick_file(),
             FileDialogType::Save => dialog_control.dialog.save_file(),
+            FileDialogType::Folder => dialog_control.dialog.pick_directory(),
         }
 
         dialog_control.current_mode = Some(dialog_type);
@@ -95,6 +111,18 @@ impl ChildWindows {
         if let Some(new_file) = self.file_dialog_export.update(ctx) {
             *self.selected_file.write() = new_file;
         }
+        if let Some(new_file) = self.file_dialog_markdown.update(ctx) {
+            *self.selected_file.write() = new_file;
+        }
+        if let Some(new_file) = self.file_dialog_csv.update(ctx) {
+            *self.selected_file.write() = new_file;
+        }
+        if let Some(new_file) = self.file_dialog_dot.update(ctx) {
+            *self.selected_file.write() = new_file;
+        }
+        if let Some(new_file) = self.file_dialog_svg.update(ctx) {
+            *self.selected_file.write() = new_file;
+        }
     }
 
     fn about(&self, ctx: &Context) {
@@ -151,6 +179,7 @@ impl ChildWindows {
 pub enum FileDialogType {
     Load,
     Save,
+    Folder,
 }
 
 // ---------------------
@@ -160,6 +189,10 @@ pub enum FileDialogType {
 pub enum FileTarget {
     Internal,
     Export,
+    Markdown,
+    Csv,
+    Dot,
+    Svg,
 }
 
 // ---------------------
@@ -208,6 +241,61 @@ impl FileDialogControl {
                 .default_save_extension(fl!("file_dialog_export_file").as_str())
                 .allow_path_edit_to_save_file_without_extension(true)
                 .load_via_thread(true),
+            FileTarget::Markdown => FileDialog::new()
+                .opening_mode(OpeningMode::LastPickedDir)
+                .default_file_name(fl!("default_markdown_file").as_str())
+                .allow_file_overwrite(true)
+                .add_file_filter_extensions(
+                    fl!("file_dialog_markdown_files").as_str(),
+                    vec![MARKDOWN_EXTENSION],
+                )
+                .add_save_extension(
+                    fl!("file_dialog_markdown_file").as_str(),
+                    MARKDOWN_EXTENSION,
+                )
+                .default_file_filter(fl!("file_dialog_markdown_files").as_str())
+                .default_save_extension(fl!("file_dialog_markdown_file").as_str())
+                .allow_path_edit_to_save_file_without_extension(true)
+                .load_via_thread(true),
+            FileTarget::Csv => FileDialog::new()
+                .opening_mode(OpeningMode::LastPickedDir)
+                .default_file_name(fl!("default_csv_file").as_str())
+                .allow_file_overwrite(true)
+                .add_file_filter_extensions(
+                    fl!("file_dialog_csv_files").as_str(),
+                    vec![CSV_EXTENSION],
+                )
+                .add_save_extension(fl!("file_dialog_csv_file").as_str(), CSV_EXTENSION)
+                .default_file_filter(fl!("file_dialog_csv_files").as_str())
+                .default_save_extension(fl!("file_dialog_csv_file").as_str())
+                .allow_path_edit_to_save_file_without_extension(true)
+                .load_via_thread(true),
+            FileTarget::Dot => FileDialog::new()
+                .opening_mode(OpeningMode::LastPickedDir)
+                .default_file_name(fl!("default_web_file").as_str())
+                .allow_file_overwrite(true)
+                .add_file_filter_extensions(
+                    fl!("file_dialog_dot_files").as_str(),
+                    vec![DOT_EXTENSION],
+                )
+                .add_save_extension(fl!("file_dialog_dot_file").as_str(), DOT_EXTENSION)
+                .default_file_filter(fl!("file_dialog_dot_files").as_str())
+                .default_save_extension(fl!("file_dialog_dot_file").as_str())
+                .allow_path_edit_to_save_file_without_extension(true)
+                .load_via_thread(true),
+            FileTarget::Svg => FileDialog::new()
+                .opening_mode(OpeningMode::LastPickedDir)
+                .default_file_name(fl!("default_web_file").as_str())
+                .allow_file_overwrite(true)
+                .add_file_filter_extensions(
+                    fl!("file_dialog_svg_files").as_str(),
+                    vec![SVG_EXTENSION],
+                )
+                .add_save_extension(fl!("file_dialog_svg_file").as_str(), SVG_EXTENSION)
+                .default_file_filter(fl!("file_dialog_svg_files").as_str())
+                .default_save_extension(fl!("file_dialog_svg_file").as_str())
+                .allow_path_edit_to_save_file_without_extension(true)
+                .load_via_thread(true),
         };
 
         FileDialogControl {
@@ -231,6 +319,10 @@ impl FileDialogControl {
                     path.with_extension(match self.target {
                         FileTarget::Internal => SAVE_EXTENSION,
                         FileTarget::Export => JSON_EXTENSION,
+                        FileTarget::Markdown => MARKDOWN_EXTENSION,
+                        FileTarget::Csv => CSV_EXTENSION,
+                        FileTarget::Dot => DOT_EXTENSION,
+                        FileTarget::Svg => SVG_EXTENSION,
                     })
                 } else {
                     path
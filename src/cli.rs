@@ -0,0 +1,522 @@
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::{
+    APP_NAME,
+    app_data::{AppData, JSON_EXTENSION},
+    campaign_diff::CampaignDiff,
+    campaign_merge::CampaignMerge,
+    entity_id::EntityId,
+    export_format::{ItemKind, find_item, items, names_of, reference_kind, set_field, text_of},
+    faction_web::{WebFilter, WebFormat},
+    markdown::{MARKDOWN_EXTENSION, MarkdownLayout},
+    tier::Tier,
+};
+
+/// The exit code when a command fails, or the data does not validate
+pub const EXIT_FAILURE: i32 = 1;
+/// The exit code when the command line cannot be understood
+pub const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "\
+usage:
+    blades_factions                                          start the editor
+    blades_factions list <file> <kind>                       list the names of every item
+    blades_factions show <file> <kind> <name>                show every field of an item
+    blades_factions add <file> <kind> <name> [field=value]   add an item
+    blades_factions update <file> <kind> <name> field=value  change fields of an item
+    blades_factions convert <from> <to>                      convert between .bfsav and .json
+    blades_factions validate <file>                          check that a file loads
+    blades_factions diff <before> <after> [to.md]            list the items added, removed and changed since before,
+                                                             or write them to a Markdown file
+    blades_factions merge <first> <second> <to> [similar]  merge two campaigns into a new file, unifying items
+                                                             with the same name, or similar names with similar
+    blades_factions markdown <file> <to>                     export Markdown pages to a folder, or to one .md
+    blades_factions csv-export <file> <kind> <csv>           write a row per item of the kind to a .csv
+    blades_factions csv-import <file> <kind> <csv>           create or update items of the kind from a .csv
+    blades_factions html <file> <folder>                     export a site of linked pages to a folder
+    blades_factions web <file> <to> [tier=II] [turf=name]    export the factions' allies, enemies, HQs and leaders
+                                                             as a Graphviz .dot, or an .svg picture
+    blades_factions vault-export <file> <vault>              write a note per item into an Obsidian vault
+    blades_factions vault-import <vault> <file>              update or create a campaign from the notes of a vault
+
+kinds: factions, persons, districts
+files ending in .json are read and written in the export format, anything else as a save file
+lists take comma separated values, quoting any value with a comma in it, and references take names";
+
+/// Runs the command given on the command line, returning its exit code,
+/// or None when there is no command and the editor should start
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first()?;
+    let result = match command.as_str() {
+        "list" => list(&args[1..]),
+        "show" => show(&args[1..]),
+        "add" => add(&args[1..]),
+        "update" => update(&args[1..]),
+        "convert" => convert(&args[1..]),
+        "validate" => validate(&args[1..]),
+        "diff" => diff(&args[1..]),
+        "merge" => merge(&args[1..]),
+        "markdown" => markdown(&args[1..]),
+        "csv-export" => csv_export(&args[1..]),
+        "csv-import" => csv_import(&args[1..]),
+        "html" => html(&args[1..]),
+        "web" => web(&args[1..]),
+        "vault-export" => vault_export(&args[1..]),
+        "vault-import" => vault_import(&args[1..]),
+        "help" | "-h" | "--help" => writeln!(
+            io::stdout().lock(),
+            "{APP_NAME} {}\n{USAGE}",
+            env!("CARGO_PKG_VERSION")
+        )
+        .map_err(CliError::from),
+        _ => Err(CliError::Usage(format!("unknown command '{command}'"))),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        // the output was piped into something which has stopped reading it, such as head
+        Err(CliError::Failure(error)) if is_broken_pipe(&error) => Some(0),
+        Err(CliError::Usage(message)) => {
+            eprintln!("{message}\n{USAGE}");
+            Some(EXIT_USAGE)
+        }
+        Err(CliError::Failure(error)) => {
+            eprintln!("error: {error:#}");
+            Some(EXIT_FAILURE)
+        }
+    }
+}
+
+// -----------------------------
+// Commands
+
+fn list(args: &[String]) -> CliResult {
+    let [file, kind] = args else {
+        return Err(usage("list takes a file and a kind"));
+    };
+    let kind = parse_kind(kind)?;
+    let value = load(Path::new(file))?.to_json_value()?;
+
+    let mut names: Vec<String> = items(&value, kind)
+        .iter()
+        .map(|item| text_of(&item["name"]))
+        .collect();
+    names.sort();
+    let mut out = io::stdout().lock();
+    for name in names {
+        writeln!(out, "{name}")?;
+    }
+    Ok(())
+}
+
+fn show(args: &[String]) -> CliResult {
+    let [file, kind, name] = args else {
+        return Err(usage("show takes a file, a kind and a name"));
+    };
+    let kind = parse_kind(kind)?;
+    let value = load(Path::new(file))?.to_json_value()?;
+    let index = find_item(&value, kind, name)?;
+
+    let mut out = io::stdout().lock();
+    if let Value::Object(fields) = &items(&value, kind)[index] {
+        for (field, field_value) in fields {
+            let text = match reference_kind(kind, field) {
+                Some(target) => names_of(&value, target, field_value),
+                None => text_of(field_value),
+            };
+            writeln!(out, "{field}: {text}")?;
+        }
+    }
+    Ok(())
+}
+
+fn add(args: &[String]) -> CliResult {
+    let [file, kind, name, fields @ ..] = args else {
+        return Err(usage("add takes a file, a kind, a name and any fields"));
+    };
+    let kind = parse_kind(kind)?;
+    let path = Path::new(file);
+    let mut value = load(path)?.to_json_value()?;
+    if find_item(&value, kind, name).is_ok() {
+        return Err(failure(format!("there is already a {kind} named '{name}'")));
+    }
+
+    let mut item = kind.template()?;
+    item["id"] = Value::String(EntityId::new().to_string());
+    item["name"] = Value::String(name.clone());
+    let list = value[kind.key()]
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("no {} in the data", kind.key()))?;
+    list.push(item);
+    let index = list.len() - 1;
+
+    set_fields(&mut value, kind, index, fields)?;
+    save(path, value)
+}
+
+fn update(args: &[String]) -> CliResult {
+    let [file, kind, name, fields @ ..] = args else {
+        return Err(usage("update takes a file, a kind, a name and fields"));
+    };
+    if fields.is_empty() {
+        return Err(usage("update needs at least one field=value"));
+    }
+    let kind = parse_kind(kind)?;
+    let path = Path::new(file);
+    let mut value = load(path)?.to_json_value()?;
+    let index = find_item(&value, kind, name)?;
+
+    set_fields(&mut value, kind, index, fields)?;
+    save(path, value)
+}
+
+fn convert(args: &[String]) -> CliResult {
+    let [from, to] = args else {
+        return Err(usage(
+            "convert takes the file to read and the file to write",
+        ));
+    };
+    let mut data = load(Path::new(from))?;
+    write(&mut data, Path::new(to))
+}
+
+fn validate(args: &[String]) -> CliResult {
+    let [file] = args else {
+        return Err(usage("validate takes a file"));
+    };
+    let path = Path::new(file);
+    let data = load(path)?;
+    // loading validates the data; reading it back checks that it can be saved again
+    AppData::from_json_value(data.to_json_value()?)?;
+    // but loading drops what it cannot use, so the file itself is checked for that
+    let problems = AppData::check_file(path)?;
+    let mut out = io::stdout().lock();
+    if !problems.is_empty() {
+        for problem in &problems {
+            writeln!(out, "{file}: {problem}")?;
+        }
+        return Err(failure(format!(
+            "loading {file} would drop the items or references listed"
+        )));
+    }
+    writeln!(out, "{file}: ok")?;
+    Ok(())
+}
+
+fn diff(args: &[String]) -> CliResult {
+    let (before, after, to) = match args {
+        [before, after] => (before, after, None),
+        [before, after, to] => (before, after, Some(to)),
+        _ => {
+            return Err(usage(
+                "diff takes the earlier file, the later file and any Markdown file to write",
+            ));
+        }
+    };
+    let value = |file: &String| load(Path::new(file))?.to_json_value();
+    let diff = CampaignDiff::new(before, &value(before)?, after, &value(after)?);
+    match to {
+        Some(to) => diff.export_markdown(Path::new(to))?,
+        None => write!(io::stdout().lock(), "{}", diff.to_markdown())?,
+    }
+    Ok(())
+}
+
+/// Items with similar names are listed, and only unified when asked
+fn merge(args: &[String]) -> CliResult {
+    let (first, second, to, similar) = match args {
+        [first, second, to] => (first, second, to, false),
+        [first, second, to, option] if option == "similar" => (first, second, to, true),
+        [_, _, _, option] => return Err(usage(format!("'{option}' is not similar"))),
+        _ => {
+            return Err(usage(
+                "merge takes the two files to merge and the file to write",
+            ));
+        }
+    };
+    let value = |file: &String| load(Path::new(file))?.to_json_value();
+    // text kept from both campaigns is marked with the name of the file it came from
+    let title = |file: &String| {
+        Path::new(file)
+            .file_stem()
+            .map_or_else(|| file.clone(), |s| s.to_string_lossy().to_string())
+    };
+    let mut campaigns =
+        CampaignMerge::new(&title(first), value(first)?, &title(second), value(second)?);
+    if similar {
+        campaigns.confirm_all();
+    } else {
+        for item_match in campaigns.matches().iter().filter(|m| !m.is_exact()) {
+            eprintln!(
+                "not merged, as the names only look alike: {} '{}' and '{}'",
+                item_match.kind, item_match.first_name, item_match.second_name
+            );
+        }
+    }
+    let mut data = AppData::from_json_value(campaigns.merged()?)
+        .map_err(|e| anyhow!("the merged data does not validate: {e}"))?;
+    write(&mut data, Path::new(to))
+}
+
+fn markdown(args: &[String]) -> CliResult {
+    let [file, to] = args else {
+        return Err(usage("markdown takes a file and where to write the pages"));
+    };
+    let mut data = load(Path::new(file))?;
+    // the pages are titled with the campaign's file name, even when it was read as JSON
+    data.set_loaded_from(Some(file.into()));
+    let to = Path::new(to);
+    let layout = if to.extension().is_some_and(|e| e == MARKDOWN_EXTENSION) {
+        MarkdownLayout::Document
+    } else {
+        MarkdownLayout::Pages
+    };
+    data.export_to_markdown(to, layout)?;
+    Ok(())
+}
+
+fn csv_export(args: &[String]) -> CliResult {
+    let [file, kind, csv] = args else {
+        return Err(usage(
+            "csv-export takes a file, a kind and the .csv to write",
+        ));
+    };
+    let kind = parse_kind(kind)?;
+    let data = load(Path::new(file))?;
+    data.export_to_csv(Path::new(csv), kind)?;
+    Ok(())
+}
+
+/// The rows which can be used are written, even when others are reported
+fn csv_import(args: &[String]) -> CliResult {
+    let [file, kind, csv] = args else {
+        return Err(usage(
+            "csv-import takes a file, a kind and the .csv to read",
+        ));
+    };
+    let kind = parse_kind(kind)?;
+    let path = Path::new(file);
+    let mut data = load(path)?;
+    let (report, _) = data.import_from_csv(Path::new(csv), kind)?;
+    if report.changed() {
+        write(&mut data, path)?;
+    }
+    writeln!(io::stdout().lock(), "{report}")?;
+    if report.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(failure(format!(
+            "{} rows were not imported",
+            report.errors.len()
+        )))
+    }
+}
+
+fn html(args: &[String]) -> CliResult {
+    let [file, folder] = args else {
+        return Err(usage("html takes a file and a folder"));
+    };
+    let mut data = load(Path::new(file))?;
+    data.set_loaded_from(Some(file.into()));
+    data.export_to_html(Path::new(folder))?;
+    Ok(())
+}
+
+/// Only factions of at least tier=, and with turf in turf=, are exported
+fn web(args: &[String]) -> CliResult {
+    let [file, to, options @ ..] = args else {
+        return Err(usage(
+            "web takes a file, where to write the graph and any filters",
+        ));
+    };
+    let mut filter = WebFilter::default();
+    for option in options {
+        match option.split_once('=') {
+            Some(("tier", text)) => {
+                filter.min_tier =
+                    Tier::parse(text).ok_or_else(|| usage(format!("unknown tier '{text}'")))?;
+            }
+            Some(("turf", district)) => filter.turf_in = Some(district.to_string()),
+            _ => return Err(usage(format!("'{option}' is not tier=... or turf=..."))),
+        }
+    }
+    let mut data = load(Path::new(file))?;
+    data.set_loaded_from(Some(file.into()));
+    let to = Path::new(to);
+    data.export_faction_web(to, &filter, WebFormat::for_path(to))?;
+    Ok(())
+}
+
+fn vault_export(args: &[String]) -> CliResult {
+    let [file, vault] = args else {
+        return Err(usage("vault-export takes a file and a vault folder"));
+    };
+    let data = load(Path::new(file))?;
+    data.export_to_vault(Path::new(vault))?;
+    Ok(())
+}
+
+fn vault_import(args: &[String]) -> CliResult {
+    let [vault, file] = args else {
+        return Err(usage(
+            "vault-import takes a vault folder and the file to write",
+        ));
+    };
+    // a campaign which is already there keeps its ids, and everything the notes do not hold
+    let file = Path::new(file);
+    let mut data = if file.exists() {
+        load(file)?
+    } else {
+        AppData::default()
+    };
+    data.update_from_vault(Path::new(vault))?;
+    write(&mut data, file)
+}
+
+// -----------------------------
+// Files
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == JSON_EXTENSION)
+}
+
+fn load(path: &Path) -> anyhow::Result<AppData> {
+    AppData::read_from_file(path)
+}
+
+fn write(data: &mut AppData, path: &Path) -> CliResult {
+    if is_json(path) {
+        data.export_to_file(path)?;
+    } else {
+        data.save_to_file(path)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the data from the edited JSON, so it is validated before anything is written
+fn save(path: &Path, value: Value) -> CliResult {
+    let mut data = AppData::from_json_value(value)
+        .map_err(|e| anyhow!("the changed data does not validate: {e}"))?;
+    write(&mut data, path)
+}
+
+// -----------------------------
+// Items
+
+fn parse_kind(text: &str) -> Result<ItemKind, CliError> {
+    ItemKind::parse(text).ok_or_else(|| usage(format!("unknown kind '{text}'")))
+}
+
+/// Sets each field=value on the item, converting the text to the type already in the field
+fn set_fields(value: &mut Value, kind: ItemKind, index: usize, fields: &[String]) -> CliResult {
+    for field in fields {
+        let Some((field, text)) = field.split_once('=') else {
+            return Err(usage(format!("'{field}' is not field=value")));
+        };
+        if field == "id" {
+            return Err(usage("the id of an item cannot be changed"));
+        }
+        // names are how items are found, so two items of a kind cannot share one
+        if field == "name"
+            && items(value, kind)
+                .iter()
+                .enumerate()
+                .any(|(i, item)| i != index && item["name"] == text)
+        {
+            return Err(failure(format!("there is already a {kind} named '{text}'")));
+        }
+        set_field(value, kind, index, field, text)?;
+    }
+    Ok(())
+}
+
+// -----------------------------
+// Errors
+
+type CliResult = Result<(), CliError>;
+
+enum CliError {
+    Usage(String),
+    Failure(anyhow::Error),
+}
+
+impl From<anyhow::Error> for CliError {
+    fn from(error: anyhow::Error) -> Self {
+        CliError::Failure(error)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        CliError::Failure(error.into())
+    }
+}
+
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+}
+
+fn usage(message: impl Into<String>) -> CliError {
+    CliError::Usage(message.into())
+}
+
+fn failure(message: String) -> CliError {
+    CliError::Failure(anyhow!(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::{
+        app_data::AppData,
+        cli::set_fields,
+        export_format::{ItemKind, add_test_item, find_item, items, names_of},
+    };
+
+    #[test]
+    fn fields_are_set_by_type_and_references_by_name() {
+        let mut value = AppData::default().to_json_value().unwrap();
+        add_test_item(&mut value, ItemKind::District, "Crow's Foot", &[]);
+        add_test_item(&mut value, ItemKind::Faction, "The Crows", &[]);
+
+        let fields = [
+            "tier=Tier3",
+            "hq=Crow's Foot",
+            "turf=Crow's Foot",
+            "notes=bold",
+        ]
+        .map(String::from);
+        let crows = find_item(&value, ItemKind::Faction, "The Crows").unwrap();
+        assert!(set_fields(&mut value, ItemKind::Faction, crows, &fields).is_ok());
+        assert!(set_fields(&mut value, ItemKind::Faction, crows, &["hq=Nowhere".into()]).is_err());
+        // keeping its own name is fine, but taking another's is not
+        let rename = |value: &mut Value, index, name: &str| {
+            set_fields(value, ItemKind::Faction, index, &[format!("name={name}")])
+        };
+        assert!(rename(&mut value, crows, "The Crows").is_ok());
+        let hive = add_test_item(&mut value, ItemKind::Faction, "The Crows", &[]);
+        // a second item of the same name would be dropped on loading
+        assert!(AppData::from_json_value(value.clone()).is_err());
+        assert!(rename(&mut value, hive, "The Hive").is_ok());
+        assert!(rename(&mut value, hive, "The Crows").is_err());
+
+        let data = AppData::from_json_value(value).unwrap();
+        let value = data.to_json_value().unwrap();
+        let crows = &items(&value, ItemKind::Faction)[0];
+        assert_eq!(crows["tier"], "Tier3");
+        assert_eq!(crows["notes"], "bold");
+        assert_eq!(
+            names_of(&value, ItemKind::District, &crows["turf"]),
+            "Crow's Foot"
+        );
+    }
+}
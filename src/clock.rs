@@ -1,7 +1,21 @@
+use std::fmt::Display;
+#[cfg(feature = "gui")]
+use std::f32::consts::TAU;
+
+#[cfg(feature = "gui")]
+use eframe::egui::{Color32, ComboBox, Pos2, Response, Sense, Shape, Stroke, Ui, Vec2, epaint::PathShape};
 use serde::{Deserialize, Serialize};
 
 
+pub const CLOCK_SIZES: &[u8] = &[4, 6, 8, 12];
+pub const CLOCK_DIAMETER: f32 = 32.0;
 
+#[cfg(feature = "gui")]
+const CLOCK_FILL: Color32 = Color32::from_rgb(150, 40, 40);
+#[cfg(feature = "gui")]
+const CLOCK_STROKE: Color32 = Color32::GRAY;
+#[cfg(feature = "gui")]
+const ARC_POINTS_PER_PART: usize = 6;
 
 #[allow(dead_code)]
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -30,6 +44,13 @@ impl Clock {
         Self::new(12, name, description)
     }
 
+    /// A clock which has already been ticked, such as one read back from a note
+    pub fn with_progress ( name: &str, description: &str, parts: u8, ticked: u8 ) -> Self {
+        let mut clock = Self::new(parts, name, description);
+        clock.ticked = ticked.min(parts);
+        clock
+    }
+
     fn new ( size: u8, name: &str, description: &str ) -> Self {
         Clock {
             name: name.to_string(),
@@ -43,6 +64,10 @@ impl Clock {
         &self.name
     }
 
+    pub fn name_mut ( &mut self ) -> &mut String {
+        &mut self.name
+    }
+
     pub fn description ( &self ) -> &str {
         &self.description
     }
@@ -52,9 +77,108 @@ impl Clock {
         (self.ticked, self.parts)
     }
 
+    pub fn is_finished ( &self ) -> bool {
+        self.ticked >= self.parts
+    }
+
     /// Increases the number of ticks by 1, and returns whether it is finished now
     pub fn tick ( &mut self ) -> bool {
         self.ticked = (self.ticked + 1).min(self.parts);
         self.ticked >= self.parts
     }
+
+    /// Decreases the number of ticks by 1
+    pub fn untick ( &mut self ) {
+        self.ticked = self.ticked.saturating_sub(1);
+    }
+
+    /// Changes the number of parts, keeping as many ticks as will fit
+    pub fn set_parts ( &mut self, parts: u8 ) {
+        self.parts = parts;
+        self.ticked = self.ticked.min(parts);
+    }
+}
+
+#[cfg(feature = "gui")]
+impl Clock {
+    /// Paints the clock as a segmented pie; click to tick, right click to untick
+    pub fn show_pie ( &mut self, ui: &mut Ui ) -> Response {
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(CLOCK_DIAMETER), Sense::click());
+        if response.clicked() {
+            self.tick();
+        } else if response.secondary_clicked() {
+            self.untick();
+        }
+
+        if ui.is_rect_visible(rect) {
+            paint_pie(ui, rect.center(), CLOCK_DIAMETER / 2.0 - 1.0, self.ticked, self.parts);
+        }
+        response
+    }
+
+    /// Shows the name, size and pie of the clock, returning true if it should be removed
+    pub fn show_edit ( &mut self, id: &str, ui: &mut Ui ) -> bool {
+        let mut remove = false;
+        ui.horizontal(|ui| {
+            self.show_pie(ui);
+            ui.text_edit_singleline(&mut self.name);
+            self.show_edit_size(id, ui);
+            remove = ui.small_button("x").clicked();
+        });
+        remove
+    }
+
+    /// Shows the size of the clock, and how far it has got
+    pub fn show_edit_size ( &mut self, id: &str, ui: &mut Ui ) {
+        let mut selected = CLOCK_SIZES.iter().position(|s| *s == self.parts).unwrap_or_default();
+        ComboBox::from_id_salt(id)
+            .width(40.0)
+            .show_index(ui, &mut selected, CLOCK_SIZES.len(), |i| CLOCK_SIZES[i].to_string());
+        if CLOCK_SIZES[selected] != self.parts {
+            self.set_parts(CLOCK_SIZES[selected]);
+        }
+
+        ui.label(format!("{}/{}", self.ticked, self.parts));
+    }
+}
+
+#[cfg(feature = "gui")]
+fn paint_pie ( ui: &Ui, center: Pos2, radius: f32, ticked: u8, parts: u8 ) {
+    let painter = ui.painter();
+    let stroke = Stroke::new(1.0, CLOCK_STROKE);
+    let parts = parts.max(1);
+    // clocks fill clockwise from the top
+    let angle = |part: f32| TAU * part / parts as f32 - TAU / 4.0;
+    let point = |a: f32| center + radius * Vec2::angled(a);
+
+    for part in 0..ticked.min(parts) {
+        let mut points = vec![center];
+        points.extend((0..=ARC_POINTS_PER_PART).map(|p| {
+            point(angle(part as f32 + p as f32 / ARC_POINTS_PER_PART as f32))
+        }));
+        painter.add(Shape::Path(PathShape::convex_polygon(points, CLOCK_FILL, Stroke::NONE)));
+    }
+
+    painter.circle_stroke(center, radius, stroke);
+    if parts > 1 {
+        for part in 0..parts {
+            painter.line_segment([center, point(angle(part as f32))], stroke);
+        }
+    }
+}
+
+// the closest quarter of a pie
+const PIE_STRINGS: &[&str] = &[
+    "○",
+    "◔",
+    "◑",
+    "◕",
+    "●",
+];
+
+impl Display for Clock {
+    fn fmt ( &self, f: &mut std::fmt::Formatter<'_> ) -> std::fmt::Result {
+        let quarters = (self.ticked as usize * 4).checked_div(self.parts as usize).unwrap_or_default();
+        write!(f, "{} {} {}/{}", self.name, PIE_STRINGS[quarters.min(4)], self.ticked, self.parts)
+    }
 }
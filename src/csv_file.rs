@@ -0,0 +1,406 @@
+use std::{fmt::Display, fs, path::Path};
+
+use anyhow::{Result, anyhow};
+use csv::{ReaderBuilder, StringRecord, Writer};
+use log::{info, warn};
+use serde_json::Value;
+
+use crate::{
+    entity_id::EntityId,
+    export_format::{ItemKind, field_text, find_item, items, reference_kind, set_field, text_of},
+    persistence::save_to_text,
+};
+
+pub const CSV_EXTENSION: &str = "csv";
+
+/// The column which rows are matched by
+const NAME_COLUMN: &str = "name";
+
+/// The columns of a kind of item: the name, then every other field of its store but the id
+pub fn columns(kind: ItemKind) -> Result<Vec<String>> {
+    let template = kind.template()?;
+    let fields = template
+        .as_object()
+        .ok_or_else(|| anyhow!("an empty {kind} is not an object"))?;
+    Ok(std::iter::once(NAME_COLUMN.to_string())
+        .chain(
+            fields
+                .keys()
+                .filter(|k| *k != "id" && *k != NAME_COLUMN)
+                .cloned(),
+        )
+        .collect())
+}
+
+/// Writes a row for each item of the kind, in name order, with references by name
+pub fn export(value: &Value, kind: ItemKind, path: &Path) -> Result<()> {
+    let columns = columns(kind)?;
+    let mut writer = Writer::from_writer(Vec::new());
+    writer.write_record(&columns)?;
+
+    let mut indices: Vec<usize> = (0..items(value, kind).len()).collect();
+    indices.sort_by_key(|i| field_text(value, kind, *i, NAME_COLUMN));
+    for index in indices {
+        writer.write_record(columns.iter().map(|c| field_text(value, kind, index, c)))?;
+    }
+
+    let text = String::from_utf8(writer.into_inner()?)?;
+    save_to_text(path, &text)?;
+    info!(
+        "exported {} {} to {}",
+        items(value, kind).len(),
+        kind.key(),
+        path.to_string_lossy()
+    );
+    Ok(())
+}
+
+/// Creates or updates an item of the kind for each row, matched by name.
+/// A row which cannot be used is left out, and listed in the report, rather than failing the file
+pub fn import(value: &mut Value, kind: ItemKind, path: &Path) -> Result<CsvReport> {
+    let text = fs::read_to_string(path)?;
+    let mut reader = ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let headers = reader.headers()?.clone();
+    let name_column = headers
+        .iter()
+        .position(|h| h.trim() == NAME_COLUMN)
+        .ok_or_else(|| anyhow!("{} has no '{NAME_COLUMN}' column", path.to_string_lossy()))?;
+
+    let mut report = CsvReport::default();
+    let known = columns(kind)?;
+    let mut fields = Vec::new();
+    for (column, heading) in headers.iter().enumerate() {
+        let heading = heading.trim();
+        if column == name_column {
+            continue;
+        }
+        if known.iter().any(|k| k == heading) {
+            fields.push((column, heading.to_string()));
+        } else {
+            report.error(
+                1,
+                format!("there is no {kind} field '{heading}', so the column is ignored"),
+            );
+        }
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        match record {
+            Ok(record) => {
+                let line = record.position().map_or(0, |p| p.line());
+                rows.push((line, record));
+            }
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                report.error(line, e.to_string());
+            }
+        }
+    }
+
+    // every new item is added before any fields are set, so rows can refer to later rows
+    let mut created = Vec::new();
+    for (line, record) in &rows {
+        let name = cell(record, name_column);
+        if name.is_empty() {
+            report.error(*line, "the row has no name".to_string());
+        } else if find_item(value, kind, name).is_err() {
+            let mut item = kind.template()?;
+            item["id"] = Value::String(EntityId::new().to_string());
+            item["name"] = Value::String(name.to_string());
+            value[kind.key()]
+                .as_array_mut()
+                .ok_or_else(|| anyhow!("no {} in the data", kind.key()))?
+                .push(item);
+            created.push(name.to_string());
+        }
+    }
+
+    // each row which worked, with its item as it was, in case it has to be put back
+    let mut imported = Vec::new();
+    let mut failed = Vec::new();
+    for (line, record) in &rows {
+        let name = cell(record, name_column);
+        let Ok(index) = find_item(value, kind, name) else {
+            continue;
+        };
+        let original = items(value, kind)[index].clone();
+        let result = fields.iter().try_for_each(|(column, field)| {
+            match record.get(*column) {
+                Some(text) => {
+                    set_field(value, kind, index, field, text).map_err(|e| anyhow!("{field}: {e}"))
+                }
+                None => Ok(()), // a short row leaves the rest of the fields as they were
+            }
+        });
+        // text in a field of numbers or choices is only found when the item is read
+        let result = result.and_then(|()| kind.check(&items(value, kind)[index]));
+        match result {
+            Ok(()) => imported.push((*line, name, original)),
+            Err(e) => {
+                value[kind.key()][index] = original;
+                report.error(*line, format!("{name}: {e}"));
+                failed.push(name);
+            }
+        }
+    }
+
+    // a new item whose row failed is not added, so a row which refers to one fails too
+    loop {
+        let dropped: Vec<&Value> = items(value, kind)
+            .iter()
+            .filter(|item| {
+                failed
+                    .iter()
+                    .any(|f| item["name"] == *f && created.iter().any(|c| c == f))
+            })
+            .collect();
+        let mut referring = Vec::new();
+        for (position, (_, name, _)) in imported.iter().enumerate() {
+            let index = find_item(value, kind, name)?;
+            if let Some((field, target)) = dropped_reference(value, kind, index, &dropped) {
+                referring.push((
+                    position,
+                    format!("{name}: {field}: '{target}' was not imported"),
+                ));
+            }
+        }
+        if referring.is_empty() {
+            break;
+        }
+        for (position, message) in referring.into_iter().rev() {
+            let (line, name, original) = imported.remove(position);
+            let index = find_item(value, kind, name)?;
+            value[kind.key()][index] = original;
+            report.error(line, message);
+            failed.push(name);
+        }
+    }
+    if let Some(list) = value[kind.key()].as_array_mut() {
+        list.retain(|item| {
+            !failed
+                .iter()
+                .any(|f| item["name"] == *f && created.iter().any(|c| c == f))
+        });
+    }
+    for (_, name, _) in &imported {
+        if created.iter().any(|c| c == name) {
+            report.created += 1;
+        } else {
+            report.updated += 1;
+        }
+    }
+
+    report.errors.sort_by_key(|e| e.line);
+    if !report.errors.is_empty() {
+        warn!(
+            "{} rows of {} were not imported",
+            report.errors.len(),
+            path.to_string_lossy()
+        );
+    }
+    Ok(report)
+}
+
+/// The first field of the item which refers to one of the dropped items, with that item's name
+fn dropped_reference(
+    value: &Value,
+    kind: ItemKind,
+    index: usize,
+    dropped: &[&Value],
+) -> Option<(String, String)> {
+    let item = &items(value, kind)[index];
+    let fields = item.as_object()?;
+    fields
+        .iter()
+        .filter(|(field, _)| reference_kind(kind, field) == Some(kind))
+        .find_map(|(field, ids)| {
+            let ids = ids
+                .as_array()
+                .map_or_else(|| vec![ids], |list| list.iter().collect());
+            dropped
+                .iter()
+                .find(|d| ids.contains(&&d["id"]))
+                .map(|d| (field.clone(), text_of(&d["name"])))
+        })
+}
+
+fn cell(record: &StringRecord, column: usize) -> &str {
+    record.get(column).unwrap_or_default().trim()
+}
+
+// -----------------------------
+// Report
+
+/// What an import did, with every row which could not be used
+#[derive(Debug, Default)]
+pub struct CsvReport {
+    pub created: usize,
+    pub updated: usize,
+    pub errors: Vec<CsvRowError>,
+}
+
+impl CsvReport {
+    fn error(&mut self, line: u64, message: String) {
+        self.errors.push(CsvRowError { line, message });
+    }
+
+    pub fn changed(&self) -> bool {
+        self.created + self.updated > 0
+    }
+}
+
+impl Display for CsvReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "created {}, updated {}, {} errors",
+            self.created,
+            self.updated,
+            self.errors.len()
+        )?;
+        for error in &self.errors {
+            write!(f, "\n{error}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct CsvRowError {
+    /// The line of the file, counting the headings as line 1
+    pub line: u64,
+    pub message: String,
+}
+
+impl Display for CsvRowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::Value;
+
+    use crate::{
+        app_data::AppData,
+        csv_file::{export, import},
+        export_format::{ItemKind, add_test_item, field_text, find_item, items},
+        persistence::test_dir,
+    };
+
+    #[test]
+    fn rows_create_update_and_report_errors() {
+        let dir = test_dir("csv");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("districts.csv");
+
+        fs::write(
+            &path,
+            "name,wealth,notable,colour\n\
+             Crow's Foot,Two,,\n\
+             Nightmarket,Five,Nobody,\n\
+             ,One,,\n",
+        )
+        .unwrap();
+        let mut value = AppData::default().to_json_value().unwrap();
+        let report = import(&mut value, ItemKind::District, &path).unwrap();
+        assert_eq!(report.created, 1);
+        // the unknown column, the unknown notable and the missing name
+        assert_eq!(report.errors.len(), 3);
+        assert!(
+            report.errors[1]
+                .to_string()
+                .starts_with("line 3: Nightmarket: notable")
+        );
+        assert!(find_item(&value, ItemKind::District, "Nightmarket").is_err());
+
+        fs::write(&path, "name,occult\nCrow's Foot,Three\n").unwrap();
+        let report = import(&mut value, ItemKind::District, &path).unwrap();
+        assert_eq!((report.created, report.updated), (0, 1));
+        let crows_foot = find_item(&value, ItemKind::District, "Crow's Foot").unwrap();
+        assert_eq!(
+            field_text(&value, ItemKind::District, crows_foot, "wealth"),
+            "Two"
+        );
+        assert_eq!(
+            field_text(&value, ItemKind::District, crows_foot, "occult"),
+            "Three"
+        );
+
+        // a value which is not one of the choices fails its own row, not the file
+        fs::write(&path, "name,wealth\nFoo,Eleven\nBar,Two\n").unwrap();
+        let report = import(&mut value, ItemKind::District, &path).unwrap();
+        assert_eq!((report.created, report.errors.len()), (1, 1));
+        assert!(report.errors[0].to_string().starts_with("line 2: Foo:"));
+        assert!(find_item(&value, ItemKind::District, "Foo").is_err());
+        assert!(AppData::from_json_value(value.clone()).is_ok());
+
+        export(&value, ItemKind::District, &path).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("name,"));
+        assert!(text.contains("Crow's Foot,"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rows_which_refer_to_a_failed_new_row_fail_too() {
+        let dir = test_dir("csv");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("factions.csv");
+        fs::write(
+            &path,
+            "name,tier,allies\n\
+             The Crows,Tier2,The Hive\n\
+             The Hive,Eleven,\n\
+             The Lampblacks,Tier1,\n",
+        )
+        .unwrap();
+
+        let mut value = AppData::default().to_json_value().unwrap();
+        let report = import(&mut value, ItemKind::Faction, &path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(report.created, 1);
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(
+            report.errors[0].to_string(),
+            "line 2: The Crows: allies: 'The Hive' was not imported"
+        );
+        assert!(find_item(&value, ItemKind::Faction, "The Crows").is_err());
+        assert!(AppData::from_json_value(value).is_ok());
+    }
+
+    #[test]
+    fn entries_with_commas_round_trip() {
+        let dir = test_dir("csv");
+        let path = dir.join("persons.csv");
+        let mut value = AppData::default().to_json_value().unwrap();
+        add_test_item(&mut value, ItemKind::District, "Silkshore, North", &[]);
+        let fields = [
+            ("personality", r#""quick, but careless", loyal"#),
+            ("found_in", "Silkshore, North"),
+        ];
+        let lyssa = add_test_item(&mut value, ItemKind::Person, "Lyssa", &fields);
+        let personality = &items(&value, ItemKind::Person)[lyssa]["personality"];
+        assert_eq!(personality[0], "quick, but careless");
+        export(&value, ItemKind::Person, &path).unwrap();
+
+        value["persons"] = Value::Array(Vec::new());
+        let report = import(&mut value, ItemKind::Person, &path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((report.created, report.errors.len()), (1, 0));
+        assert_eq!(
+            field_text(&value, ItemKind::Person, 0, "personality"),
+            r#""quick, but careless", loyal"#
+        );
+        assert_eq!(
+            field_text(&value, ItemKind::Person, 0, "found_in"),
+            "Silkshore, North"
+        );
+    }
+}
@@ -0,0 +1,52 @@
+use anyhow::{Result, anyhow};
+use rust_embed::RustEmbed;
+
+/// Campaigns which ship with the app, in the export format
+#[derive(RustEmbed)]
+#[folder = "assets/data"]
+struct Datasets;
+
+/// The districts, factions and notable people of Doskvol, as the book describes them
+pub const DOSKVOL: &str = "doskvol.json";
+
+/// Reads a bundled dataset, ready to be imported
+pub fn load(name: &str) -> Result<serde_json::Value> {
+    let file = Datasets::get(name).ok_or_else(|| anyhow!("there is no dataset '{name}'"))?;
+    Ok(serde_json::from_slice(&file.data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        app_data::AppData,
+        dataset::{DOSKVOL, load},
+        export_format::{ItemKind, items},
+    };
+
+    #[test]
+    fn doskvol_loads_with_every_reference() {
+        let raw = load(DOSKVOL).unwrap();
+        let data = AppData::from_dataset(DOSKVOL).unwrap();
+        assert!(!data.is_changed());
+        let loaded = data.to_json_value().unwrap();
+
+        for kind in [ItemKind::District, ItemKind::Person, ItemKind::Faction] {
+            assert_eq!(items(&raw, kind).len(), items(&loaded, kind).len());
+        }
+        // a reference which did not match a name would be dropped on the way in
+        let count = |value, field: &str| {
+            items(value, ItemKind::Faction)
+                .iter()
+                .map(|f| match &f[field] {
+                    serde_json::Value::Array(list) => list.len(),
+                    serde_json::Value::Null => 0,
+                    _ => 1,
+                })
+                .sum::<usize>()
+        };
+        for field in ["hq", "turf", "leader", "allies", "enemies"] {
+            assert_eq!(count(&raw, field), count(&loaded, field), "{field}");
+        }
+        assert!(AppData::from_dataset("nowhere.json").is_err());
+    }
+}
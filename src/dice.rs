@@ -0,0 +1,342 @@
+use std::fmt::Display;
+
+#[cfg(feature = "gui")]
+use eframe::egui::{
+    Button, ComboBox, Context, DragValue, Grid, RichText, ScrollArea, SidePanel, Ui,
+};
+use enum_iterator::Sequence;
+#[cfg(feature = "gui")]
+use enum_iterator::all;
+use log::info;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+#[cfg(feature = "gui")]
+use crate::app::UI_PADDING;
+use crate::localize::fl;
+
+/// The largest pool the roller offers
+pub const MAX_POOL: u8 = 10;
+
+/// How many rolls are kept in the session history
+const HISTORY_LENGTH: usize = 50;
+
+// -----------------------------
+// Rolls
+
+/// The kinds of roll in a Forged in the Dark game; they read the same dice differently
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum RollKind {
+    #[default]
+    Action,
+    Fortune,
+    Resistance,
+    Engagement,
+}
+
+impl Display for RollKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use RollKind::*;
+
+        let text = match self {
+            Action => fl!("roll_kind_action"),
+            Fortune => fl!("roll_kind_fortune"),
+            Resistance => fl!("roll_kind_resistance"),
+            Engagement => fl!("roll_kind_engagement"),
+        };
+        write!(f, "{text}")
+    }
+}
+
+/// The outcome bands: 1-3, 4/5, 6, and more than one 6
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    Failure,
+    Partial,
+    Success,
+    Critical,
+}
+
+impl Outcome {
+    /// The band of a single die
+    pub fn from_die(die: u8) -> Self {
+        match die {
+            6 => Outcome::Success,
+            4 | 5 => Outcome::Partial,
+            _ => Outcome::Failure,
+        }
+    }
+}
+
+/// A pool of d6s which has been rolled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceRoll {
+    kind: RollKind,
+    pool: u8,
+    dice: Vec<u8>,
+}
+
+#[allow(dead_code)]
+impl DiceRoll {
+    /// Rolls a pool of d6s; a pool of 0 rolls two dice and keeps the lowest
+    pub fn roll(kind: RollKind, pool: u8, rng: &mut impl Rng) -> Self {
+        let count = if pool == 0 { 2 } else { pool };
+        let dice = (0..count).map(|_| rng.gen_range(1..=6)).collect();
+        DiceRoll { kind, pool, dice }
+    }
+
+    /// A roll with known dice, for reading results that were not rolled here
+    pub fn from_dice(kind: RollKind, pool: u8, dice: Vec<u8>) -> Self {
+        DiceRoll { kind, pool, dice }
+    }
+
+    pub fn kind(&self) -> RollKind {
+        self.kind
+    }
+
+    pub fn pool(&self) -> u8 {
+        self.pool
+    }
+
+    pub fn dice(&self) -> &[u8] {
+        &self.dice
+    }
+
+    /// The die which counts: the highest, or the lowest for a zero dice roll
+    pub fn result(&self) -> u8 {
+        if self.pool == 0 {
+            self.dice.iter().min().copied().unwrap_or(1)
+        } else {
+            self.dice.iter().max().copied().unwrap_or(1)
+        }
+    }
+
+    /// More than one 6, which a zero dice roll can never get
+    pub fn is_critical(&self) -> bool {
+        self.pool > 0 && self.dice.iter().filter(|d| **d == 6).count() > 1
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        if self.is_critical() {
+            Outcome::Critical
+        } else {
+            Outcome::from_die(self.result())
+        }
+    }
+
+    /// The stress taken to resist, 6 less the result; a critical clears 1 stress instead
+    pub fn stress(&self) -> i8 {
+        if self.is_critical() {
+            -1
+        } else {
+            6 - self.result() as i8
+        }
+    }
+
+    /// What the result means for this kind of roll
+    pub fn outcome_text(&self) -> String {
+        use Outcome::*;
+        use RollKind::*;
+
+        match (self.kind, self.outcome()) {
+            (Resistance, _) => fl!("roll_stress", stress = self.stress()),
+            (Action, Failure) => fl!("roll_action_failure"),
+            (Action, Partial) => fl!("roll_action_partial"),
+            (Action, Success) => fl!("roll_action_success"),
+            (Action, Critical) => fl!("roll_action_critical"),
+            (Fortune, Failure) => fl!("roll_fortune_failure"),
+            (Fortune, Partial) => fl!("roll_fortune_partial"),
+            (Fortune, Success) => fl!("roll_fortune_success"),
+            (Fortune, Critical) => fl!("roll_fortune_critical"),
+            (Engagement, Failure) => fl!("roll_engagement_failure"),
+            (Engagement, Partial) => fl!("roll_engagement_partial"),
+            (Engagement, Success) => fl!("roll_engagement_success"),
+            (Engagement, Critical) => fl!("roll_engagement_critical"),
+        }
+    }
+
+    pub fn dice_text(&self) -> String {
+        self.dice
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+// -----------------------------
+// Roller
+
+/// The dice panel, which keeps the rolls made this session
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct DiceRoller {
+    visible: bool,
+    kind: RollKind,
+    pool: u8,
+    history: Vec<DiceRoll>,
+    rng: StdRng,
+}
+
+impl Default for DiceRoller {
+    fn default() -> Self {
+        Self::with_rng(StdRng::from_entropy())
+    }
+}
+
+#[allow(dead_code)]
+impl DiceRoller {
+    /// A roller which always gives the same rolls, for testing
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> Self {
+        DiceRoller {
+            visible: false,
+            kind: RollKind::default(),
+            pool: 1,
+            history: Vec::new(),
+            rng,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Rolls, and keeps the roll in the history, newest first
+    pub fn roll(&mut self, kind: RollKind, pool: u8) -> &DiceRoll {
+        let roll = DiceRoll::roll(kind, pool, &mut self.rng);
+        info!("rolled {kind} with {pool}: {:?}", roll.dice());
+        self.history.insert(0, roll);
+        self.history.truncate(HISTORY_LENGTH);
+        &self.history[0]
+    }
+
+    pub fn history(&self) -> &[DiceRoll] {
+        &self.history
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+}
+
+#[cfg(feature = "gui")]
+impl DiceRoller {
+    pub fn show(&mut self, ctx: &Context) {
+        if !self.visible {
+            return;
+        }
+
+        SidePanel::right("dice").resizable(false).show(ctx, |ui| {
+            ui.add_space(UI_PADDING);
+            ui.label(RichText::new(fl!("dice_heading")).heading().strong());
+            ui.add_space(UI_PADDING);
+            self.show_controls(ui);
+            ui.add_space(UI_PADDING);
+            ui.separator();
+            self.show_history(ui);
+        });
+    }
+
+    fn show_controls(&mut self, ui: &mut Ui) {
+        Grid::new("dice controls").num_columns(2).show(ui, |ui| {
+            ui.label(fl!("dice_kind"));
+            ComboBox::from_id_salt("dice kind")
+                .selected_text(self.kind.to_string())
+                .show_ui(ui, |ui| {
+                    for kind in all::<RollKind>() {
+                        ui.selectable_value(&mut self.kind, kind, kind.to_string());
+                    }
+                });
+            ui.end_row();
+
+            ui.label(fl!("dice_pool"));
+            ui.add(DragValue::new(&mut self.pool).range(0..=MAX_POOL));
+            ui.end_row();
+        });
+
+        ui.add_space(UI_PADDING);
+        ui.horizontal(|ui| {
+            if ui.button(fl!("dice_roll")).clicked() {
+                self.roll(self.kind, self.pool);
+            }
+            if ui
+                .add_enabled(!self.history.is_empty(), Button::new(fl!("dice_clear")))
+                .clicked()
+            {
+                self.clear_history();
+            }
+        });
+    }
+
+    fn show_history(&self, ui: &mut Ui) {
+        if self.history.is_empty() {
+            ui.label(RichText::new(fl!("dice_no_rolls")).italics());
+            return;
+        }
+
+        ScrollArea::vertical().show(ui, |ui| {
+            for (index, roll) in self.history.iter().enumerate() {
+                let dice = RichText::new(roll.dice_text()).monospace();
+                let dice = if index == 0 { dice.strong() } else { dice };
+                ui.horizontal(|ui| {
+                    ui.label(fl!(
+                        "dice_kind_pool",
+                        kind = roll.kind().to_string(),
+                        pool = roll.pool()
+                    ));
+                    ui.label(dice);
+                });
+                ui.label(format!("    {}", roll.outcome_text()));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use crate::dice::{DiceRoll, DiceRoller, Outcome, RollKind};
+
+    #[test]
+    fn outcome_bands_and_zero_dice() {
+        let roll = |pool, dice: &[u8]| DiceRoll::from_dice(RollKind::Action, pool, dice.to_vec());
+
+        assert_eq!(roll(2, &[1, 3]).outcome(), Outcome::Failure);
+        assert_eq!(roll(2, &[2, 5]).outcome(), Outcome::Partial);
+        assert_eq!(roll(2, &[6, 1]).outcome(), Outcome::Success);
+        assert_eq!(roll(3, &[6, 4, 6]).outcome(), Outcome::Critical);
+        // zero dice take the lowest, and cannot crit
+        assert_eq!(roll(0, &[6, 2]).outcome(), Outcome::Failure);
+        assert_eq!(roll(0, &[6, 6]).outcome(), Outcome::Success);
+
+        let resist = |dice: &[u8]| DiceRoll::from_dice(RollKind::Resistance, 2, dice.to_vec());
+        assert_eq!(resist(&[2, 4]).stress(), 2);
+        assert_eq!(resist(&[6, 6]).stress(), -1);
+    }
+
+    #[test]
+    fn seeded_rolls_repeat() {
+        let mut first = StdRng::seed_from_u64(7);
+        let mut second = StdRng::seed_from_u64(7);
+        for pool in 0..=4 {
+            let roll = DiceRoll::roll(RollKind::Fortune, pool, &mut first);
+            assert_eq!(roll, DiceRoll::roll(RollKind::Fortune, pool, &mut second));
+            let expected = if pool == 0 { 2 } else { pool as usize };
+            assert_eq!(roll.dice().len(), expected);
+            assert!(roll.dice().iter().all(|d| (1..=6).contains(d)));
+        }
+
+        let mut roller = DiceRoller::with_seed(7);
+        roller.roll(RollKind::Action, 1);
+        roller.roll(RollKind::Action, 3);
+        assert_eq!(roller.history().len(), 2);
+        assert_eq!(roller.history()[0].pool(), 3);
+    }
+}
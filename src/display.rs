@@ -0,0 +1,217 @@
+use std::{fmt::Display, slice::Iter};
+
+use enum_iterator::Sequence;
+
+use crate::{
+    app_data::DataIndex,
+    localize::fl,
+    managed_list::{GenericRef, ManagedList, Named},
+    sorting::Sorting,
+};
+
+#[allow(dead_code)]
+#[derive(Clone)]
+pub struct DisplayLine {
+    fields: Vec<String>,
+    id: DataIndex,
+}
+
+#[allow(dead_code)]
+impl DisplayLine {
+    pub fn id(&self) -> &String {
+        assert!(!self.fields.is_empty()); // this should be impossible
+        &self.fields[0]
+    }
+
+    pub fn num_fields(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn field(&self, number: usize) -> &str {
+        self.fields
+            .get(number)
+            .expect("unable to find promised index in display line")
+            .as_ref()
+    }
+
+    pub fn field_iter(&self) -> Iter<'_, String> {
+        self.fields.iter()
+    }
+}
+
+fn displayline_from_item_ref<T: Named + Clone>(item: &T, index: &GenericRef<T>) -> DisplayLine {
+    DisplayLine {
+        fields: item.display_fields(),
+        id: index.data_index(),
+    }
+}
+
+// -------------------
+#[derive(Clone)]
+pub struct DisplayTable {
+    lines: Vec<DisplayLine>,
+    columns: Vec<ColumnInfo>,
+    sorting: Sorting,
+}
+
+#[allow(dead_code)]
+impl DisplayTable {
+    pub fn lines_iter(&self) -> impl Iterator<Item = &DisplayLine> {
+        self.lines.iter()
+    }
+
+    pub fn line(&self, index: usize) -> &DisplayLine {
+        assert!(index < self.lines.len());
+        &self.lines[index]
+    }
+
+    pub fn lines_len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn column_widths_iter(&self) -> impl Iterator<Item = ColumnWidth> {
+        self.columns.iter().map(|c| c.width)
+    }
+
+    /// The column headings, and whether the table is sorted on each
+    pub fn headings_iter(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.heading.as_str(), i == self.sorting.sort_field()))
+    }
+
+    pub fn number_columns(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn sorting(&self) -> &Sorting {
+        &self.sorting
+    }
+}
+
+const MIN_COL_WIDTH: f32 = 40.0;
+const NAME_COL_WIDTH: f32 = 120.0;
+
+impl<T: Named + Clone> From<&ManagedList<T>> for DisplayTable {
+    fn from(list: &ManagedList<T>) -> Self {
+        let item_list = list.item_ref_list();
+        let mut lines: Vec<DisplayLine> = item_list
+            .iter()
+            .map(|(index, item)| displayline_from_item_ref(*item, index))
+            .collect();
+        let sorting = list.get_sorting();
+        let sort_fn = |a: &DisplayLine, b: &DisplayLine| {
+            a.fields[sorting.sort_field()].cmp(&b.fields[sorting.sort_field()])
+        };
+        lines.sort_by(sort_fn);
+        if sorting.sort_reversed() {
+            lines.reverse();
+        }
+        let headings = T::display_headings();
+        assert!(!headings.is_empty());
+        let last_heading = headings.len() - 1;
+        let columns = headings
+            .into_iter()
+            .enumerate()
+            .map(|(i, h)| {
+                use ColumnWidth::*;
+                ColumnInfo {
+                    width: if i == last_heading {
+                        Extended
+                    } else if i == 0 {
+                        Standard(NAME_COL_WIDTH)
+                    } else {
+                        Standard(MIN_COL_WIDTH)
+                    },
+                    heading: h,
+                }
+            })
+            .collect();
+        DisplayTable {
+            lines,
+            columns,
+            sorting,
+        }
+    }
+}
+
+// -----------------------
+// Column Width
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnWidth {
+    Extended,
+    Standard(f32),
+}
+
+// -----------------------
+// Column Info
+#[derive(Debug, Clone)]
+pub struct ColumnInfo {
+    width: ColumnWidth,
+    heading: String,
+}
+
+// ===========================
+// MainView
+
+#[allow(dead_code)]
+#[derive(Debug, Default, Clone, Copy, Sequence, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MainView {
+    #[default]
+    Factions,
+    Persons,
+    Districts,
+    Clocks,
+}
+
+impl MainView {
+    pub fn item_name(&self) -> String {
+        use MainView::*;
+
+        match self {
+            Factions => fl!("main_item_faction"),
+            Persons => fl!("main_item_person"),
+            Districts => fl!("main_item_district"),
+            Clocks => fl!("main_item_clock"),
+        }
+    }
+}
+
+// ===========================
+// Item Page
+
+/// Somewhere the fields of one item are written out, such as a Markdown or HTML page
+pub trait ItemPage {
+    /// A short field, left out when it is empty
+    fn field(&mut self, heading: &str, value: &str);
+
+    /// A field which refers to another item
+    fn link_field(&mut self, heading: &str, kind: MainView, name: Option<&str>);
+
+    /// A field which refers to a list of other items
+    fn links_field(&mut self, heading: &str, kind: MainView, names: &[String]);
+
+    /// A longer piece of text, under its own heading, left out when it is empty
+    fn section(&mut self, heading: &str, text: &str);
+
+    /// A bulleted list under its own heading, left out when it is empty
+    fn list_section(&mut self, heading: &str, items: &[String]);
+}
+
+impl Display for MainView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use MainView::*;
+
+        write!(
+            f,
+            "{}",
+            match self {
+                Factions => fl!("main_factions"),
+                Persons => fl!("main_persons"),
+                Districts => fl!("main_districts"),
+                Clocks => fl!("main_clocks"),
+            }
+        )
+    }
+}
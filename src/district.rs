@@ -1,22 +1,30 @@
+#[cfg(feature = "gui")]
 use eframe::egui::{Color32, RichText, TextEdit, TextStyle, Ui};
 use log::warn;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "gui")]
 use crate::{
     app::EditResult,
-    app_data::DataIndex,
     app_display::{
         DESCRIPTION_ROWS, FIELD_HORIZONTAL_SPACE, FIELD_VERTICAL_SPACE, NOTES_ROWS, ShowEdit,
         ShowEditInfo, show_edit_frame, show_edit_list,
     },
+};
+use crate::{
+    app_data::DataIndex,
+    display::{ItemPage, MainView},
     dots::Dots,
+    entity_id::{EntityId, NameIds},
     localize::fl,
     managed_list::{Named, PersonRef, PersonRefList},
+    obsidian::{NoteReader, NoteWriter},
 };
 
 #[allow(dead_code)]
 #[derive(Default, Clone, PartialEq)]
 pub struct District {
+    id: EntityId,
     name: String,
     description: String,
     wealth: Dots,
@@ -42,6 +50,20 @@ impl District {
         }
         self.notable = PersonRefList::from_list(notable);
     }
+
+    /// Returns the headings of every field which refers to the target
+    pub fn reference_fields(&self, target: DataIndex) -> Vec<String> {
+        if self.notable.contains_data_index(target) {
+            vec![fl!("notables_heading")]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Removes every reference to the target, returning whether anything changed
+    pub fn clear_references(&mut self, target: DataIndex) -> bool {
+        self.notable.remove_data_index(target)
+    }
 }
 
 // ---------------------------
@@ -50,6 +72,14 @@ impl Named for District {
         &self.name
     }
 
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: EntityId) {
+        self.id = id;
+    }
+
     fn display_name(&self) -> String {
         self.name.clone()
     }
@@ -77,17 +107,18 @@ impl Named for District {
     }
 
     // MUST agree with display_fields
-    fn display_headings() -> Vec<RichText> {
+    fn display_headings() -> Vec<String> {
         vec![
-            RichText::new(fl!("name_heading")),
-            RichText::new(fl!("wealth_heading")),
-            RichText::new(fl!("safety_heading")),
-            RichText::new(fl!("crime_heading")),
-            RichText::new(fl!("occult_heading")),
+            fl!("name_heading"),
+            fl!("wealth_heading"),
+            fl!("safety_heading"),
+            fl!("crime_heading"),
+            fl!("occult_heading"),
         ]
     }
 }
 
+#[cfg(feature = "gui")]
 impl ShowEdit for District {
     fn show_edit(&mut self, ui: &mut Ui, item_info: ShowEditInfo) -> Option<EditResult> {
         show_edit_frame(ui, fl!("main_item_district"), "district", item_info, |ui| {
@@ -171,6 +202,47 @@ pub struct DistrictStore {
     notes: String,
 }
 
+impl DistrictStore {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Writes every field, with references as links to their pages
+    pub fn write_page(&self, page: &mut impl ItemPage) {
+        page.field(&fl!("wealth_heading"), &self.wealth.to_string());
+        page.field(&fl!("safety_heading"), &self.safety.to_string());
+        page.field(&fl!("crime_heading"), &self.crime.to_string());
+        page.field(&fl!("occult_heading"), &self.occult.to_string());
+        page.links_field(&fl!("notables_heading"), MainView::Persons, &self.notable);
+        page.section(&fl!("description_heading"), &self.description);
+        page.section(&fl!("notes_heading"), &self.notes);
+    }
+
+    /// Writes the dots as frontmatter, and references as wikilinks
+    pub fn write_note(&self, note: &mut NoteWriter) {
+        note.number("wealth", self.wealth.into());
+        note.number("safety", self.safety.into());
+        note.number("crime", self.crime.into());
+        note.number("occult", self.occult.into());
+        note.links("Notables", MainView::Persons, &self.notable);
+        note.section("Description", &self.description);
+        note.section("Notes", &self.notes);
+    }
+
+    pub fn from_note(note: &NoteReader) -> Self {
+        DistrictStore {
+            name: note.name().to_string(),
+            description: note.section("Description"),
+            wealth: note.number("wealth").into(),
+            safety: note.number("safety").into(),
+            crime: note.number("crime").into(),
+            occult: note.number("occult").into(),
+            notable: note.links("Notables", MainView::Persons),
+            notes: note.section("Notes"),
+        }
+    }
+}
+
 impl From<&District> for DistrictStore {
     fn from(from_district: &District) -> Self {
         DistrictStore {
@@ -194,6 +266,87 @@ impl From<&District> for DistrictStore {
 impl From<&DistrictStore> for District {
     fn from(from_store: &DistrictStore) -> Self {
         District {
+            id: EntityId::default(), // given on add
+            name: from_store.name.clone(),
+            description: from_store.description.clone(),
+            notable: PersonRefList::default(), // added after creation
+            notes: from_store.notes.clone(),
+            wealth: from_store.wealth,
+            safety: from_store.safety,
+            crime: from_store.crime,
+            occult: from_store.occult,
+        }
+    }
+}
+
+// -----------------------------
+// Stored, version 3 (references by id)
+
+#[allow(dead_code)]
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct DistrictStore3 {
+    pub id: EntityId,
+    name: String,
+    description: String,
+    wealth: Dots,
+    safety: Dots,
+    crime: Dots,
+    occult: Dots,
+    pub notable: Vec<EntityId>, // people
+    notes: String,
+}
+
+impl DistrictStore3 {
+    /// Converts a store which refers to items by name
+    pub fn from_named(from_store: DistrictStore, ids: &NameIds) -> Self {
+        let name = from_store.name;
+        DistrictStore3 {
+            id: ids.district(&name),
+            notable: from_store
+                .notable
+                .iter()
+                .filter_map(|p| ids.person_ref(p, &format!("notable of district {name}")))
+                .collect(),
+            name,
+            description: from_store.description,
+            wealth: from_store.wealth,
+            safety: from_store.safety,
+            crime: from_store.crime,
+            occult: from_store.occult,
+            notes: from_store.notes,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl From<&District> for DistrictStore3 {
+    fn from(from_district: &District) -> Self {
+        DistrictStore3 {
+            id: from_district.id,
+            name: from_district.name.clone(),
+            description: from_district.description.clone(),
+            notable: from_district
+                .notable
+                .list()
+                .iter()
+                .filter_map(|i| i.id())
+                .collect(),
+            notes: from_district.notes.clone(),
+            wealth: from_district.wealth,
+            safety: from_district.safety,
+            crime: from_district.crime,
+            occult: from_district.occult,
+        }
+    }
+}
+
+impl From<&DistrictStore3> for District {
+    fn from(from_store: &DistrictStore3) -> Self {
+        District {
+            id: from_store.id,
             name: from_store.name.clone(),
             description: from_store.description.clone(),
             notable: PersonRefList::default(), // added after creation
@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+#[cfg(feature = "gui")]
 use eframe::egui::{ComboBox, Ui};
 use log::error;
 use serde::{Deserialize, Serialize};
@@ -23,7 +24,10 @@ impl Dots {
     pub fn combo_list ( ) -> &'static [&'static str] {  // todo: is this needed?
         DOT_STRINGS
     }
+}
 
+#[cfg(feature = "gui")]
+impl Dots {
     pub fn show_edit ( &mut self, name: &str, ui: &mut Ui ) {
         let mut selected = *self as usize;
         ComboBox::from_id_salt(name)
@@ -0,0 +1,93 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A persistent identifier for an item, which survives renames and is used
+/// for references in the save format (from version 3)
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct EntityId(Uuid);
+
+impl EntityId {
+    pub fn new() -> Self {
+        EntityId(Uuid::new_v4())
+    }
+
+    /// Items which have not yet been added to a list have no id
+    pub fn is_nil(&self) -> bool {
+        self.0.is_nil()
+    }
+}
+
+impl Display for EntityId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// ----------------------------------------
+// NameIds
+
+/// The ids given to each name, when converting a save which refers to items by name
+#[derive(Debug, Default)]
+pub struct NameIds {
+    districts: BTreeMap<String, EntityId>,
+    persons: BTreeMap<String, EntityId>,
+    factions: BTreeMap<String, EntityId>,
+}
+
+impl NameIds {
+    pub fn new(
+        districts: impl Iterator<Item = String>,
+        persons: impl Iterator<Item = String>,
+        factions: impl Iterator<Item = String>,
+    ) -> Self {
+        NameIds {
+            districts: districts.map(|n| (n, EntityId::new())).collect(),
+            persons: persons.map(|n| (n, EntityId::new())).collect(),
+            factions: factions.map(|n| (n, EntityId::new())).collect(),
+        }
+    }
+
+    /// The id given to this district name (nil if missing, so a new id is given when added)
+    pub fn district(&self, name: &str) -> EntityId {
+        self.districts.get(name).copied().unwrap_or_default()
+    }
+
+    pub fn person(&self, name: &str) -> EntityId {
+        self.persons.get(name).copied().unwrap_or_default()
+    }
+
+    pub fn faction(&self, name: &str) -> EntityId {
+        self.factions.get(name).copied().unwrap_or_default()
+    }
+
+    /// Converts a district reference, logging it if the name is not present
+    pub fn district_ref(&self, name: &str, context: &str) -> Option<EntityId> {
+        let found = self.districts.get(name).copied();
+        if found.is_none() {
+            error!("unable to find district {name} as {context}");
+        }
+        found
+    }
+
+    pub fn person_ref(&self, name: &str, context: &str) -> Option<EntityId> {
+        let found = self.persons.get(name).copied();
+        if found.is_none() {
+            error!("unable to find person {name} as {context}");
+        }
+        found
+    }
+
+    pub fn faction_ref(&self, name: &str, context: &str) -> Option<EntityId> {
+        let found = self.factions.get(name).copied();
+        if found.is_none() {
+            error!("unable to find faction {name} as {context}");
+        }
+        found
+    }
+}
@@ -0,0 +1,278 @@
+use std::fmt::Display;
+
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+use crate::{
+    action::Action,
+    app_data::AppData,
+    district::{District, DistrictStore3},
+    faction::{Faction, FactionStore3},
+    person::{Person, PersonStore3},
+};
+
+// -----------------------------
+// Items
+
+/// The kinds of item which are kept in lists in the export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Faction,
+    Person,
+    District,
+}
+
+impl ItemKind {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "faction" | "factions" => Some(ItemKind::Faction),
+            "person" | "persons" | "people" => Some(ItemKind::Person),
+            "district" | "districts" => Some(ItemKind::District),
+            _ => None,
+        }
+    }
+
+    /// The list of these items in the export format
+    pub fn key(&self) -> &'static str {
+        match self {
+            ItemKind::Faction => "factions",
+            ItemKind::Person => "persons",
+            ItemKind::District => "districts",
+        }
+    }
+
+    /// An empty item in the export format, with every field present
+    pub fn template(&self) -> Result<Value> {
+        let mut scratch = AppData::default();
+        let mut node = match self {
+            ItemKind::Faction => Action::FactionAdd(Faction::default()),
+            ItemKind::Person => Action::PersonAdd(Person::default()),
+            ItemKind::District => Action::DistrictAdd(District::default()),
+        }
+        .into();
+        scratch.do_action(&mut node)?;
+        items(&scratch.to_json_value()?, *self)
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow!("unable to create an empty {self}"))
+    }
+
+    /// Checks that an item in the export format can be read as this kind, so that a bad
+    /// value is found in its own item, rather than failing all of the data
+    pub fn check(&self, item: &Value) -> Result<()> {
+        let item = item.clone();
+        match self {
+            ItemKind::Faction => serde_json::from_value::<FactionStore3>(item).map(|_| ()),
+            ItemKind::Person => serde_json::from_value::<PersonStore3>(item).map(|_| ()),
+            ItemKind::District => serde_json::from_value::<DistrictStore3>(item).map(|_| ()),
+        }
+        .map_err(|e| anyhow!("{e}"))
+    }
+}
+
+impl Display for ItemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ItemKind::Faction => "faction",
+            ItemKind::Person => "person",
+            ItemKind::District => "district",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// The kind of item a field refers to, by id
+pub fn reference_kind(kind: ItemKind, field: &str) -> Option<ItemKind> {
+    use ItemKind::*;
+
+    match (kind, field) {
+        (Faction, "hq" | "turf") | (Person, "found_in") => Some(District),
+        (Faction, "leader" | "notable") | (District, "notable") => Some(Person),
+        (Faction, "allies" | "enemies") => Some(Faction),
+        _ => None,
+    }
+}
+
+pub fn items(value: &Value, kind: ItemKind) -> &[Value] {
+    value[kind.key()]
+        .as_array()
+        .map_or(&[], |list| list.as_slice())
+}
+
+/// Finds an item by name, or by id
+pub fn find_item(value: &Value, kind: ItemKind, name: &str) -> Result<usize> {
+    items(value, kind)
+        .iter()
+        .position(|item| item["name"] == name || item["id"] == name)
+        .ok_or_else(|| anyhow!("there is no {kind} named '{name}'"))
+}
+
+fn find_id(value: &Value, kind: ItemKind, name: &str) -> Result<Value> {
+    let index = find_item(value, kind, name)?;
+    Ok(items(value, kind)[index]["id"].clone())
+}
+
+// -----------------------------
+// Fields
+
+/// Separates the entries of a list written as text
+const LIST_SEPARATOR: char = ',';
+/// Surrounds an entry which has the separator in it, and is doubled within one
+const LIST_QUOTE: char = '"';
+
+/// Sets the field from text, converting it to the type already in the field
+pub fn set_field(
+    value: &mut Value,
+    kind: ItemKind,
+    index: usize,
+    field: &str,
+    text: &str,
+) -> Result<()> {
+    let Some(current) = items(value, kind)[index].get(field) else {
+        return Err(anyhow!("a {kind} has no field '{field}'"));
+    };
+
+    let new_value = match (reference_kind(kind, field), current) {
+        (Some(target), Value::Array(_)) => Value::Array(
+            split_list(text)
+                .iter()
+                .map(|name| find_id(value, target, name))
+                .collect::<Result<Vec<Value>>>()?,
+        ),
+        (Some(_), _) if text.is_empty() => Value::Null,
+        (Some(target), _) => find_id(value, target, text)?,
+        (None, Value::String(_)) => Value::String(text.to_string()),
+        // lists of anything but text, such as clocks, are written as JSON
+        (None, Value::Array(_)) if text.trim_start().starts_with('[') => {
+            serde_json::from_str(text).map_err(|e| anyhow!("'{field}' is not a list: {e}"))?
+        }
+        (None, Value::Array(_)) => {
+            Value::Array(split_list(text).into_iter().map(Value::String).collect())
+        }
+        // numbers and enums; anything which is not JSON is taken as a string
+        (None, _) => serde_json::from_str(text).unwrap_or(Value::String(text.to_string())),
+    };
+    value[kind.key()][index][field] = new_value;
+    Ok(())
+}
+
+/// The field as text, in the form set_field reads it back
+pub fn field_text(value: &Value, kind: ItemKind, index: usize, field: &str) -> String {
+    let field_value = &items(value, kind)[index][field];
+    match (reference_kind(kind, field), field_value) {
+        (Some(target), _) => names_of(value, target, field_value),
+        (None, Value::Array(list)) if list.iter().any(|v| !v.is_string()) => {
+            field_value.to_string()
+        }
+        _ => text_of(field_value),
+    }
+}
+
+/// The entries of a list written by join_list, where a quoted entry may hold the separator
+pub fn split_list(text: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut chars = text.chars().peekable();
+    while chars.peek().is_some() {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let quoted = chars.next_if_eq(&LIST_QUOTE).is_some();
+        let mut entry = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                LIST_QUOTE if quoted && chars.next_if_eq(&LIST_QUOTE).is_none() => {
+                    // anything between the closing quote and the separator is dropped
+                    chars.by_ref().find(|c| *c == LIST_SEPARATOR);
+                    break;
+                }
+                LIST_SEPARATOR if !quoted => break,
+                _ => entry.push(c),
+            }
+        }
+        let entry = if quoted {
+            entry
+        } else {
+            entry.trim().to_string()
+        };
+        if !entry.is_empty() {
+            entries.push(entry);
+        }
+    }
+    entries
+}
+
+/// A list as text, quoting any entry which split_list would not read back as it is
+pub fn join_list(entries: impl IntoIterator<Item = String>) -> String {
+    entries
+        .into_iter()
+        .map(|entry| {
+            if entry.contains(LIST_SEPARATOR)
+                || entry.starts_with(LIST_QUOTE)
+                || entry.trim() != entry
+            {
+                let quote = LIST_QUOTE.to_string();
+                format!("{quote}{}{quote}", entry.replace(&quote, &quote.repeat(2)))
+            } else {
+                entry
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&format!("{LIST_SEPARATOR} "))
+}
+
+pub fn text_of(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        Value::Array(list) if list.iter().all(Value::is_string) => {
+            join_list(list.iter().map(text_of))
+        }
+        other => other.to_string(),
+    }
+}
+
+/// The names of the items which a reference field holds the ids of
+pub fn names_of(value: &Value, kind: ItemKind, ids: &Value) -> String {
+    let name_of = |id: &Value| {
+        items(value, kind)
+            .iter()
+            .find(|item| item["id"] == *id)
+            .map_or_else(|| text_of(id), |item| text_of(&item["name"]))
+    };
+    match ids {
+        Value::Array(list) => join_list(list.iter().map(name_of)),
+        Value::Null => String::new(),
+        id => name_of(id),
+    }
+}
+
+/// Adds an item with a new id for a test, setting each field from text, and returns its index
+#[cfg(test)]
+pub fn add_test_item(
+    value: &mut Value,
+    kind: ItemKind,
+    name: &str,
+    fields: &[(&str, &str)],
+) -> usize {
+    let mut item = kind.template().unwrap();
+    item["id"] = Value::String(crate::entity_id::EntityId::new().to_string());
+    item["name"] = Value::String(name.to_string());
+    let list = value[kind.key()].as_array_mut().unwrap();
+    list.push(item);
+    let index = list.len() - 1;
+    for (field, text) in fields {
+        set_field(value, kind, index, field, text).unwrap();
+    }
+    index
+}
+
+/// A campaign for a test, with the districts given and then the factions with their fields
+#[cfg(test)]
+pub fn test_campaign(districts: &[&str], factions: &[(&str, &[(&str, &str)])]) -> Value {
+    let mut value = AppData::default().to_json_value().unwrap();
+    for district in districts {
+        add_test_item(&mut value, ItemKind::District, district, &[]);
+    }
+    for (name, fields) in factions {
+        add_test_item(&mut value, ItemKind::Faction, name, fields);
+    }
+    value
+}
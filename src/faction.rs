@@ -1,25 +1,35 @@
-use eframe::egui::{Color32, RichText, TextEdit, TextStyle, Ui};
+#[cfg(feature = "gui")]
+use eframe::egui::{Color32, Label, RichText, Sense, TextEdit, TextStyle, Ui};
+#[cfg(feature = "gui")]
+use log::info;
 use log::{error, warn};
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "gui")]
 use crate::{
     app::EditResult,
-    app_data::DataIndex,
     app_display::{
         DESCRIPTION_ROWS, FIELD_HORIZONTAL_SPACE, FIELD_VERTICAL_SPACE, NOTES_ROWS, ShowEdit,
         ShowEditInfo, show_edit_frame, show_edit_item, show_edit_list,
     },
+};
+use crate::{
+    app_data::DataIndex,
     clock::Clock,
+    display::{ItemPage, MainView},
+    entity_id::{EntityId, NameIds},
     localize::fl,
     managed_list::{
         DistrictRef, DistrictRefList, FactionRef, FactionRefList, Named, PersonRef, PersonRefList,
     },
+    obsidian::{NoteReader, NoteWriter},
     tier::Tier,
 };
 
 #[allow(dead_code)]
 #[derive(Default, Clone, PartialEq)]
 pub struct Faction {
+    id: EntityId,
     name: String,
     description: String,
     tier: Tier,
@@ -40,6 +50,14 @@ impl Named for Faction {
         &self.name
     }
 
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: EntityId) {
+        self.id = id;
+    }
+
     fn display_name(&self) -> String {
         self.name.clone()
     }
@@ -77,22 +95,60 @@ impl Named for Faction {
                 })
                 .collect::<Vec<String>>()
                 .join(", "),
+            self.clocks
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
             //hq loc?
         ]
     }
 
     // MUST agree with display_fields
-    fn display_headings() -> Vec<RichText> {
+    fn display_headings() -> Vec<String> {
         vec![
-            RichText::new(fl!("name_heading")),
-            RichText::new(fl!("tier_heading")),
-            RichText::new(fl!("hq_heading")),
-            RichText::new(fl!("turf_heading")),
+            fl!("name_heading"),
+            fl!("tier_heading"),
+            fl!("hq_heading"),
+            fl!("turf_heading"),
+            fl!("clocks_heading"),
         ]
     }
 }
 
 impl Faction {
+    pub fn tier(&self) -> Tier {
+        self.tier
+    }
+
+    pub fn clocks(&self) -> &[Clock] {
+        &self.clocks
+    }
+
+    pub fn hq(&self) -> Option<&DistrictRef> {
+        self.hq.as_ref()
+    }
+
+    pub fn turf(&self) -> &[DistrictRef] {
+        self.turf.list()
+    }
+
+    pub fn leader(&self) -> Option<&PersonRef> {
+        self.leader.as_ref()
+    }
+
+    pub fn allies(&self) -> &[FactionRef] {
+        self.allies.list()
+    }
+
+    pub fn enemies(&self) -> &[FactionRef] {
+        self.enemies.list()
+    }
+
+    pub fn clocks_mut(&mut self) -> &mut Vec<Clock> {
+        &mut self.clocks
+    }
+
     pub fn set_hq(&mut self, hq: Option<DistrictRef>) {
         if self.hq.is_some() {
             warn!("replacing hq of {} when it is not empty", self.name);
@@ -134,8 +190,59 @@ impl Faction {
         }
         self.enemies = FactionRefList::from_list(enemies);
     }
+
+    /// Returns the headings of every field which refers to the target
+    pub fn reference_fields(&self, target: DataIndex) -> Vec<String> {
+        let mut fields = Vec::new();
+        if self.hq.as_ref().is_some_and(|r| r.is_data_index(target)) {
+            fields.push(fl!("hq_heading"));
+        }
+        if self.turf.contains_data_index(target) {
+            fields.push(fl!("turf_heading"));
+        }
+        if self
+            .leader
+            .as_ref()
+            .is_some_and(|r| r.is_data_index(target))
+        {
+            fields.push(fl!("leader_heading"));
+        }
+        if self.notable.contains_data_index(target) {
+            fields.push(fl!("notables_heading"));
+        }
+        if self.allies.contains_data_index(target) {
+            fields.push(fl!("allies_heading"));
+        }
+        if self.enemies.contains_data_index(target) {
+            fields.push(fl!("enemies_heading"));
+        }
+        fields
+    }
+
+    /// Removes every reference to the target, returning whether anything changed
+    pub fn clear_references(&mut self, target: DataIndex) -> bool {
+        let mut changed = false;
+        if self.hq.as_ref().is_some_and(|r| r.is_data_index(target)) {
+            self.hq = None;
+            changed = true;
+        }
+        if self
+            .leader
+            .as_ref()
+            .is_some_and(|r| r.is_data_index(target))
+        {
+            self.leader = None;
+            changed = true;
+        }
+        changed |= self.turf.remove_data_index(target);
+        changed |= self.notable.remove_data_index(target);
+        changed |= self.allies.remove_data_index(target);
+        changed |= self.enemies.remove_data_index(target);
+        changed
+    }
 }
 
+#[cfg(feature = "gui")]
 impl ShowEdit for Faction {
     fn show_edit(&mut self, ui: &mut Ui, item_info: ShowEditInfo) -> Option<EditResult> {
         show_edit_frame(ui, fl!("main_item_faction"), "faction", item_info, |ui| {
@@ -239,6 +346,28 @@ impl ShowEdit for Faction {
                     });
                 });
 
+                ui.add_space(FIELD_VERTICAL_SPACE);
+                ui.label(RichText::new(fl!("clocks_heading")).small().weak());
+                let mut remove = None;
+                for (i, clock) in self.clocks.iter_mut().enumerate() {
+                    if clock.show_edit(&format!("clock {i}"), ui) {
+                        remove = Some(i);
+                    }
+                }
+                if let Some(i) = remove {
+                    info!("removing clock {}", self.clocks[i].name());
+                    self.clocks.remove(i);
+                }
+                if ui
+                    .add(Label::new(RichText::new("+").strong()).sense(Sense::click()))
+                    .clicked()
+                {
+                    info!("adding clock to {}", self.name);
+                    self.clocks.push(Clock::new_clock_4(&fl!("clock_new"), ""));
+                }
+
+                item_info.show_edit_relationships(ui);
+
                 ui.add_space(FIELD_VERTICAL_SPACE * 2.0);
                 ui.label(RichText::new(fl!("notes_heading")).small().weak());
                 ui.add(
@@ -272,6 +401,67 @@ pub struct FactionStore {
     clocks: Vec<Clock>,
 }
 
+impl FactionStore {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Writes every field, with references as links to their pages
+    pub fn write_page(&self, page: &mut impl ItemPage) {
+        page.field(&fl!("tier_heading"), &self.tier.to_string());
+        page.link_field(&fl!("hq_heading"), MainView::Districts, self.hq.as_deref());
+        page.links_field(&fl!("turf_heading"), MainView::Districts, &self.turf);
+        page.link_field(
+            &fl!("leader_heading"),
+            MainView::Persons,
+            self.leader.as_deref(),
+        );
+        page.links_field(&fl!("notables_heading"), MainView::Persons, &self.notable);
+        page.links_field(&fl!("allies_heading"), MainView::Factions, &self.allies);
+        page.links_field(&fl!("enemies_heading"), MainView::Factions, &self.enemies);
+        page.section(&fl!("description_heading"), &self.description);
+        page.section(&fl!("assets_heading"), &self.assets);
+        page.section(&fl!("general_heading"), &self.general);
+        let clocks: Vec<String> = self.clocks.iter().map(|c| c.to_string()).collect();
+        page.list_section(&fl!("clocks_heading"), &clocks);
+        page.section(&fl!("notes_heading"), &self.notes);
+    }
+
+    /// Writes the tier as frontmatter, and references as wikilinks
+    pub fn write_note(&self, note: &mut NoteWriter) {
+        note.number("tier", self.tier.into());
+        note.links("HQ", MainView::Districts, self.hq.as_slice());
+        note.links("Turf", MainView::Districts, &self.turf);
+        note.links("Leader", MainView::Persons, self.leader.as_slice());
+        note.links("Notables", MainView::Persons, &self.notable);
+        note.links("Allies", MainView::Factions, &self.allies);
+        note.links("Enemies", MainView::Factions, &self.enemies);
+        note.section("Description", &self.description);
+        note.section("Assets", &self.assets);
+        note.section("General", &self.general);
+        note.clocks("Clocks", &self.clocks);
+        note.section("Notes", &self.notes);
+    }
+
+    pub fn from_note(note: &NoteReader) -> Self {
+        FactionStore {
+            name: note.name().to_string(),
+            description: note.section("Description"),
+            tier: note.number("tier").into(),
+            hq: note.link("HQ", MainView::Districts),
+            turf: note.links("Turf", MainView::Districts),
+            leader: note.link("Leader", MainView::Persons),
+            notable: note.links("Notables", MainView::Persons),
+            assets: note.section("Assets"),
+            notes: note.section("Notes"),
+            allies: note.links("Allies", MainView::Factions),
+            enemies: note.links("Enemies", MainView::Factions),
+            general: note.section("General"),
+            clocks: note.clocks("Clocks"),
+        }
+    }
+}
+
 impl From<&Faction> for FactionStore {
     fn from(from_faction: &Faction) -> Self {
         FactionStore {
@@ -315,6 +505,138 @@ impl From<&Faction> for FactionStore {
 impl From<&FactionStore> for Faction {
     fn from(from_store: &FactionStore) -> Self {
         Faction {
+            id: EntityId::default(), // given on add
+            name: from_store.name.clone(),
+            description: from_store.description.clone(),
+            tier: from_store.tier,
+            hq: None,                          // added after creation
+            turf: DistrictRefList::default(),  // added after creation
+            leader: None,                      // added after creation
+            notable: PersonRefList::default(), // added after creation
+            assets: from_store.assets.clone(),
+            notes: from_store.notes.clone(),
+            allies: FactionRefList::default(), // added after creation
+            enemies: FactionRefList::default(), // added after creation
+            general: from_store.general.clone(),
+            clocks: from_store.clocks.clone(),
+        }
+    }
+}
+
+// -----------------------------
+// Stored, version 3 (references by id)
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FactionStore3 {
+    pub id: EntityId,
+    name: String,
+    description: String,
+    tier: Tier,
+    pub hq: Option<EntityId>,     // district
+    pub turf: Vec<EntityId>,      // districts
+    pub leader: Option<EntityId>, // person
+    pub notable: Vec<EntityId>,   // people
+    assets: String,
+    notes: String,
+    pub allies: Vec<EntityId>,  // factions
+    pub enemies: Vec<EntityId>, // factions
+    general: String,
+    clocks: Vec<Clock>,
+}
+
+impl FactionStore3 {
+    /// Converts a store which refers to items by name
+    pub fn from_named(from_store: FactionStore, ids: &NameIds) -> Self {
+        let name = from_store.name;
+        FactionStore3 {
+            id: ids.faction(&name),
+            hq: from_store
+                .hq
+                .and_then(|d| ids.district_ref(&d, &format!("hq of faction {name}"))),
+            turf: from_store
+                .turf
+                .iter()
+                .filter_map(|d| ids.district_ref(d, &format!("turf of faction {name}")))
+                .collect(),
+            leader: from_store
+                .leader
+                .and_then(|p| ids.person_ref(&p, &format!("leader of faction {name}"))),
+            notable: from_store
+                .notable
+                .iter()
+                .filter_map(|p| ids.person_ref(p, &format!("notable of faction {name}")))
+                .collect(),
+            allies: from_store
+                .allies
+                .iter()
+                .filter_map(|f| ids.faction_ref(f, &format!("ally of faction {name}")))
+                .collect(),
+            enemies: from_store
+                .enemies
+                .iter()
+                .filter_map(|f| ids.faction_ref(f, &format!("enemy of faction {name}")))
+                .collect(),
+            name,
+            description: from_store.description,
+            tier: from_store.tier,
+            assets: from_store.assets,
+            notes: from_store.notes,
+            general: from_store.general,
+            clocks: from_store.clocks,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl From<&Faction> for FactionStore3 {
+    fn from(from_faction: &Faction) -> Self {
+        FactionStore3 {
+            id: from_faction.id,
+            name: from_faction.name.clone(),
+            description: from_faction.description.clone(),
+            tier: from_faction.tier,
+            hq: from_faction.hq.as_ref().and_then(|i| i.id()),
+            turf: from_faction
+                .turf
+                .list()
+                .iter()
+                .filter_map(|i| i.id())
+                .collect(),
+            leader: from_faction.leader.as_ref().and_then(|i| i.id()),
+            notable: from_faction
+                .notable
+                .list()
+                .iter()
+                .filter_map(|i| i.id())
+                .collect(),
+            assets: from_faction.assets.clone(),
+            notes: from_faction.notes.clone(),
+            allies: from_faction
+                .allies
+                .list()
+                .iter()
+                .filter_map(|i| i.id())
+                .collect(),
+            enemies: from_faction
+                .enemies
+                .list()
+                .iter()
+                .filter_map(|i| i.id())
+                .collect(),
+            general: from_faction.general.clone(),
+            clocks: from_faction.clocks.clone(),
+        }
+    }
+}
+
+impl From<&FactionStore3> for Faction {
+    fn from(from_store: &FactionStore3) -> Self {
+        Faction {
+            id: from_store.id,
             name: from_store.name.clone(),
             description: from_store.description.clone(),
             tier: from_store.tier,
@@ -0,0 +1,482 @@
+use std::f32::consts::TAU;
+
+#[cfg(feature = "gui")]
+use eframe::egui::{
+    Align2, Button, Color32, CursorIcon, FontId, Pos2, RichText, Sense, Shape, Stroke, Ui, Vec2,
+};
+#[cfg(feature = "gui")]
+use log::info;
+
+#[cfg(feature = "gui")]
+use crate::{app::UI_PADDING, localize::fl};
+use crate::{
+    app_data::AppData,
+    faction::Faction,
+    managed_list::{GenericRef, Named},
+    tier::Tier,
+};
+
+/// The distance the layout aims for between allies
+const IDEAL_LENGTH: f32 = 140.0;
+/// Enemies pull together this much more weakly than allies, so they settle further apart
+const ENEMY_SLACK: f32 = 4.0;
+/// How strongly every faction is drawn to the middle, so unconnected ones don't drift away
+const GRAVITY: f32 = 0.05;
+
+/// The most a faction may move in the first step of the layout; this cools with each step
+const START_TEMPERATURE: f32 = 100.0;
+const COOLING: f32 = 0.95;
+/// Below this the layout has settled, and stops moving
+const MIN_TEMPERATURE: f32 = 0.5;
+/// How much the layout wakes up after a faction has been dragged
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+const DRAG_TEMPERATURE: f32 = 20.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EdgeKind {
+    Ally,
+    Enemy,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct GraphPoint {
+    x: f32,
+    y: f32,
+}
+
+impl GraphPoint {
+    fn new(x: f32, y: f32) -> Self {
+        GraphPoint { x, y }
+    }
+
+    fn length(&self) -> f32 {
+        self.x.hypot(self.y)
+    }
+
+    fn to(&self, other: GraphPoint) -> GraphPoint {
+        GraphPoint::new(other.x - self.x, other.y - self.y)
+    }
+}
+
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Debug, Clone)]
+struct GraphNode {
+    name: String,
+    tier: Tier,
+    position: GraphPoint,
+}
+
+/// Every faction, with its allies and enemies, laid out so they can be drawn as a graph.
+/// The positions are kept between frames, and as factions are added or changed
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Debug, Clone, Default)]
+pub struct FactionGraph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<(usize, usize, EdgeKind)>,
+    temperature: f32,
+    /// A faction held by the pointer, which the layout leaves where it is
+    pinned: Option<usize>,
+    pan: GraphPoint,
+    zoom: f32,
+}
+
+impl FactionGraph {
+    /// Brings the graph up to date with the factions, keeping the positions of those already there
+    pub fn sync(&mut self, data: &AppData) {
+        self.sync_filtered(data, |_| true);
+    }
+
+    /// Brings the graph up to date with only the factions which are kept
+    pub fn sync_filtered(&mut self, data: &AppData, keep: impl Fn(&Faction) -> bool) {
+        let mut factions = data.faction_list().item_ref_list();
+        factions.retain(|(_, f)| keep(f));
+        let names = |list: &[GenericRef<_>]| -> Vec<String> {
+            list.iter().filter_map(|r| r.name()).collect()
+        };
+        let nodes = factions
+            .iter()
+            .map(|(_, f)| (f.name().to_string(), f.tier()))
+            .collect();
+        let mut edges = Vec::new();
+        for (_, faction) in &factions {
+            for ally in names(faction.allies()) {
+                edges.push((faction.name().to_string(), ally, EdgeKind::Ally));
+            }
+            for enemy in names(faction.enemies()) {
+                edges.push((faction.name().to_string(), enemy, EdgeKind::Enemy));
+            }
+        }
+        self.set_items(nodes, edges);
+    }
+
+    fn set_items(&mut self, items: Vec<(String, Tier)>, edges: Vec<(String, String, EdgeKind)>) {
+        let first_layout = self.nodes.is_empty();
+        let mut changed = items.len() != self.nodes.len();
+        let count = items.len();
+        let nodes: Vec<GraphNode> = items
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, tier))| {
+                let position = match self.nodes.iter().find(|n| n.name == name) {
+                    Some(node) => node.position,
+                    None => {
+                        changed = true;
+                        circle_position(index, count)
+                    }
+                };
+                GraphNode {
+                    name,
+                    tier,
+                    position,
+                }
+            })
+            .collect();
+
+        let index_of = |name: &str| nodes.iter().position(|n| n.name == name);
+        let mut new_edges: Vec<(usize, usize, EdgeKind)> = Vec::new();
+        for (from, to, kind) in edges {
+            let (Some(from), Some(to)) = (index_of(&from), index_of(&to)) else {
+                continue;
+            };
+            // an alliance held by both sides is one edge
+            let (from, to) = (from.min(to), from.max(to));
+            if from != to && !new_edges.contains(&(from, to, kind)) {
+                new_edges.push((from, to, kind));
+            }
+        }
+        changed |= new_edges != self.edges;
+
+        self.nodes = nodes;
+        self.edges = new_edges;
+        if self.pinned.is_some_and(|p| p >= self.nodes.len()) {
+            self.pinned = None;
+        }
+        if first_layout {
+            self.zoom = 1.0;
+        }
+        if changed {
+            self.temperature = START_TEMPERATURE;
+        }
+    }
+
+    /// Places every faction evenly around a circle, and leaves them there
+    pub fn arrange_circle(&mut self) {
+        let count = self.nodes.len();
+        for (index, node) in self.nodes.iter_mut().enumerate() {
+            node.position = circle_position(index, count);
+        }
+        self.temperature = 0.0;
+    }
+
+    /// Lets the factions push apart, and allies and enemies pull together, until they settle
+    pub fn spread_out(&mut self) {
+        self.temperature = START_TEMPERATURE;
+    }
+
+    pub fn is_settled(&self) -> bool {
+        self.temperature < MIN_TEMPERATURE
+    }
+
+    /// Steps the layout until it has settled
+    pub fn settle(&mut self) {
+        // every step cools the layout, so this always ends
+        while self.step() {}
+    }
+
+    /// Where each faction is, by name
+    pub fn positions(&self) -> impl Iterator<Item = (&str, f32, f32)> {
+        self.nodes
+            .iter()
+            .map(|n| (n.name.as_str(), n.position.x, n.position.y))
+    }
+
+    /// Moves each faction one step of a force directed layout, returning whether anything moved
+    pub fn step(&mut self) -> bool {
+        if self.is_settled() {
+            return false;
+        }
+        let mut moves = vec![GraphPoint::default(); self.nodes.len()];
+
+        // every faction pushes every other away
+        for i in 0..self.nodes.len() {
+            for j in (i + 1)..self.nodes.len() {
+                let mut apart = self.nodes[j].position.to(self.nodes[i].position);
+                if apart.length() < 1.0 {
+                    // on top of each other, so push them apart in some direction
+                    apart = GraphPoint::new((i + 1) as f32, (j + 1) as f32);
+                }
+                let distance = apart.length();
+                let force = IDEAL_LENGTH * IDEAL_LENGTH / distance;
+                moves[i].x += apart.x / distance * force;
+                moves[i].y += apart.y / distance * force;
+                moves[j].x -= apart.x / distance * force;
+                moves[j].y -= apart.y / distance * force;
+            }
+        }
+
+        // allies, and more weakly enemies, pull together
+        for (from, to, kind) in &self.edges {
+            let together = self.nodes[*from].position.to(self.nodes[*to].position);
+            let distance = together.length().max(1.0);
+            let slack = match kind {
+                EdgeKind::Ally => 1.0,
+                EdgeKind::Enemy => ENEMY_SLACK,
+            };
+            let force = distance * distance / (IDEAL_LENGTH * slack);
+            moves[*from].x += together.x / distance * force;
+            moves[*from].y += together.y / distance * force;
+            moves[*to].x -= together.x / distance * force;
+            moves[*to].y -= together.y / distance * force;
+        }
+
+        for (index, (node, step)) in self.nodes.iter_mut().zip(moves).enumerate() {
+            if self.pinned == Some(index) {
+                continue;
+            }
+            let step = GraphPoint::new(
+                step.x - node.position.x * GRAVITY,
+                step.y - node.position.y * GRAVITY,
+            );
+            let length = step.length();
+            if length > 0.0 {
+                let limit = length.min(self.temperature);
+                node.position.x += step.x / length * limit;
+                node.position.y += step.y / length * limit;
+            }
+        }
+        self.temperature *= COOLING;
+        true
+    }
+
+    /// How big a faction is drawn, growing with their tier
+    pub fn node_radius(tier: Tier) -> f32 {
+        const BASE_RADIUS: f32 = 14.0;
+        const TIER_RADIUS: f32 = 5.0;
+        BASE_RADIUS + TIER_RADIUS * usize::from(tier) as f32
+    }
+}
+
+/// Where a faction goes, when they are spread evenly around a circle
+fn circle_position(index: usize, count: usize) -> GraphPoint {
+    let radius = (IDEAL_LENGTH * count as f32 / TAU).max(IDEAL_LENGTH);
+    let angle = TAU * index as f32 / count.max(1) as f32;
+    GraphPoint::new(radius * angle.cos(), radius * angle.sin())
+}
+
+#[cfg(feature = "gui")]
+impl FactionGraph {
+    /// Draws the graph, returning the name of a faction when it is clicked
+    pub fn show(&mut self, ui: &mut Ui) -> Option<String> {
+        const MIN_ZOOM: f32 = 0.2;
+        const MAX_ZOOM: f32 = 4.0;
+        const SCROLL_ZOOM: f32 = 0.002;
+        const LABEL_SIZE: f32 = 14.0;
+        const TIER_SIZE: f32 = 16.0;
+        const EDGE_WIDTH: f32 = 2.0;
+        const DASH_LENGTH: f32 = 8.0;
+        const GAP_LENGTH: f32 = 6.0;
+
+        let ally_color = Color32::from_rgb(60, 160, 60);
+        let enemy_color = Color32::from_rgb(200, 60, 60);
+
+        ui.add_space(UI_PADDING);
+        ui.horizontal(|ui| {
+            if ui.add(Button::new(fl!("graph_circle"))).clicked() {
+                info!("arranging faction graph in a circle");
+                self.arrange_circle();
+            }
+            if ui.add(Button::new(fl!("graph_spread"))).clicked() {
+                info!("spreading out faction graph");
+                self.spread_out();
+            }
+            if ui.add(Button::new(fl!("graph_reset_view"))).clicked() {
+                self.pan = GraphPoint::default();
+                self.zoom = 1.0;
+            }
+            ui.add_space(UI_PADDING);
+            ui.label(RichText::new(format!("— {}", fl!("allies_heading"))).color(ally_color));
+            ui.label(RichText::new(format!("- - {}", fl!("enemies_heading"))).color(enemy_color));
+        });
+        ui.add_space(UI_PADDING);
+
+        let (response, painter) = ui.allocate_painter(ui.available_size(), Sense::click_and_drag());
+        let rect = response.rect;
+        let origin = rect.center() + Vec2::new(self.pan.x, self.pan.y);
+        let zoom = self.zoom;
+        let to_screen = |p: GraphPoint| -> Pos2 { origin + Vec2::new(p.x * zoom, p.y * zoom) };
+        let node_at = |nodes: &[GraphNode], pos: Pos2| -> Option<usize> {
+            // the last drawn is on top
+            nodes.iter().rposition(|n| {
+                to_screen(n.position).distance(pos) <= Self::node_radius(n.tier) * zoom
+            })
+        };
+
+        // zoom about the pointer, so what is under it stays there
+        if let Some(pointer) = response.hover_pos() {
+            let (scroll, pinch) = ui.input(|i| (i.smooth_scroll_delta.y, i.zoom_delta()));
+            let factor = pinch * (scroll * SCROLL_ZOOM).exp();
+            if factor != 1.0 {
+                let new_zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+                let from_origin = pointer - origin;
+                let moved = from_origin * (new_zoom / self.zoom) - from_origin;
+                self.pan.x -= moved.x;
+                self.pan.y -= moved.y;
+                self.zoom = new_zoom;
+            }
+        }
+
+        // drag a faction to move it, or anywhere else to pan
+        if response.drag_started() {
+            self.pinned = response
+                .interact_pointer_pos()
+                .and_then(|pos| node_at(&self.nodes, pos));
+        }
+        if response.dragged() {
+            let delta = response.drag_delta();
+            match self.pinned {
+                Some(index) => {
+                    self.nodes[index].position.x += delta.x / self.zoom;
+                    self.nodes[index].position.y += delta.y / self.zoom;
+                }
+                None => {
+                    self.pan.x += delta.x;
+                    self.pan.y += delta.y;
+                }
+            }
+        }
+        if response.drag_stopped() && self.pinned.take().is_some() {
+            self.temperature = self.temperature.max(DRAG_TEMPERATURE);
+        }
+
+        let mut clicked = None;
+        if response.clicked()
+            && let Some(index) = response
+                .interact_pointer_pos()
+                .and_then(|pos| node_at(&self.nodes, pos))
+        {
+            clicked = Some(self.nodes[index].name.clone());
+        }
+
+        if self.step() {
+            ui.ctx().request_repaint();
+        }
+
+        let painter = painter.with_clip_rect(rect);
+        let origin = rect.center() + Vec2::new(self.pan.x, self.pan.y);
+        let zoom = self.zoom;
+        let to_screen = |p: GraphPoint| -> Pos2 { origin + Vec2::new(p.x * zoom, p.y * zoom) };
+
+        for (from, to, kind) in &self.edges {
+            let points = [
+                to_screen(self.nodes[*from].position),
+                to_screen(self.nodes[*to].position),
+            ];
+            match kind {
+                EdgeKind::Ally => {
+                    painter.line_segment(points, Stroke::new(EDGE_WIDTH, ally_color));
+                }
+                EdgeKind::Enemy => {
+                    painter.extend(Shape::dashed_line(
+                        &points,
+                        Stroke::new(EDGE_WIDTH, enemy_color),
+                        DASH_LENGTH,
+                        GAP_LENGTH,
+                    ));
+                }
+            }
+        }
+
+        let visuals = ui.visuals();
+        let hovered = response
+            .hover_pos()
+            .and_then(|pos| node_at(&self.nodes, pos));
+        for (index, node) in self.nodes.iter().enumerate() {
+            let center = to_screen(node.position);
+            let radius = Self::node_radius(node.tier) * zoom;
+            let fill = if hovered == Some(index) {
+                visuals.selection.bg_fill
+            } else {
+                visuals.widgets.inactive.bg_fill
+            };
+            painter.circle(center, radius, fill, visuals.widgets.inactive.fg_stroke);
+            painter.text(
+                center,
+                Align2::CENTER_CENTER,
+                node.tier.to_string(),
+                FontId::proportional(TIER_SIZE * zoom),
+                visuals.text_color(),
+            );
+            painter.text(
+                center + Vec2::new(0.0, radius + 2.0),
+                Align2::CENTER_TOP,
+                &node.name,
+                FontId::proportional(LABEL_SIZE * zoom.max(0.7)),
+                visuals.text_color(),
+            );
+        }
+        if hovered.is_some() {
+            ui.ctx().set_cursor_icon(CursorIcon::PointingHand);
+        }
+
+        clicked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        faction_graph::{EdgeKind, FactionGraph},
+        tier::Tier,
+    };
+
+    #[test]
+    fn allies_settle_closer_than_enemies() {
+        let mut graph = FactionGraph::default();
+        let items = ["Crows", "Lampblacks", "Red Sashes", "Bluecoats"]
+            .map(|n| (n.to_string(), Tier::Tier2))
+            .to_vec();
+        let edges = vec![
+            ("Crows".to_string(), "Bluecoats".to_string(), EdgeKind::Ally),
+            ("Bluecoats".to_string(), "Crows".to_string(), EdgeKind::Ally),
+            (
+                "Lampblacks".to_string(),
+                "Red Sashes".to_string(),
+                EdgeKind::Enemy,
+            ),
+            ("Crows".to_string(), "Nobody".to_string(), EdgeKind::Enemy),
+        ];
+        graph.set_items(items, edges);
+        // both sides of an alliance are one edge, and missing factions are left out
+        assert_eq!(graph.edges.len(), 2);
+
+        let mut steps = 0;
+        while graph.step() {
+            steps += 1;
+        }
+        assert!(steps > 0 && graph.is_settled());
+
+        let distance =
+            |a: usize, b: usize| graph.nodes[a].position.to(graph.nodes[b].position).length();
+        assert!(distance(0, 3) < distance(1, 2));
+        assert!(distance(0, 1) > 1.0 && distance(2, 3) > 1.0);
+
+        // changing nothing leaves the layout settled
+        let items = graph
+            .nodes
+            .iter()
+            .map(|n| (n.name.clone(), n.tier))
+            .collect();
+        graph.set_items(
+            items,
+            vec![
+                ("Crows".to_string(), "Bluecoats".to_string(), EdgeKind::Ally),
+                (
+                    "Lampblacks".to_string(),
+                    "Red Sashes".to_string(),
+                    EdgeKind::Enemy,
+                ),
+            ],
+        );
+        assert!(graph.is_settled());
+    }
+}
@@ -0,0 +1,270 @@
+#[cfg(feature = "gui")]
+use eframe::egui::{Checkbox, Grid, RichText, Ui};
+use log::debug;
+#[cfg(feature = "gui")]
+use log::info;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "gui")]
+use crate::app_display::FIELD_HORIZONTAL_SPACE;
+use crate::{
+    action::{Action, ActionNode},
+    app_data::AppData,
+    dice::{DiceRoll, Outcome, RollKind},
+    localize::fl,
+    managed_list::{FactionRef, Named},
+    tier::Tier,
+};
+
+/// The proposed results of advancing every faction's current project, so they can be
+/// reviewed before they are applied
+#[derive(Clone, Default)]
+pub struct FactionTurn {
+    entries: Vec<TurnEntry>,
+}
+
+#[allow(dead_code)]
+impl FactionTurn {
+    /// Rolls for every faction which has an unfinished clock, working on the first of them
+    pub fn roll(app_data: &AppData, rng: &mut impl Rng) -> Self {
+        let entries = app_data
+            .faction_list()
+            .item_ref_list()
+            .into_iter()
+            .filter_map(|(faction_ref, faction)| {
+                let (clock_index, clock) = faction
+                    .clocks()
+                    .iter()
+                    .enumerate()
+                    .find(|(_, c)| !c.is_finished())?;
+                let roll =
+                    DiceRoll::roll(RollKind::Fortune, usize::from(faction.tier()) as u8, rng);
+                let ticks = fortune_ticks(&roll);
+                let (ticked, parts) = clock.status();
+                let dice = roll.dice().to_vec();
+                debug!("{} rolled {dice:?} for {ticks} ticks", faction.name());
+                Some(TurnEntry {
+                    faction: faction_ref,
+                    faction_name: faction.name().to_string(),
+                    tier: faction.tier(),
+                    dice,
+                    clock_index,
+                    clock_name: clock.name().to_string(),
+                    ticks,
+                    finishes: ticked + ticks >= parts,
+                    include: true,
+                })
+            })
+            .collect();
+        FactionTurn { entries }
+    }
+
+    pub fn entries(&self) -> &[TurnEntry] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Creates the node which ticks every included clock, and records the turn in the log
+    pub fn actions(&self, app_data: &AppData) -> ActionNode {
+        let mut node = ActionNode::new();
+        let mut results = Vec::new();
+
+        for entry in self.entries.iter().filter(|e| e.include) {
+            let Some(mut faction) = app_data.clone_faction(&entry.faction) else {
+                continue;
+            };
+            let Some(clock) = faction.clocks_mut().get_mut(entry.clock_index) else {
+                continue;
+            };
+            for _ in 0..entry.ticks {
+                clock.tick();
+            }
+            results.push(TurnResult {
+                faction: entry.faction_name.clone(),
+                dice: entry.dice.clone(),
+                clock: entry.clock_name.clone(),
+                ticks: entry.ticks,
+                finished: clock.is_finished(),
+            });
+            node.push_back(Action::FactionReplace(entry.faction.clone(), faction));
+        }
+
+        if !results.is_empty() {
+            node.push_back(Action::TurnLogPush(TurnLogEntry {
+                turn: app_data.turn_log().len() + 1,
+                results,
+            }));
+        }
+        node
+    }
+}
+
+#[cfg(feature = "gui")]
+impl FactionTurn {
+    /// Shows the proposed results, where each faction can be left out of the turn
+    pub fn show_edit(&mut self, ui: &mut Ui) {
+        if self.entries.is_empty() {
+            ui.label(fl!("turn_no_clocks"));
+            return;
+        }
+
+        Grid::new("faction turn")
+            .num_columns(6)
+            .striped(true)
+            .spacing([FIELD_HORIZONTAL_SPACE, 4.0])
+            .show(ui, |ui| {
+                ui.label("");
+                ui.label(RichText::new(fl!("main_item_faction")).strong());
+                ui.label(RichText::new(fl!("tier_heading")).strong());
+                ui.label(RichText::new(fl!("turn_dice_heading")).strong());
+                ui.label(RichText::new(fl!("turn_clock_heading")).strong());
+                ui.label(RichText::new(fl!("turn_ticks_heading")).strong());
+                ui.end_row();
+
+                for entry in self.entries.iter_mut() {
+                    if ui.add(Checkbox::without_text(&mut entry.include)).changed() {
+                        info!(
+                            "{} {} faction turn",
+                            entry.faction_name,
+                            if entry.include {
+                                "included in"
+                            } else {
+                                "left out of"
+                            }
+                        );
+                    }
+                    ui.label(entry.faction_name.as_str());
+                    ui.label(entry.tier.to_string());
+                    ui.label(
+                        entry
+                            .dice
+                            .iter()
+                            .map(|d| d.to_string())
+                            .collect::<Vec<String>>()
+                            .join(" "),
+                    );
+                    ui.label(entry.clock_name.as_str());
+                    let ticks = if entry.finishes {
+                        fl!("turn_ticks_finished", ticks = entry.ticks)
+                    } else {
+                        entry.ticks.to_string()
+                    };
+                    ui.label(ticks);
+                    ui.end_row();
+                }
+            });
+    }
+}
+
+/// One faction's roll, and the clock it would advance
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+#[derive(Clone)]
+pub struct TurnEntry {
+    faction: FactionRef,
+    faction_name: String,
+    tier: Tier,
+    dice: Vec<u8>,
+    clock_index: usize,
+    clock_name: String,
+    ticks: u8,
+    finishes: bool,
+    include: bool,
+}
+
+// -----------------------------
+// Rolling
+
+/// The ticks earned by a fortune roll: 1 on 1-3, 2 on 4/5, 3 on a 6, and 5 on a critical
+fn fortune_ticks(roll: &DiceRoll) -> u8 {
+    match roll.outcome() {
+        Outcome::Critical => 5,
+        Outcome::Success => 3,
+        Outcome::Partial => 2,
+        Outcome::Failure => 1,
+    }
+}
+
+// -----------------------------
+// Log
+
+/// The record of one faction turn
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TurnLogEntry {
+    turn: usize,
+    results: Vec<TurnResult>,
+}
+
+#[allow(dead_code)]
+impl TurnLogEntry {
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn results(&self) -> &[TurnResult] {
+        &self.results
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TurnResult {
+    faction: String,
+    dice: Vec<u8>,
+    clock: String,
+    ticks: u8,
+    finished: bool,
+}
+
+impl TurnResult {
+    /// A line of text describing what the faction did
+    pub fn summary(&self) -> String {
+        let dice = self
+            .dice
+            .iter()
+            .map(|d| d.to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        if self.finished {
+            fl!(
+                "turn_log_finished",
+                faction = self.faction.as_str(),
+                dice = dice,
+                ticks = self.ticks,
+                clock = self.clock.as_str()
+            )
+        } else {
+            fl!(
+                "turn_log_ticked",
+                faction = self.faction.as_str(),
+                dice = dice,
+                ticks = self.ticks,
+                clock = self.clock.as_str()
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dice::{DiceRoll, RollKind},
+        faction_turn::fortune_ticks,
+    };
+
+    #[test]
+    fn fortune_ticks_by_result() {
+        let ticks = |pool, dice: &[u8]| {
+            fortune_ticks(&DiceRoll::from_dice(RollKind::Fortune, pool, dice.to_vec()))
+        };
+        assert_eq!(ticks(2, &[1, 3]), 1);
+        assert_eq!(ticks(2, &[2, 5]), 2);
+        assert_eq!(ticks(2, &[6, 1]), 3);
+        assert_eq!(ticks(2, &[6, 6]), 5);
+        // tier 0 takes the lowest, and cannot crit
+        assert_eq!(ticks(0, &[6, 2]), 1);
+        assert_eq!(ticks(0, &[6, 6]), 3);
+    }
+}
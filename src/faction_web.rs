@@ -0,0 +1,451 @@
+use std::{f32::consts::TAU, fmt::Write, path::Path};
+
+use anyhow::Result;
+use log::info;
+
+use crate::{
+    app_data::AppData,
+    faction::Faction,
+    faction_graph::FactionGraph,
+    html::escape,
+    managed_list::{GenericRef, Named},
+    persistence::save_to_text,
+    tier::Tier,
+};
+
+pub const DOT_EXTENSION: &str = "dot";
+pub const SVG_EXTENSION: &str = "svg";
+
+/// How far an HQ or a leader is drawn from the faction it belongs to
+const SATELLITE_DISTANCE: f32 = 70.0;
+/// The turn between the HQs and leaders around one faction
+const SATELLITE_SPREAD: f32 = TAU / 8.0;
+const SVG_MARGIN: f32 = 80.0;
+const DISTRICT_SIZE: f32 = 16.0;
+const PERSON_RADIUS: f32 = 6.0;
+
+const ALLY_COLOR: &str = "#3ca03c";
+const ENEMY_COLOR: &str = "#c83c3c";
+const LINK_COLOR: &str = "#888888";
+const FACTION_FILL: &str = "#dde4f0";
+const DISTRICT_FILL: &str = "#eeeeee";
+const PERSON_FILL: &str = "#f0e0c0";
+
+// -----------------------------
+// Filter
+
+/// Which factions go into the web; their HQs and leaders come with them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WebFilter {
+    /// Only factions of at least this tier
+    pub min_tier: Tier,
+    /// Only factions with turf in the district of this name
+    pub turf_in: Option<String>,
+}
+
+impl WebFilter {
+    pub fn keeps(&self, faction: &Faction) -> bool {
+        faction.tier() >= self.min_tier
+            && self.turf_in.as_ref().is_none_or(|district| {
+                faction
+                    .turf()
+                    .iter()
+                    .any(|t| t.name().as_ref() == Some(district))
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebFormat {
+    Dot,
+    Svg,
+}
+
+impl WebFormat {
+    /// SVG when the file ends in .svg, and DOT otherwise
+    pub fn for_path(path: &Path) -> Self {
+        if path.extension().is_some_and(|e| e == SVG_EXTENSION) {
+            WebFormat::Svg
+        } else {
+            WebFormat::Dot
+        }
+    }
+}
+
+// -----------------------------
+// Web
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WebNodeKind {
+    Faction(Tier),
+    District,
+    Person,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WebEdgeKind {
+    Ally,
+    Enemy,
+    Hq,
+    Leader,
+}
+
+#[derive(Debug, Clone)]
+struct WebNode {
+    name: String,
+    kind: WebNodeKind,
+}
+
+/// The factions which pass a filter, with their allies and enemies among them,
+/// and the districts and people they have as HQs and leaders
+#[derive(Debug, Clone, Default)]
+pub struct FactionWeb {
+    nodes: Vec<WebNode>,
+    edges: Vec<(usize, usize, WebEdgeKind)>,
+}
+
+impl FactionWeb {
+    pub fn new(data: &AppData, filter: &WebFilter) -> Self {
+        let mut factions = data.faction_list().item_ref_list();
+        factions.retain(|(_, f)| filter.keeps(f));
+
+        let mut web = FactionWeb::default();
+        for (_, faction) in &factions {
+            web.add_node(faction.name(), WebNodeKind::Faction(faction.tier()));
+        }
+        for (index, (_, faction)) in factions.iter().enumerate() {
+            let links = [
+                (
+                    faction.hq().and_then(GenericRef::name),
+                    WebNodeKind::District,
+                    WebEdgeKind::Hq,
+                ),
+                (
+                    faction.leader().and_then(GenericRef::name),
+                    WebNodeKind::Person,
+                    WebEdgeKind::Leader,
+                ),
+            ];
+            for (link, kind, edge) in links {
+                if let Some(name) = link {
+                    let other = web.add_node(&name, kind);
+                    web.add_edge(index, other, edge);
+                }
+            }
+            for (list, edge) in [
+                (faction.allies(), WebEdgeKind::Ally),
+                (faction.enemies(), WebEdgeKind::Enemy),
+            ] {
+                for other in list.iter().filter_map(GenericRef::name) {
+                    // a faction which was filtered out takes the edge with them
+                    if let Some(other) = factions.iter().position(|(_, f)| f.name() == other) {
+                        // an alliance held by both sides is one edge
+                        web.add_edge(index.min(other), index.max(other), edge);
+                    }
+                }
+            }
+        }
+        web
+    }
+
+    fn add_node(&mut self, name: &str, kind: WebNodeKind) -> usize {
+        // districts and people are shared by every faction which names them
+        let same = |n: &WebNode| {
+            n.name == name && std::mem::discriminant(&n.kind) == std::mem::discriminant(&kind)
+        };
+        self.nodes.iter().position(same).unwrap_or_else(|| {
+            self.nodes.push(WebNode {
+                name: name.to_string(),
+                kind,
+            });
+            self.nodes.len() - 1
+        })
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize, kind: WebEdgeKind) {
+        if from != to && !self.edges.contains(&(from, to, kind)) {
+            self.edges.push((from, to, kind));
+        }
+    }
+
+    pub fn faction_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|n| matches!(n.kind, WebNodeKind::Faction(_)))
+            .count()
+    }
+
+    // -----------------------------
+    // DOT
+
+    /// The web as a Graphviz graph, for dot, neato and the like to lay out
+    pub fn to_dot(&self, title: &str) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(dot, "graph {} {{", dot_quote(title));
+        let _ = writeln!(dot, "    label={};", dot_quote(title));
+        let _ = writeln!(dot, "    overlap=false;");
+        let _ = writeln!(dot, "    node [fontname=\"sans-serif\"];");
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let attributes = match node.kind {
+                WebNodeKind::Faction(tier) => format!(
+                    "label={}, shape=ellipse, style=filled, fillcolor=\"{FACTION_FILL}\", penwidth={}",
+                    dot_quote(&format!("{}\n{tier}", node.name)),
+                    1 + usize::from(tier)
+                ),
+                WebNodeKind::District => format!(
+                    "label={}, shape=box, style=filled, fillcolor=\"{DISTRICT_FILL}\"",
+                    dot_quote(&node.name)
+                ),
+                WebNodeKind::Person => format!(
+                    "label={}, shape=note, style=filled, fillcolor=\"{PERSON_FILL}\"",
+                    dot_quote(&node.name)
+                ),
+            };
+            let _ = writeln!(dot, "    n{index} [{attributes}];");
+        }
+
+        for (from, to, kind) in &self.edges {
+            let attributes = match kind {
+                WebEdgeKind::Ally => format!("color=\"{ALLY_COLOR}\", penwidth=2"),
+                WebEdgeKind::Enemy => {
+                    format!("color=\"{ENEMY_COLOR}\", penwidth=2, style=dashed")
+                }
+                WebEdgeKind::Hq => format!("color=\"{LINK_COLOR}\", style=dotted, label=\"HQ\""),
+                WebEdgeKind::Leader => {
+                    format!("color=\"{LINK_COLOR}\", style=dotted, label=\"leader\"")
+                }
+            };
+            let _ = writeln!(dot, "    n{from} -- n{to} [{attributes}];");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // -----------------------------
+    // SVG
+
+    /// Where each node goes: the factions are laid out as in the graph view,
+    /// and each HQ and leader sits just outside the factions they belong to
+    fn layout(&self, data: &AppData, filter: &WebFilter) -> Vec<(f32, f32)> {
+        let mut graph = FactionGraph::default();
+        graph.sync_filtered(data, |f| filter.keeps(f));
+        graph.settle();
+
+        let mut positions: Vec<Option<(f32, f32)>> = self
+            .nodes
+            .iter()
+            .map(|node| match node.kind {
+                WebNodeKind::Faction(_) => graph
+                    .positions()
+                    .find(|(name, ..)| *name == node.name)
+                    .map(|(_, x, y)| (x, y)),
+                _ => None,
+            })
+            .collect();
+
+        let mut satellites = vec![0_usize; self.nodes.len()];
+        for (index, node) in self.nodes.iter().enumerate() {
+            if matches!(node.kind, WebNodeKind::Faction(_)) {
+                continue;
+            }
+            let owners: Vec<usize> = self
+                .edges
+                .iter()
+                .filter(|(_, to, _)| *to == index)
+                .map(|(from, ..)| *from)
+                .collect();
+            let Some(first) = owners.first() else {
+                continue;
+            };
+            let count = owners.len() as f32;
+            let (x, y) = owners
+                .iter()
+                .filter_map(|o| positions[*o])
+                .fold((0.0, 0.0), |(x, y), (ox, oy)| {
+                    (x + ox / count, y + oy / count)
+                });
+            // away from the middle of the web, turning a little for each one around the same faction
+            let angle = y.atan2(x) + SATELLITE_SPREAD * satellites[*first] as f32;
+            satellites[*first] += 1;
+            positions[index] = Some((
+                x + SATELLITE_DISTANCE * angle.cos(),
+                y + SATELLITE_DISTANCE * angle.sin(),
+            ));
+        }
+        positions
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect()
+    }
+
+    /// The web drawn as a picture, which needs nothing else to view or print
+    pub fn to_svg(&self, title: &str, data: &AppData, filter: &WebFilter) -> String {
+        let positions = self.layout(data, filter);
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (0.0_f32, 0.0_f32, 0.0_f32, 0.0_f32);
+        for (x, y) in &positions {
+            min_x = min_x.min(*x);
+            min_y = min_y.min(*y);
+            max_x = max_x.max(*x);
+            max_y = max_y.max(*y);
+        }
+        let at = |index: usize| {
+            let (x, y) = positions[index];
+            (x - min_x + SVG_MARGIN, y - min_y + SVG_MARGIN)
+        };
+        let width = max_x - min_x + 2.0 * SVG_MARGIN;
+        let height = max_y - min_y + 2.0 * SVG_MARGIN;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.0}\" height=\"{height:.0}\" \
+             viewBox=\"0 0 {width:.0} {height:.0}\" font-family=\"sans-serif\" font-size=\"12\">"
+        );
+        let _ = writeln!(svg, "  <title>{}</title>", escape(title));
+        let _ = writeln!(
+            svg,
+            "  <rect width=\"100%\" height=\"100%\" fill=\"white\"/>"
+        );
+
+        for (from, to, kind) in &self.edges {
+            let ((x1, y1), (x2, y2)) = (at(*from), at(*to));
+            let style = match kind {
+                WebEdgeKind::Ally => format!("stroke=\"{ALLY_COLOR}\" stroke-width=\"2\""),
+                WebEdgeKind::Enemy => {
+                    format!("stroke=\"{ENEMY_COLOR}\" stroke-width=\"2\" stroke-dasharray=\"8 6\"")
+                }
+                WebEdgeKind::Hq | WebEdgeKind::Leader => {
+                    format!("stroke=\"{LINK_COLOR}\" stroke-width=\"1\" stroke-dasharray=\"2 3\"")
+                }
+            };
+            let _ = writeln!(
+                svg,
+                "  <line x1=\"{x1:.1}\" y1=\"{y1:.1}\" x2=\"{x2:.1}\" y2=\"{y2:.1}\" {style}/>"
+            );
+        }
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            let (x, y) = at(index);
+            let name = escape(&node.name);
+            let _ = match node.kind {
+                WebNodeKind::Faction(tier) => {
+                    let radius = FactionGraph::node_radius(tier);
+                    writeln!(
+                        svg,
+                        "  <circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{radius:.1}\" fill=\"{FACTION_FILL}\" stroke=\"black\"/>\n  \
+                         <text x=\"{x:.1}\" y=\"{y:.1}\" text-anchor=\"middle\" dominant-baseline=\"central\" font-size=\"14\">{tier}</text>\n  \
+                         <text x=\"{x:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-weight=\"bold\">{name}</text>",
+                        y + radius + 14.0
+                    )
+                }
+                WebNodeKind::District => writeln!(
+                    svg,
+                    "  <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{DISTRICT_SIZE}\" height=\"{DISTRICT_SIZE}\" fill=\"{DISTRICT_FILL}\" stroke=\"{LINK_COLOR}\"/>\n  \
+                     <text x=\"{x:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{name}</text>",
+                    x - DISTRICT_SIZE / 2.0,
+                    y - DISTRICT_SIZE / 2.0,
+                    y + DISTRICT_SIZE + 4.0
+                ),
+                WebNodeKind::Person => writeln!(
+                    svg,
+                    "  <circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{PERSON_RADIUS}\" fill=\"{PERSON_FILL}\" stroke=\"{LINK_COLOR}\"/>\n  \
+                     <text x=\"{x:.1}\" y=\"{:.1}\" text-anchor=\"middle\" font-style=\"italic\">{name}</text>",
+                    y + PERSON_RADIUS + 14.0
+                ),
+            };
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Quotes text as a DOT string
+fn dot_quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes the factions which pass the filter, with their HQs and leaders, as DOT or SVG
+pub fn export(
+    data: &AppData,
+    title: &str,
+    filter: &WebFilter,
+    format: WebFormat,
+    path: &Path,
+) -> Result<()> {
+    let web = FactionWeb::new(data, filter);
+    let text = match format {
+        WebFormat::Dot => web.to_dot(title),
+        WebFormat::Svg => web.to_svg(title, data, filter),
+    };
+    save_to_text(path, &text)?;
+    info!(
+        "exported a web of {} factions to {}",
+        web.faction_count(),
+        path.to_string_lossy()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        app_data::AppData,
+        export_format::{ItemKind, add_test_item},
+        faction_web::{FactionWeb, WebFilter},
+        tier::Tier,
+    };
+
+    #[test]
+    fn filters_keep_factions_with_their_hqs() {
+        let mut value = AppData::default().to_json_value().unwrap();
+        add_test_item(&mut value, ItemKind::District, "Crow's Foot", &[]);
+        for (name, tier) in [("The Crows", "II"), ("The \"Lampblacks\"", "I")] {
+            let tier = format!("{:?}", Tier::parse(tier).unwrap());
+            let fields = [
+                ("tier", tier.as_str()),
+                ("hq", "Crow's Foot"),
+                ("turf", "Crow's Foot"),
+            ];
+            add_test_item(&mut value, ItemKind::Faction, name, &fields);
+        }
+        let data = AppData::from_json_value(value).unwrap();
+
+        let everyone = FactionWeb::new(&data, &WebFilter::default());
+        assert_eq!(everyone.faction_count(), 2);
+        let dot = everyone.to_dot("Doskvol");
+        assert!(dot.starts_with("graph \"Doskvol\" {"));
+        assert!(dot.contains("The \\\"Lampblacks\\\"\\nI\""));
+        // both share one HQ
+        assert_eq!(dot.matches("shape=box").count(), 1);
+        assert_eq!(dot.matches("label=\"HQ\"").count(), 2);
+
+        let tier_two = WebFilter {
+            min_tier: Tier::Tier2,
+            ..WebFilter::default()
+        };
+        let web = FactionWeb::new(&data, &tier_two);
+        assert_eq!(web.faction_count(), 1);
+        let svg = web.to_svg("Doskvol", &data, &tier_two);
+        assert!(svg.contains("The Crows"));
+        assert!(!svg.contains("Lampblacks"));
+        assert!(svg.contains("Crow&#39;s Foot"));
+
+        let elsewhere = WebFilter {
+            turf_in: Some("Nightmarket".to_string()),
+            ..WebFilter::default()
+        };
+        assert_eq!(FactionWeb::new(&data, &elsewhere).faction_count(), 0);
+    }
+}
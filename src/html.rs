@@ -0,0 +1,429 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use enum_iterator::all;
+use log::info;
+
+use crate::{
+    app_data::AppData,
+    display::{ItemPage, MainView},
+    district::DistrictStore,
+    faction::FactionStore,
+    managed_list::{ManagedList, Named},
+    markdown::{folder, slug, unique_slugs},
+    persistence::{save_to_bytes, save_to_text},
+    person::PersonStore2,
+};
+
+pub const HTML_EXTENSION: &str = "html";
+
+/// The page which lists the items of each kind, and the page which lists the kinds
+const INDEX_NAME: &str = "index";
+
+const STYLE_NAME: &str = "style.css";
+const FONT_NAME: &str = "Dihjauti-Regular.otf";
+const FONT_LICENSE_NAME: &str = "OFL.txt";
+
+/// The font is bundled, so the site looks the same offline as in the editor
+const FONT: &[u8] = include_bytes!("../Dihjauti-Regular.otf");
+const FONT_LICENSE: &str = include_str!("../SIL Open Font License.txt");
+
+const STYLE: &str = r#"@font-face {
+    font-family: "Dihjauti";
+    src: url("Dihjauti-Regular.otf") format("opentype");
+}
+
+body {
+    font-family: "Dihjauti", sans-serif;
+    max-width: 50em;
+    margin: 0 auto;
+    padding: 1em;
+    line-height: 1.4;
+}
+
+nav a {
+    margin-right: 1em;
+}
+
+.kind {
+    font-style: italic;
+    opacity: 0.7;
+}
+
+.field-heading {
+    font-weight: bold;
+}
+
+table {
+    border-collapse: collapse;
+}
+
+th, td {
+    text-align: left;
+    padding: 0.2em 1em 0.2em 0;
+}
+"#;
+
+/// Writes a folder of pages which can be browsed offline: an index of the kinds of item,
+/// an index for each kind, and a page for each item
+pub fn export(data: &AppData, title: &str, path: &Path) -> Result<()> {
+    let links = HtmlLinks::new(data);
+
+    save_to_text(
+        &page_path(path, None, INDEX_NAME),
+        &home_page(data, title, &links),
+    )?;
+    save_to_text(
+        &page_path(path, Some(MainView::Factions), INDEX_NAME),
+        &index_page(data.faction_list(), MainView::Factions, title, &links),
+    )?;
+    save_to_text(
+        &page_path(path, Some(MainView::Persons), INDEX_NAME),
+        &index_page(data.person_list(), MainView::Persons, title, &links),
+    )?;
+    save_to_text(
+        &page_path(path, Some(MainView::Districts), INDEX_NAME),
+        &index_page(data.district_list(), MainView::Districts, title, &links),
+    )?;
+    save_to_text(
+        &page_path(path, Some(MainView::Clocks), INDEX_NAME),
+        &index_page(data.clock_list(), MainView::Clocks, title, &links),
+    )?;
+
+    for (_, faction) in data.faction_list().item_ref_list() {
+        let mut page = HtmlPage::new(&links, "../");
+        page.title(MainView::Factions, faction.name());
+        FactionStore::from(faction).write_page(&mut page);
+        save_item_page(
+            path,
+            &links,
+            MainView::Factions,
+            faction.name(),
+            page,
+            title,
+        )?;
+    }
+    for (_, person) in data.person_list().item_ref_list() {
+        let mut page = HtmlPage::new(&links, "../");
+        page.title(MainView::Persons, person.name());
+        PersonStore2::from(person).write_page(&mut page);
+        save_item_page(path, &links, MainView::Persons, person.name(), page, title)?;
+    }
+    for (_, district) in data.district_list().item_ref_list() {
+        let mut page = HtmlPage::new(&links, "../");
+        page.title(MainView::Districts, district.name());
+        DistrictStore::from(district).write_page(&mut page);
+        save_item_page(
+            path,
+            &links,
+            MainView::Districts,
+            district.name(),
+            page,
+            title,
+        )?;
+    }
+    for (_, clock) in data.clock_list().item_ref_list() {
+        let mut page = HtmlPage::new(&links, "../");
+        page.title(MainView::Clocks, clock.name());
+        clock.write_page(&mut page);
+        save_item_page(path, &links, MainView::Clocks, clock.name(), page, title)?;
+    }
+
+    save_to_text(&path.join(STYLE_NAME), STYLE)?;
+    save_to_bytes(&path.join(FONT_NAME), FONT)?;
+    save_to_text(&path.join(FONT_LICENSE_NAME), FONT_LICENSE)?;
+    info!("exported html to {}", path.to_string_lossy());
+    Ok(())
+}
+
+fn save_item_page(
+    path: &Path,
+    links: &HtmlLinks,
+    kind: MainView,
+    name: &str,
+    page: HtmlPage,
+    title: &str,
+) -> Result<()> {
+    save_to_text(
+        &page_path(path, Some(kind), &links.slug(kind, name)),
+        &page.finish(name, title),
+    )
+}
+
+/// A page in the folder of its kind, or at the top of the site
+fn page_path(path: &Path, kind: Option<MainView>, name: &str) -> PathBuf {
+    let path = match kind {
+        Some(kind) => path.join(folder(kind)),
+        None => path.to_path_buf(),
+    };
+    path.join(name).with_extension(HTML_EXTENSION)
+}
+
+fn home_page(data: &AppData, title: &str, links: &HtmlLinks) -> String {
+    let mut page = HtmlPage::new(links, "");
+    page.heading(1, title);
+    page.line("<ul>");
+    for kind in all::<MainView>() {
+        page.line(&format!(
+            "<li><a href=\"{}/{INDEX_NAME}.{HTML_EXTENSION}\">{}</a> ({})</li>",
+            folder(kind),
+            escape(&kind.to_string()),
+            data.view_size(kind),
+        ));
+    }
+    page.line("</ul>");
+    page.finish(title, title)
+}
+
+/// Every item of one kind, as a table of the same columns as the main view
+fn index_page<T: Clone + Named>(
+    list: &ManagedList<T>,
+    kind: MainView,
+    title: &str,
+    links: &HtmlLinks,
+) -> String {
+    let mut page = HtmlPage::new(links, "../");
+    page.heading(1, &kind.to_string());
+    page.line("<table>");
+    let headings: Vec<String> = T::display_headings()
+        .iter()
+        .map(|h| format!("<th>{}</th>", escape(h)))
+        .collect();
+    page.line(&format!("<tr>{}</tr>", headings.concat()));
+
+    let mut items: Vec<&T> = list.item_ref_list().into_iter().map(|(_, i)| i).collect();
+    items.sort_by(|a, b| a.name().cmp(b.name()));
+    for item in items {
+        // the first column is the name, which leads to the item's page
+        let fields: Vec<String> = item
+            .display_fields()
+            .iter()
+            .enumerate()
+            .map(|(column, field)| {
+                if column == 0 {
+                    format!("<td>{}</td>", page.link(kind, item.name()))
+                } else {
+                    format!("<td>{}</td>", escape(field))
+                }
+            })
+            .collect();
+        page.line(&format!("<tr>{}</tr>", fields.concat()));
+    }
+    page.line("</table>");
+    page.finish(&kind.to_string(), title)
+}
+
+/// Keeps text from being read as HTML
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// -----------------------------
+// Links
+
+/// The page name of every item, so that references can become links
+pub struct HtmlLinks {
+    slugs: BTreeMap<(MainView, String), String>,
+}
+
+impl HtmlLinks {
+    fn new(data: &AppData) -> Self {
+        let mut slugs = BTreeMap::new();
+        let mut add = |kind: MainView, mut names: Vec<String>| {
+            names.sort();
+            for (name, slug) in unique_slugs(names.iter().map(String::as_str)) {
+                slugs.insert((kind, name.to_string()), slug);
+            }
+        };
+        add(MainView::Factions, data.faction_list().names_sorted());
+        add(MainView::Persons, data.person_list().names_sorted());
+        add(MainView::Districts, data.district_list().names_sorted());
+        add(MainView::Clocks, data.clock_list().names_sorted());
+        HtmlLinks { slugs }
+    }
+
+    fn slug(&self, kind: MainView, name: &str) -> String {
+        self.slugs
+            .get(&(kind, name.to_string()))
+            .cloned()
+            .unwrap_or_else(|| slug(name))
+    }
+
+    /// Where a link to an item points, if the item was exported
+    fn target(&self, kind: MainView, name: &str, prefix: &str) -> Option<String> {
+        let slug = self.slugs.get(&(kind, name.to_string()))?;
+        Some(format!("{prefix}{}/{slug}.{HTML_EXTENSION}", folder(kind)))
+    }
+}
+
+// -----------------------------
+// Pages
+
+/// The body of one page of the site
+pub struct HtmlPage<'a> {
+    links: &'a HtmlLinks,
+    /// What leads from this page back to the top of the site
+    prefix: &'a str,
+    text: String,
+}
+
+impl<'a> HtmlPage<'a> {
+    fn new(links: &'a HtmlLinks, prefix: &'a str) -> Self {
+        HtmlPage {
+            links,
+            prefix,
+            text: String::new(),
+        }
+    }
+
+    /// The whole document, with the style, and a way back to every index
+    fn finish(self, heading: &str, title: &str) -> String {
+        let prefix = self.prefix;
+        let mut nav = format!(
+            "<a href=\"{prefix}{INDEX_NAME}.{HTML_EXTENSION}\">{}</a>",
+            escape(title)
+        );
+        for kind in all::<MainView>() {
+            nav.push_str(&format!(
+                "<a href=\"{prefix}{}/{INDEX_NAME}.{HTML_EXTENSION}\">{}</a>",
+                folder(kind),
+                escape(&kind.to_string())
+            ));
+        }
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+             <link rel=\"stylesheet\" href=\"{prefix}{STYLE_NAME}\">\n</head>\n<body>\n\
+             <nav>{nav}</nav>\n{}</body>\n</html>\n",
+            escape(heading),
+            self.text
+        )
+    }
+
+    fn line(&mut self, line: &str) {
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    fn heading(&mut self, level: usize, text: &str) {
+        self.line(&format!("<h{level}>{}</h{level}>", escape(text.trim())));
+    }
+
+    fn title(&mut self, kind: MainView, name: &str) {
+        self.heading(1, name);
+        self.line(&format!(
+            "<p class=\"kind\">{}</p>",
+            escape(&kind.item_name())
+        ));
+    }
+
+    /// A link to an item, or just its name if it was not exported
+    fn link(&self, kind: MainView, name: &str) -> String {
+        match self.links.target(kind, name, self.prefix) {
+            Some(target) => format!("<a href=\"{}\">{}</a>", escape(&target), escape(name)),
+            None => escape(name),
+        }
+    }
+
+    /// A field whose value is already HTML
+    fn field_html(&mut self, heading: &str, value: &str) {
+        if !value.trim().is_empty() {
+            self.line(&format!(
+                "<p><span class=\"field-heading\">{}:</span> {}</p>",
+                escape(heading),
+                value.trim()
+            ));
+        }
+    }
+}
+
+impl ItemPage for HtmlPage<'_> {
+    fn field(&mut self, heading: &str, value: &str) {
+        self.field_html(heading, &escape(value));
+    }
+
+    fn link_field(&mut self, heading: &str, kind: MainView, name: Option<&str>) {
+        if let Some(name) = name {
+            let link = self.link(kind, name);
+            self.field_html(heading, &link);
+        }
+    }
+
+    fn links_field(&mut self, heading: &str, kind: MainView, names: &[String]) {
+        let links: Vec<String> = names.iter().map(|n| self.link(kind, n)).collect();
+        self.field_html(heading, &links.join(", "));
+    }
+
+    fn section(&mut self, heading: &str, text: &str) {
+        if !text.trim().is_empty() {
+            self.heading(2, heading);
+            // blank lines separate paragraphs, and other line breaks are kept
+            for paragraph in text.trim().split("\n\n") {
+                let lines: Vec<String> = paragraph.trim().lines().map(escape).collect();
+                self.line(&format!("<p>{}</p>", lines.join("<br>")));
+            }
+        }
+    }
+
+    fn list_section(&mut self, heading: &str, items: &[String]) {
+        if !items.is_empty() {
+            self.heading(2, heading);
+            self.line("<ul>");
+            for item in items {
+                self.line(&format!("<li>{}</li>", escape(item)));
+            }
+            self.line("</ul>");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        display::{ItemPage, MainView},
+        faction::FactionStore,
+        html::{HtmlLinks, HtmlPage},
+    };
+
+    #[test]
+    fn references_become_escaped_links() {
+        let links = HtmlLinks {
+            slugs: [(
+                (MainView::Districts, "Crow's Foot".to_string()),
+                "crows-foot".to_string(),
+            )]
+            .into(),
+        };
+        let crows: FactionStore = serde_json::from_str(r#"{"name":"The Crows","description":"A gang\n\n<b>bold</b>","tier":"Tier2","hq":"Crow's Foot","turf":["Crow's Foot","Nowhere"],"leader":null,"notable":[],"assets":"","notes":"","allies":[],"enemies":[],"general":"","clocks":[]}"#).unwrap();
+
+        let mut page = HtmlPage::new(&links, "../");
+        page.title(MainView::Factions, crows.name());
+        crows.write_page(&mut page);
+        page.list_section("Clocks", &[]);
+        let html = page.finish(crows.name(), "Test");
+
+        assert!(html.contains("<link rel=\"stylesheet\" href=\"../style.css\">"));
+        assert!(html.contains("<h1>The Crows</h1>"));
+        // tier is shown as in the editor
+        assert!(html.contains("</span> II</p>"));
+        assert!(
+            html.contains(
+                "<a href=\"../districts/crows-foot.html\">Crow&#39;s Foot</a>, Nowhere</p>"
+            )
+        );
+        assert!(html.contains("<p>A gang</p>\n<p>&lt;b&gt;bold&lt;/b&gt;</p>"));
+    }
+}
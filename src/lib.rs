@@ -0,0 +1,51 @@
+//! The campaign model of Blades Factions, and its save files.
+//!
+//! The egui application is built on top of this behind the `gui` feature, which is on by default.
+
+pub mod action;
+pub mod app_data;
+pub mod backup;
+pub mod campaign_clock;
+pub mod campaign_diff;
+pub mod campaign_merge;
+pub mod cli;
+pub mod clock;
+pub mod csv_file;
+pub mod dataset;
+pub mod dice;
+pub mod display;
+pub mod district;
+pub mod dots;
+pub mod entity_id;
+pub mod export_format;
+pub mod faction;
+pub mod faction_graph;
+pub mod faction_turn;
+pub mod faction_web;
+pub mod html;
+pub mod localize;
+pub mod managed_list;
+pub mod markdown;
+pub mod merge;
+pub mod obsidian;
+pub mod persistence;
+pub mod person;
+pub mod recovery;
+pub mod relationship;
+pub mod setting_pack;
+pub mod sorting;
+pub mod tier;
+pub mod todo;
+
+#[cfg(feature = "gui")]
+pub mod app;
+#[cfg(feature = "gui")]
+pub mod app_display;
+#[cfg(feature = "gui")]
+pub mod app_settings;
+#[cfg(feature = "gui")]
+pub mod child_windows;
+
+pub const APP_NAME: &str = "Blades Factions";
+pub const COMPANY_DOMAIN: &str = "org";
+pub const COMPANY_NAME: &str = "Darcsyde";
@@ -1,36 +1,36 @@
+#[cfg(feature = "gui")]
 use std::{env::current_exe, fs};
 
-use app_settings::AppSettings;
+#[cfg(feature = "gui")]
 use directories_next::ProjectDirs;
+#[cfg(feature = "gui")]
 use eframe::{egui::{Vec2, ViewportBuilder}, run_native, NativeOptions};
+#[cfg(feature = "gui")]
 use log::{error, info, warn, LevelFilter};
 
-use app::App;
-
-mod app;
-mod app_data;
-mod localize;
-mod app_settings;
-mod child_windows;
-mod faction;
-mod person;
-mod district;
-mod clock;
-mod tier;
-mod managed_list;
-mod relationship;
-mod action;
-mod todo;
-mod app_display;
-mod sorting;
-mod dots;
-
-const APP_NAME: &str = "Blades Factions";
-const COMPANY_DOMAIN: &str = "org";
-const COMPANY_NAME: &str = "Darcsyde";
+use blades_factions::cli;
+#[cfg(feature = "gui")]
+use blades_factions::{app::App, app_settings::AppSettings, APP_NAME, COMPANY_DOMAIN, COMPANY_NAME};
 
 
 fn main() {
+    // any arguments make this a command line tool; it does not log, to keep its output clean
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(exit_code) = cli::run(&args) {
+        std::process::exit(exit_code);
+    }
+
+    run_gui();
+}
+
+/// Without the gui, there is only the command line, so no arguments asks for help
+#[cfg(not(feature = "gui"))]
+fn run_gui ( ) {
+    std::process::exit(cli::run(&["help".to_string()]).unwrap_or_default());
+}
+
+#[cfg(feature = "gui")]
+fn run_gui ( ) {
     setup_logger().expect("log did not start");
     info!("Starting");
 
@@ -82,6 +82,7 @@ fn main() {
 
 // ========================
 
+#[cfg(feature = "gui")]
 fn setup_logger ( ) -> Result<(), fern::InitError> {
     const LOG_FILE: &str = "factions_output.log";
     let _ = fs::remove_file(LOG_FILE);  // !! ignoring possible real errors
@@ -1,14 +1,15 @@
 use std::{collections::BTreeMap, marker::PhantomData, sync::Arc};
 
-use eframe::egui::{RichText, mutex::RwLock};
 use log::{debug, info, warn};
+use parking_lot::RwLock;
 
 use crate::{
     app_data::DataIndex,
-    app_display::NewStringStatus,
-    district::{District, DistrictStore},
-    faction::{Faction, FactionStore},
-    person::{Person, PersonStore1, PersonStore2},
+    campaign_clock::{CampaignClock, CampaignClockStore},
+    district::{District, DistrictStore, DistrictStore3},
+    entity_id::EntityId,
+    faction::{Faction, FactionStore, FactionStore3},
+    person::{Person, PersonStore1, PersonStore2, PersonStore3},
     sorting::Sorting,
 };
 
@@ -35,10 +36,26 @@ impl<T: Clone + Named> GenericRef<T> {
     //     self.0.read().index.into()
     // }
 
+    /// True if this currently refers to the item at the given data index
+    pub fn is_data_index(&self, data_index: DataIndex) -> bool {
+        let index = self.0.read().index;
+        !matches!(index, DataIndex::Nothing) && index == data_index
+    }
+
     pub fn name(&self) -> Option<String> {
         self.0.read().name().map(|n| n.to_string())
     }
 
+    /// The persistent id of the referenced item, if it is present
+    pub fn id(&self) -> Option<EntityId> {
+        let ind = self.0.read();
+        if !matches!(ind.index, DataIndex::Nothing) {
+            Some(ind.id)
+        } else {
+            None
+        }
+    }
+
     pub fn display_name(&self) -> Option<String> {
         self.0.read().display_name().map(|n| n.to_string())
     }
@@ -61,6 +78,9 @@ pub type PersonRef = GenericRef<Person>;
 #[allow(dead_code)]
 pub type DistrictRef = GenericRef<District>;
 
+#[allow(dead_code)]
+pub type CampaignClockRef = GenericRef<CampaignClock>;
+
 #[allow(dead_code)]
 #[derive(Default, Clone)]
 pub struct GenericRefList<T: Clone + Named> {
@@ -101,6 +121,17 @@ impl<T: Clone + Named> GenericRefList<T> {
         self.list.swap_remove(index);
     }
 
+    pub fn contains_data_index(&self, data_index: DataIndex) -> bool {
+        self.list.iter().any(|r| r.is_data_index(data_index))
+    }
+
+    /// Returns whether anything was removed
+    pub fn remove_data_index(&mut self, data_index: DataIndex) -> bool {
+        let before = self.list.len();
+        self.list.retain(|r| !r.is_data_index(data_index));
+        self.list.len() != before
+    }
+
     pub fn new_name(&self) -> Option<&str> {
         self.new.as_deref()
     }
@@ -138,7 +169,9 @@ pub type DistrictRefList = GenericRefList<District>;
 pub struct NamedIndex<T: Clone + Named> {
     name: String,
     display_name: String,
+    id: EntityId,
     index: DataIndex,
+    removed_from: Option<usize>, // the slot it had, so a restore keeps the same index
     typ: PhantomData<T>,
 }
 
@@ -170,6 +203,7 @@ impl<T: Clone + Named> NamedIndex<T> {
 pub struct ManagedList<T: Clone + Named> {
     list: Vec<Option<T>>,
     list_index: BTreeMap<String, GenericRef<T>>,
+    id_index: BTreeMap<EntityId, GenericRef<T>>,
     sorting: Sorting,
 }
 
@@ -189,19 +223,28 @@ impl<T: Clone + Named> ManagedList<T> {
     }
 
     /// Returns the reference to the new item
+    /// Note: the item is given a new id if it has none, or if its id is already in use
     pub fn add(&mut self, item: &T) -> Option<GenericRef<T>> {
         let name = item.name().to_string();
         let display_name = item.display_name();
         if !self.list_index.contains_key(&name) {
+            let mut item = item.clone();
+            if item.id().is_nil() || self.id_index.contains_key(&item.id()) {
+                item.set_id(EntityId::new());
+            }
+            let id = item.id();
             let index = T::make_data_index(self.list.len());
-            self.list.push(Some(item.clone()));
+            self.list.push(Some(item));
             let named_index = GenericRef(Arc::new(RwLock::new(NamedIndex {
                 name: name.clone(),
                 display_name,
+                id,
                 index,
+                removed_from: None,
                 typ: PhantomData,
             })));
             self.list_index.insert(name, named_index.clone());
+            self.id_index.insert(id, named_index.clone());
             Some(named_index)
         } else {
             warn!("key {name} already present in list, during add");
@@ -217,7 +260,7 @@ impl<T: Clone + Named> ManagedList<T> {
                 panic!("asked to remove incorrect index from managed list");
             };
 
-            info!("(not) removing {:?}", named_index.data_index());
+            info!("removing {:?}", named_index.data_index());
 
             let ret = if let Some(Some(val)) = self.list.get(index) {
                 Some(val.clone())
@@ -228,6 +271,9 @@ impl<T: Clone + Named> ManagedList<T> {
 
             // update the reference
             let mut ind = named_index.0.write();
+            self.list_index.remove(&ind.name); // so the name can be used again
+            self.id_index.remove(&ind.id);
+            ind.removed_from = Some(index);
             ind.index = DataIndex::Nothing;
             ind.name = "<Removed>".to_owned();
 
@@ -238,9 +284,51 @@ impl<T: Clone + Named> ManagedList<T> {
         }
     }
 
+    /// Puts a removed item back, reviving the existing reference (and every copy of it)
+    /// The item keeps the data index it had before it was removed
+    /// Returns false if the reference is still in use, or the name has been taken since
+    pub fn restore(&mut self, named_index: &GenericRef<T>, mut item: T) -> bool {
+        if named_index.has_index() {
+            warn!("asked to restore a reference which is still in use");
+            return false;
+        }
+
+        let name = item.name().to_string();
+        if self.list_index.contains_key(&name) {
+            warn!("key {name} already present in list, during restore");
+            return false;
+        }
+
+        if item.id().is_nil() || self.id_index.contains_key(&item.id()) {
+            item.set_id(EntityId::new());
+        }
+        let id = item.id();
+
+        let mut ind = named_index.0.write();
+        // the old slot is never reused by add, so it should still be empty
+        let slot = match ind.removed_from.take() {
+            Some(slot) if matches!(self.list.get(slot), Some(None)) => slot,
+            _ => {
+                self.list.push(None);
+                self.list.len() - 1
+            }
+        };
+        ind.index = T::make_data_index(slot);
+        ind.name = name.clone();
+        ind.display_name = item.display_name();
+        ind.id = id;
+        drop(ind);
+
+        self.list[slot] = Some(item);
+        self.list_index.insert(name, named_index.clone());
+        self.id_index.insert(id, named_index.clone());
+        true
+    }
+
     /// Returns the old item, if something was replaced
     /// Note: the reference name is updated as well
-    pub fn replace(&mut self, index: &GenericRef<T>, new_item: T) -> Option<T> {
+    /// Note: the replacement always keeps the id of the item it replaces
+    pub fn replace(&mut self, index: &GenericRef<T>, mut new_item: T) -> Option<T> {
         if index.has_index() {
             let Some(ind) = index.index() else {
                 unreachable!("no index found despite having an index (in replace)");
@@ -250,6 +338,7 @@ impl<T: Clone + Named> ManagedList<T> {
             else {
                 unreachable!("unable to find managed_list item with functioning index");
             };
+            new_item.set_id(old_item.id());
             let new_name = new_item.name();
             let old_name = old_item.name();
             let same_name = new_name == old_name;
@@ -302,6 +391,11 @@ impl<T: Clone + Named> ManagedList<T> {
         self.list_index.get(name).cloned()
     }
 
+    /// Returns the reference to the item with this id, if it exists
+    pub fn find_id(&self, id: EntityId) -> Option<GenericRef<T>> {
+        self.id_index.get(&id).cloned()
+    }
+
     /// Returns a reference to the existing item, if it is present
     pub fn fetch(&self, index: &GenericRef<T>) -> Option<&T> {
         let index = index.index()?;
@@ -310,8 +404,8 @@ impl<T: Clone + Named> ManagedList<T> {
 
     pub fn item_ref_list(&self) -> Vec<(GenericRef<T>, &T)> {
         self.list_index
-            .iter()
-            .filter_map(|(_st, re)| {
+            .values()
+            .filter_map(|re| {
                 if re.has_index() {
                     self.fetch(re).map(|item| (re.clone(), item))
                 } else {
@@ -342,6 +436,53 @@ impl<T: Clone + Named> ManagedList<T> {
         };
         ret.as_ref()
     }
+
+    /// Returns the reference for a (transient) data index, if that item is present
+    pub fn find_data_index(&self, data_index: DataIndex) -> Option<GenericRef<T>> {
+        let index = T::fetch_data_index(data_index)?;
+        let item = self.list.get(index)?.as_ref()?;
+        self.find(item.name())
+    }
+}
+
+impl From<&ManagedList<Person>> for Vec<PersonStore3> {
+    fn from(value: &ManagedList<Person>) -> Self {
+        value
+            .list
+            .iter()
+            .filter_map(|maybe_p| maybe_p.as_ref().map(PersonStore3::from))
+            .collect()
+    }
+}
+
+impl From<&ManagedList<District>> for Vec<DistrictStore3> {
+    fn from(value: &ManagedList<District>) -> Self {
+        value
+            .list
+            .iter()
+            .filter_map(|maybe_p| maybe_p.as_ref().map(DistrictStore3::from))
+            .collect()
+    }
+}
+
+impl From<&ManagedList<Faction>> for Vec<FactionStore3> {
+    fn from(value: &ManagedList<Faction>) -> Self {
+        value
+            .list
+            .iter()
+            .filter_map(|maybe_p| maybe_p.as_ref().map(FactionStore3::from))
+            .collect()
+    }
+}
+
+impl From<&ManagedList<CampaignClock>> for Vec<CampaignClockStore> {
+    fn from(value: &ManagedList<CampaignClock>) -> Self {
+        value
+            .list
+            .iter()
+            .filter_map(|maybe_c| maybe_c.as_ref().map(CampaignClockStore::from))
+            .collect()
+    }
 }
 
 impl From<&ManagedList<Person>> for Vec<PersonStore2> {
@@ -390,11 +531,23 @@ impl From<&ManagedList<Faction>> for Vec<FactionStore> {
 #[allow(dead_code)]
 pub trait Named {
     fn name(&self) -> &str;
+    fn id(&self) -> EntityId;
+    fn set_id(&mut self, id: EntityId);
     fn display_name(&self) -> String;
     fn make_data_index(index: usize) -> DataIndex;
     fn fetch_data_index(index: DataIndex) -> Option<usize>;
     fn display_fields(&self) -> Vec<String>;
-    fn display_headings() -> Vec<RichText>;
+    fn display_headings() -> Vec<String>;
+}
+
+// ----------------------
+// NewItemStatus
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum NewStringStatus {
+    #[default]
+    NoItem,
+    Requested, // could include a timer here of some sort
+    Showing(String),
 }
 
 // --------------------------------
@@ -512,6 +665,9 @@ mod tests {
         assert_eq!(old1.name(), "Test1");
         assert!(m_list.fetch(&item1_ref).is_some());
         assert_eq!(m_list.fetch(&item1_ref).unwrap().name(), "New1");
+        assert!(!old1.id().is_nil());
+        assert_eq!(m_list.fetch(&item1_ref).unwrap().id(), old1.id());
+        assert!(m_list.find_id(old1.id()).is_some());
     }
 
     #[test]
@@ -558,6 +714,41 @@ mod tests {
         assert_eq!(found3.unwrap().name(), "Test3");
     }
 
+    #[test]
+    fn restore_managed_list() {
+        let mut m_list = ManagedList::<District>::default();
+
+        let item1 = District::new("Test1");
+        let Some(mut item1_ref) = m_list.add(&item1) else {
+            panic!("error on add item1");
+        };
+        let item1_copy = item1_ref.clone();
+
+        let Some(removed) = m_list.remove(&mut item1_ref) else {
+            panic!("unable to remove item1");
+        };
+        assert_eq!(m_list.item_count(), 0);
+        assert!(m_list.find("Test1").is_none());
+        assert!(item1_copy.name().is_none());
+
+        let old_index = item1_ref.data_index();
+        assert!(m_list.restore(&item1_ref, removed));
+        assert_eq!(m_list.item_count(), 1);
+        assert_ne!(item1_copy.data_index(), old_index); // was Nothing
+        assert_eq!(m_list.next_index(), 1); // kept its old slot
+        assert_eq!(item1_copy.name().as_deref(), Some("Test1"));
+        assert_eq!(m_list.fetch(&item1_copy).unwrap().name(), "Test1");
+        assert!(!m_list.restore(&item1_ref, District::new("Test1")));
+
+        // a removed name can be used again
+        let Some(mut again_ref) = m_list.find("Test1") else {
+            panic!("unable to find restored item");
+        };
+        m_list.remove(&mut again_ref);
+        assert!(m_list.add(&District::new("Test1")).is_some());
+        assert!(!m_list.restore(&again_ref, item1));
+    }
+
     #[allow(dead_code)]
     fn setup_logger() -> Result<(), fern::InitError> {
         const LOG_FILE: &str = "factions_test_output.log";
@@ -0,0 +1,410 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use log::info;
+
+use crate::{
+    display::{ItemPage, MainView},
+    district::DistrictStore,
+    faction::FactionStore,
+    localize::fl,
+    persistence::save_to_text,
+    person::PersonStore2,
+};
+
+pub const MARKDOWN_EXTENSION: &str = "md";
+
+/// The page which lists every item, when exporting pages
+const INDEX_NAME: &str = "index";
+
+/// How a Markdown export is laid out
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MarkdownLayout {
+    /// A page for each item, in a folder for each kind of item, with an index page
+    #[default]
+    Pages,
+    /// Every item in a single document
+    Document,
+}
+
+/// The items of a campaign, as they are exported, with references held by name
+pub struct MarkdownCampaign<'a> {
+    pub title: &'a str,
+    pub persons: &'a [PersonStore2],
+    pub districts: &'a [DistrictStore],
+    pub factions: &'a [FactionStore],
+}
+
+/// The heading of the index, when the campaign has not been saved
+pub fn default_title() -> String {
+    fl!("markdown_campaign")
+}
+
+/// Writes the campaign to a folder of pages, or to a single document
+pub fn export(campaign: &MarkdownCampaign, path: &Path, layout: MarkdownLayout) -> Result<()> {
+    let links = MarkdownLinks::new(campaign, layout);
+    match layout {
+        MarkdownLayout::Pages => {
+            for (kind, name, page) in item_pages(campaign, &links) {
+                save_to_text(&path.join(links.page_path(kind, name)), &page)?;
+            }
+            save_to_text(
+                &path.join(INDEX_NAME).with_extension(MARKDOWN_EXTENSION),
+                &index_page(campaign, &links),
+            )?;
+        }
+        MarkdownLayout::Document => {
+            save_to_text(
+                &path.with_extension(MARKDOWN_EXTENSION),
+                &document(campaign, &links),
+            )?;
+        }
+    }
+    info!("exported markdown to {}", path.to_string_lossy());
+    Ok(())
+}
+
+/// Every item on its own page, in name order
+fn item_pages<'a>(
+    campaign: &'a MarkdownCampaign,
+    links: &MarkdownLinks,
+) -> Vec<(MainView, &'a str, String)> {
+    let mut pages = Vec::new();
+    for faction in sorted(campaign.factions, FactionStore::name) {
+        let mut page = MarkdownPage::new(links, 1, "../");
+        page.title(MainView::Factions, faction.name());
+        faction.write_page(&mut page);
+        pages.push((MainView::Factions, faction.name(), page.finish()));
+    }
+    for person in sorted(campaign.persons, PersonStore2::name) {
+        let mut page = MarkdownPage::new(links, 1, "../");
+        page.title(MainView::Persons, person.name());
+        person.write_page(&mut page);
+        pages.push((MainView::Persons, person.name(), page.finish()));
+    }
+    for district in sorted(campaign.districts, DistrictStore::name) {
+        let mut page = MarkdownPage::new(links, 1, "../");
+        page.title(MainView::Districts, district.name());
+        district.write_page(&mut page);
+        pages.push((MainView::Districts, district.name(), page.finish()));
+    }
+    pages
+}
+
+fn index_page(campaign: &MarkdownCampaign, links: &MarkdownLinks) -> String {
+    let mut page = MarkdownPage::new(links, 1, "");
+    page.heading(1, campaign.title);
+    for (kind, names) in kind_names(campaign) {
+        page.heading(2, &kind.to_string());
+        for name in names {
+            page.line(&format!("- {}", page.link(kind, name)));
+        }
+    }
+    page.finish()
+}
+
+fn document(campaign: &MarkdownCampaign, links: &MarkdownLinks) -> String {
+    let mut page = MarkdownPage::new(links, 3, "");
+    page.heading(1, campaign.title);
+
+    page.heading(2, &MainView::Factions.to_string());
+    for faction in sorted(campaign.factions, FactionStore::name) {
+        page.title(MainView::Factions, faction.name());
+        faction.write_page(&mut page);
+    }
+    page.heading(2, &MainView::Persons.to_string());
+    for person in sorted(campaign.persons, PersonStore2::name) {
+        page.title(MainView::Persons, person.name());
+        person.write_page(&mut page);
+    }
+    page.heading(2, &MainView::Districts.to_string());
+    for district in sorted(campaign.districts, DistrictStore::name) {
+        page.title(MainView::Districts, district.name());
+        district.write_page(&mut page);
+    }
+    page.finish()
+}
+
+/// The names of every item, by kind, in name order
+fn kind_names<'a>(campaign: &'a MarkdownCampaign) -> [(MainView, Vec<&'a str>); 3] {
+    let sorted_names = |mut names: Vec<&'a str>| {
+        names.sort();
+        names
+    };
+    [
+        (
+            MainView::Factions,
+            sorted_names(campaign.factions.iter().map(FactionStore::name).collect()),
+        ),
+        (
+            MainView::Persons,
+            sorted_names(campaign.persons.iter().map(PersonStore2::name).collect()),
+        ),
+        (
+            MainView::Districts,
+            sorted_names(campaign.districts.iter().map(DistrictStore::name).collect()),
+        ),
+    ]
+}
+
+fn sorted<T>(items: &[T], name: fn(&T) -> &str) -> Vec<&T> {
+    let mut items: Vec<&T> = items.iter().collect();
+    items.sort_by(|a, b| name(a).cmp(name(b)));
+    items
+}
+
+// -----------------------------
+// Links
+
+/// The page name of every item, so that references can become links
+pub struct MarkdownLinks {
+    layout: MarkdownLayout,
+    slugs: BTreeMap<(MainView, String), String>,
+}
+
+impl MarkdownLinks {
+    fn new(campaign: &MarkdownCampaign, layout: MarkdownLayout) -> Self {
+        let mut slugs = BTreeMap::new();
+        for (kind, names) in kind_names(campaign) {
+            for (name, slug) in unique_slugs(names) {
+                slugs.insert((kind, name.to_string()), slug);
+            }
+        }
+        MarkdownLinks { layout, slugs }
+    }
+
+    /// The page of an item, relative to the export folder
+    fn page_path(&self, kind: MainView, name: &str) -> PathBuf {
+        let slug = self.slug(kind, name);
+        Path::new(folder(kind))
+            .join(slug)
+            .with_extension(MARKDOWN_EXTENSION)
+    }
+
+    fn slug(&self, kind: MainView, name: &str) -> String {
+        self.slugs
+            .get(&(kind, name.to_string()))
+            .cloned()
+            .unwrap_or_else(|| slug(name))
+    }
+
+    /// Where a link to an item points, if the item was exported
+    fn target(&self, kind: MainView, name: &str, prefix: &str) -> Option<String> {
+        let slug = self.slugs.get(&(kind, name.to_string()))?;
+        Some(match self.layout {
+            MarkdownLayout::Pages => {
+                format!("{prefix}{}/{slug}.{MARKDOWN_EXTENSION}", folder(kind))
+            }
+            MarkdownLayout::Document => format!("#{}", anchor(kind, slug)),
+        })
+    }
+}
+
+/// The slug of every name, numbered where two names would otherwise share one
+pub fn unique_slugs<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<(&'a str, String)> {
+    // names are unique, but their slugs need not be
+    let mut used = BTreeSet::new();
+    let mut slugs = Vec::new();
+    for name in names {
+        let base = slug(name);
+        let mut unique = base.clone();
+        let mut count = 1;
+        while !used.insert(unique.clone()) {
+            count += 1;
+            unique = format!("{base}-{count}");
+        }
+        slugs.push((name, unique));
+    }
+    slugs
+}
+
+/// The folder of each kind of item, named as in the export format
+pub fn folder(kind: MainView) -> &'static str {
+    match kind {
+        MainView::Factions => "factions",
+        MainView::Persons => "persons",
+        MainView::Districts => "districts",
+        MainView::Clocks => "clocks",
+    }
+}
+
+fn anchor(kind: MainView, slug: &str) -> String {
+    format!("{}-{slug}", folder(kind))
+}
+
+/// A name made safe for a file name or an anchor: lower case letters and digits, joined by dashes
+pub fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            slug.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-' || c == '_') && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() {
+        "item".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Keeps a name from being read as Markdown inside link text
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// -----------------------------
+// Pages
+
+/// One Markdown document, or one item's part of it
+pub struct MarkdownPage<'a> {
+    links: &'a MarkdownLinks,
+    /// The heading level of an item's name; its sections are one level below
+    level: usize,
+    /// What leads from this page back to the export folder
+    prefix: &'a str,
+    text: String,
+}
+
+impl<'a> MarkdownPage<'a> {
+    fn new(links: &'a MarkdownLinks, level: usize, prefix: &'a str) -> Self {
+        MarkdownPage {
+            links,
+            level,
+            prefix,
+            text: String::new(),
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut text = self.text.trim_end().to_string();
+        text.push('\n');
+        text
+    }
+
+    fn line(&mut self, line: &str) {
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    fn heading(&mut self, level: usize, text: &str) {
+        if !self.text.is_empty() && !self.text.ends_with("\n\n") {
+            self.text.push('\n');
+        }
+        self.line(&format!("{} {}", "#".repeat(level), text.trim()));
+        self.text.push('\n');
+    }
+
+    /// The item's name, with an anchor to link to when everything is in one document
+    fn title(&mut self, kind: MainView, name: &str) {
+        if self.links.layout == MarkdownLayout::Document {
+            if !self.text.ends_with("\n\n") {
+                self.text.push('\n');
+            }
+            let anchor = anchor(kind, &self.links.slug(kind, name));
+            self.line(&format!("<a id=\"{anchor}\"></a>"));
+        }
+        self.heading(self.level, name);
+        self.line(&format!("*{}*", kind.item_name()));
+        self.text.push('\n');
+    }
+
+    /// A link to an item, or just its name if it was not exported
+    fn link(&self, kind: MainView, name: &str) -> String {
+        match self.links.target(kind, name, self.prefix) {
+            Some(target) => format!("[{}]({target})", escape(name)),
+            None => escape(name),
+        }
+    }
+}
+
+impl ItemPage for MarkdownPage<'_> {
+    fn field(&mut self, heading: &str, value: &str) {
+        if !value.trim().is_empty() {
+            self.line(&format!("- **{heading}:** {}", value.trim()));
+        }
+    }
+
+    fn link_field(&mut self, heading: &str, kind: MainView, name: Option<&str>) {
+        if let Some(name) = name {
+            let link = self.link(kind, name);
+            self.field(heading, &link);
+        }
+    }
+
+    fn links_field(&mut self, heading: &str, kind: MainView, names: &[String]) {
+        let links: Vec<String> = names.iter().map(|n| self.link(kind, n)).collect();
+        self.field(heading, &links.join(", "));
+    }
+
+    fn section(&mut self, heading: &str, text: &str) {
+        if !text.trim().is_empty() {
+            self.heading(self.level + 1, heading);
+            self.line(text.trim());
+        }
+    }
+
+    fn list_section(&mut self, heading: &str, items: &[String]) {
+        if !items.is_empty() {
+            self.heading(self.level + 1, heading);
+            for item in items {
+                self.line(&format!("- {item}"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        district::DistrictStore,
+        faction::FactionStore,
+        markdown::{MarkdownCampaign, MarkdownLayout, MarkdownLinks, slug},
+        person::PersonStore2,
+    };
+
+    #[test]
+    fn references_become_relative_links() {
+        assert_eq!(slug("The Crows' Nest"), "the-crows-nest");
+        assert_eq!(slug("  --  "), "item");
+
+        let districts: Vec<DistrictStore> = vec![
+            serde_json::from_str(r#"{"name":"Crow's Foot","description":"","wealth":"Two","safety":"One","crime":"Four","occult":"Zero","notable":[],"notes":""}"#).unwrap(),
+        ];
+        let factions: Vec<FactionStore> = vec![
+            serde_json::from_str(r#"{"name":"The Crows","description":"A gang","tier":"Tier2","hq":"Crow's Foot","turf":["Crow's Foot","Nowhere"],"leader":null,"notable":[],"assets":"","notes":"","allies":[],"enemies":[],"general":"","clocks":[]}"#).unwrap(),
+        ];
+        let persons: Vec<PersonStore2> = Vec::new();
+        let campaign = MarkdownCampaign {
+            title: "Test",
+            persons: &persons,
+            districts: &districts,
+            factions: &factions,
+        };
+
+        let links = MarkdownLinks::new(&campaign, MarkdownLayout::Pages);
+        let pages = super::item_pages(&campaign, &links);
+        let (_, _, crows) = &pages[0];
+        assert!(crows.starts_with("# The Crows\n"));
+        assert!(crows.contains("- **HQ:** [Crow's Foot](../districts/crows-foot.md)"));
+        // a reference to something which was not exported stays as text
+        assert!(crows.contains(", Nowhere\n"));
+
+        let links = MarkdownLinks::new(&campaign, MarkdownLayout::Document);
+        let document = super::document(&campaign, &links);
+        assert!(document.contains("<a id=\"districts-crows-foot\"></a>"));
+        assert!(document.contains("[Crow's Foot](#districts-crows-foot)"));
+    }
+}
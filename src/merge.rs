@@ -0,0 +1,586 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
+#[cfg(feature = "gui")]
+use eframe::egui::{CollapsingHeader, ComboBox, Grid, RichText, ScrollArea, TextEdit, Ui};
+use serde_json::Value;
+
+#[cfg(feature = "gui")]
+use crate::{app::UI_PADDING, localize::fl};
+use crate::{
+    entity_id::EntityId,
+    export_format::{ItemKind, field_text, find_item, items, reference_kind},
+};
+
+/// The kinds of item which are matched by name, and may conflict
+pub(crate) const MERGE_KINDS: [ItemKind; 3] =
+    [ItemKind::District, ItemKind::Person, ItemKind::Faction];
+
+/// The lists of the export format which are not matched item by item
+pub(crate) const CLOCKS: &str = "clocks";
+const RELATIONSHIPS: &str = "relationships";
+const TURN_LOG: &str = "turn_log";
+
+/// The list a clock field refers to, by id
+pub(crate) fn clock_reference(field: &str) -> Option<&'static str> {
+    match field {
+        "factions" | "persons" | "districts" => ItemKind::parse(field).map(|k| k.key()),
+        "next" | "rival" => Some(CLOCKS),
+        _ => None,
+    }
+}
+
+// -----------------------------
+// Items
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStatus {
+    /// No item of the kind has this name
+    New,
+    /// An item has this name, and every field is the same
+    Identical,
+    /// An item has this name, but some fields differ
+    Conflict,
+}
+
+/// What to do with an incoming item whose name is taken by a different item
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictChoice {
+    #[default]
+    KeepExisting,
+    TakeIncoming,
+    /// Each field which differs is taken from one side or the other
+    MergeFields,
+    /// The incoming item is added alongside, under a new name
+    Rename,
+}
+
+/// A field which differs between the existing and incoming item, as text
+#[derive(Debug, Clone)]
+pub struct FieldDifference {
+    pub field: String,
+    pub existing: String,
+    pub incoming: String,
+    pub take_incoming: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeItem {
+    pub kind: ItemKind,
+    pub name: String,
+    incoming_index: usize,
+    existing_index: Option<usize>,
+    pub differences: Vec<FieldDifference>,
+    pub choice: ConflictChoice,
+    pub new_name: String,
+}
+
+impl MergeItem {
+    pub fn status(&self) -> MergeStatus {
+        match (self.existing_index, self.differences.is_empty()) {
+            (None, _) => MergeStatus::New,
+            (Some(_), true) => MergeStatus::Identical,
+            (Some(_), false) => MergeStatus::Conflict,
+        }
+    }
+
+    /// Whether the incoming item is added as a new item
+    fn is_added(&self) -> bool {
+        self.status() == MergeStatus::New
+            || (self.status() == MergeStatus::Conflict && self.choice == ConflictChoice::Rename)
+    }
+
+    /// The fields which are copied from the incoming item
+    fn taken_fields(&self, incoming: &Value) -> Vec<String> {
+        match (self.status(), self.choice) {
+            (MergeStatus::Identical, _) => Vec::new(),
+            (MergeStatus::Conflict, ConflictChoice::KeepExisting) => Vec::new(),
+            (MergeStatus::Conflict, ConflictChoice::MergeFields) => self
+                .differences
+                .iter()
+                .filter(|d| d.take_incoming)
+                .map(|d| d.field.clone())
+                .collect(),
+            _ => fields(&items(incoming, self.kind)[self.incoming_index]),
+        }
+    }
+}
+
+/// Every field of an item but its id and name
+fn fields(item: &Value) -> Vec<String> {
+    item.as_object()
+        .map(|o| {
+            o.keys()
+                .filter(|k| *k != "id" && *k != "name")
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// -----------------------------
+// Preview
+
+/// What an import would do to the current data, item by item, and how each conflict is resolved.
+/// Both sides are held in the export format, so references are compared by name
+#[derive(Debug, Clone)]
+pub struct MergePreview {
+    current: Value,
+    incoming: Value,
+    items: Vec<MergeItem>,
+}
+
+impl MergePreview {
+    pub fn new(current: Value, incoming: Value) -> Self {
+        let mut merge_items = Vec::new();
+        for kind in MERGE_KINDS {
+            for (incoming_index, item) in items(&incoming, kind).iter().enumerate() {
+                let name = field_text(&incoming, kind, incoming_index, "name");
+                let existing_index = find_item(&current, kind, &name)
+                    .ok()
+                    .filter(|i| items(&current, kind)[*i]["name"] == item["name"]);
+                let differences = existing_index.map_or_else(Vec::new, |existing_index| {
+                    fields(item)
+                        .into_iter()
+                        .filter_map(|field| {
+                            let existing = field_text(&current, kind, existing_index, &field);
+                            let incoming = field_text(&incoming, kind, incoming_index, &field);
+                            (existing != incoming).then_some(FieldDifference {
+                                field,
+                                existing,
+                                incoming,
+                                take_incoming: false,
+                            })
+                        })
+                        .collect()
+                });
+                merge_items.push(MergeItem {
+                    kind,
+                    new_name: format!("{name} (2)"),
+                    name,
+                    incoming_index,
+                    existing_index,
+                    differences,
+                    choice: ConflictChoice::default(),
+                });
+            }
+        }
+        MergePreview {
+            current,
+            incoming,
+            items: merge_items,
+        }
+    }
+
+    pub fn items(&self) -> &[MergeItem] {
+        &self.items
+    }
+
+    pub fn items_mut(&mut self) -> &mut [MergeItem] {
+        &mut self.items
+    }
+
+    pub fn count(&self, status: MergeStatus) -> usize {
+        self.items.iter().filter(|i| i.status() == status).count()
+    }
+
+    /// Why the merge cannot be done as it stands, such as a new name which is already taken
+    pub fn problem(&self) -> Option<String> {
+        let mut taken: Vec<(&str, String)> = Vec::new();
+        for item in self.items.iter().filter(|i| i.is_added()) {
+            let name = self.added_name(item);
+            let key = item.kind.key();
+            if name.trim().is_empty() {
+                return Some(format!(
+                    "the new name for the {} '{}' is empty",
+                    item.kind, item.name
+                ));
+            }
+            if find_item(&self.current, item.kind, &name).is_ok()
+                || taken.iter().any(|(k, n)| *k == key && *n == name)
+            {
+                return Some(format!("there is already a {} named '{name}'", item.kind));
+            }
+            taken.push((key, name));
+        }
+        None
+    }
+
+    fn added_name(&self, item: &MergeItem) -> String {
+        match item.choice {
+            ConflictChoice::Rename if item.status() == MergeStatus::Conflict => {
+                item.new_name.clone()
+            }
+            _ => item.name.clone(),
+        }
+    }
+
+    /// The current data with the incoming items merged in, in the export format.
+    /// New clocks, relationships between new pairs, and the faction turn log come along too
+    pub fn merged(&self) -> Result<Value> {
+        if let Some(problem) = self.problem() {
+            return Err(anyhow!(problem));
+        }
+        let mut merged = self.current.clone();
+        let mut ids = IdMap::new();
+
+        let mut targets = Vec::new();
+        for item in &self.items {
+            let key = item.kind.key();
+            let incoming_id =
+                items(&self.incoming, item.kind)[item.incoming_index]["id"].to_string();
+            let target = match item.existing_index {
+                Some(index) if !item.is_added() => index,
+                _ => {
+                    let mut new_item = item.kind.template()?;
+                    new_item["id"] = Value::String(EntityId::new().to_string());
+                    new_item["name"] = Value::String(self.added_name(item));
+                    push(&mut merged, key, new_item)?
+                }
+            };
+            ids.insert((key, incoming_id), merged[key][target]["id"].clone());
+            targets.push(target);
+        }
+
+        let new_clocks = add_clocks(&mut merged, &self.incoming, &mut ids)?;
+
+        for (item, target) in self.items.iter().zip(targets) {
+            let key = item.kind.key();
+            let incoming_item = &items(&self.incoming, item.kind)[item.incoming_index];
+            for field in item.taken_fields(&self.incoming) {
+                let value = match reference_kind(item.kind, &field) {
+                    Some(target_kind) => map_ids(&ids, target_kind.key(), &incoming_item[&field]),
+                    None => incoming_item[&field].clone(),
+                };
+                merged[key][target][&field] = value;
+            }
+        }
+
+        link_clocks(&mut merged, &new_clocks, &ids);
+        add_relationships(&mut merged, &self.incoming, &ids)?;
+
+        // an import continues the log of the current data, leaving out the turns it already has
+        for mut entry in self.incoming[TURN_LOG]
+            .as_array()
+            .cloned()
+            .unwrap_or_default()
+        {
+            let log = merged[TURN_LOG]
+                .as_array()
+                .map_or(&[][..], |l| l.as_slice());
+            if !log.iter().any(|e| e["results"] == entry["results"]) {
+                entry["turn"] = Value::from(log.len() + 1);
+                push(&mut merged, TURN_LOG, entry)?;
+            }
+        }
+
+        Ok(merged)
+    }
+}
+
+/// Where each incoming id ends up in the merged data, by the list it is in.
+/// It must hold every item before any fields are copied, so that references can be mapped
+pub(crate) type IdMap = BTreeMap<(&'static str, String), Value>;
+
+/// Adds the incoming clocks whose names are not taken, returning where they were added.
+/// Their references are linked once every item has been placed
+pub(crate) fn add_clocks(
+    merged: &mut Value,
+    incoming: &Value,
+    ids: &mut IdMap,
+) -> Result<Vec<usize>> {
+    let mut new_clocks = Vec::new();
+    for clock in incoming[CLOCKS]
+        .as_array()
+        .map_or(&[][..], |list| list.as_slice())
+    {
+        let existing = merged[CLOCKS]
+            .as_array()
+            .and_then(|list| list.iter().position(|c| c["name"] == clock["name"]));
+        let target = match existing {
+            Some(index) => index,
+            None => {
+                let mut new_clock = clock.clone();
+                new_clock["id"] = Value::String(EntityId::new().to_string());
+                let index = push(merged, CLOCKS, new_clock)?;
+                new_clocks.push(index);
+                index
+            }
+        };
+        ids.insert(
+            (CLOCKS, clock["id"].to_string()),
+            merged[CLOCKS][target]["id"].clone(),
+        );
+    }
+    Ok(new_clocks)
+}
+
+pub(crate) fn link_clocks(merged: &mut Value, new_clocks: &[usize], ids: &IdMap) {
+    for index in new_clocks {
+        for field in fields(&merged[CLOCKS][index]) {
+            if let Some(target_key) = clock_reference(&field) {
+                let value = map_ids(ids, target_key, &merged[CLOCKS][index][&field]);
+                merged[CLOCKS][index][&field] = value;
+            }
+        }
+    }
+}
+
+/// A relationship is only brought in between a pair which has none yet
+pub(crate) fn add_relationships(merged: &mut Value, incoming: &Value, ids: &IdMap) -> Result<()> {
+    let actor = |id: &Value| {
+        ids.get(&(ItemKind::Faction.key(), id.to_string()))
+            .or_else(|| ids.get(&(ItemKind::Person.key(), id.to_string())))
+            .cloned()
+    };
+    for relationship in incoming[RELATIONSHIPS]
+        .as_array()
+        .map_or(&[][..], |list| list.as_slice())
+    {
+        let (Some(first), Some(second)) = (
+            actor(&relationship["first"]),
+            actor(&relationship["second"]),
+        ) else {
+            continue;
+        };
+        let present = merged[RELATIONSHIPS].as_array().is_some_and(|list| {
+            list.iter().any(|r| {
+                (r["first"] == first && r["second"] == second)
+                    || (r["first"] == second && r["second"] == first)
+            })
+        });
+        if !present {
+            let mut new_relationship = relationship.clone();
+            new_relationship["first"] = first;
+            new_relationship["second"] = second;
+            push(merged, RELATIONSHIPS, new_relationship)?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn push(value: &mut Value, key: &str, item: Value) -> Result<usize> {
+    let list = value[key]
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("no {key} in the data"))?;
+    list.push(item);
+    Ok(list.len() - 1)
+}
+
+/// Maps incoming ids to the merged data, dropping any which did not come across
+pub(crate) fn map_ids(ids: &IdMap, key: &str, value: &Value) -> Value {
+    let map = |id: &Value| ids.get(&(key, id.to_string())).cloned();
+    match value {
+        Value::Array(list) => Value::Array(list.iter().filter_map(map).collect()),
+        Value::Null => Value::Null,
+        id => map(id).unwrap_or(Value::Null),
+    }
+}
+
+#[cfg(feature = "gui")]
+impl MergePreview {
+    /// Lists the new, identical and conflicting items, with a choice for each conflict
+    pub fn show_edit(&mut self, ui: &mut Ui) {
+        const TEXT_WIDTH: f32 = 220.0;
+        const LIST_HEIGHT: f32 = 360.0;
+
+        ui.label(fl!(
+            "merge_counts",
+            new = self.count(MergeStatus::New),
+            identical = self.count(MergeStatus::Identical),
+            conflicts = self.count(MergeStatus::Conflict)
+        ));
+        ui.add_space(UI_PADDING);
+
+        ScrollArea::vertical()
+            .max_height(LIST_HEIGHT)
+            .show(ui, |ui| {
+                for (status, heading) in [
+                    (MergeStatus::Conflict, fl!("merge_conflicts")),
+                    (MergeStatus::New, fl!("merge_new")),
+                    (MergeStatus::Identical, fl!("merge_identical")),
+                ] {
+                    if self.count(status) == 0 {
+                        continue;
+                    }
+                    CollapsingHeader::new(heading)
+                        .default_open(status == MergeStatus::Conflict)
+                        .show(ui, |ui| {
+                            for (index, item) in self.items.iter_mut().enumerate() {
+                                if item.status() != status {
+                                    continue;
+                                }
+                                let label = format!("{}: {}", item.kind, item.name);
+                                if status != MergeStatus::Conflict {
+                                    ui.label(label);
+                                    continue;
+                                }
+                                ui.horizontal(|ui| {
+                                    ui.label(RichText::new(label).strong());
+                                    ComboBox::from_id_salt(("merge_choice", index))
+                                        .selected_text(choice_text(item.choice))
+                                        .show_ui(ui, |ui| {
+                                            for choice in [
+                                                ConflictChoice::KeepExisting,
+                                                ConflictChoice::TakeIncoming,
+                                                ConflictChoice::MergeFields,
+                                                ConflictChoice::Rename,
+                                            ] {
+                                                ui.selectable_value(
+                                                    &mut item.choice,
+                                                    choice,
+                                                    choice_text(choice),
+                                                );
+                                            }
+                                        });
+                                    if item.choice == ConflictChoice::Rename {
+                                        ui.add(
+                                            TextEdit::singleline(&mut item.new_name)
+                                                .desired_width(TEXT_WIDTH),
+                                        );
+                                    }
+                                });
+                                if item.choice == ConflictChoice::MergeFields {
+                                    Grid::new(("merge_fields", index)).striped(true).show(
+                                        ui,
+                                        |ui| {
+                                            for difference in &mut item.differences {
+                                                ui.label(&difference.field);
+                                                ui.radio_value(
+                                                    &mut difference.take_incoming,
+                                                    false,
+                                                    &difference.existing,
+                                                );
+                                                ui.radio_value(
+                                                    &mut difference.take_incoming,
+                                                    true,
+                                                    &difference.incoming,
+                                                );
+                                                ui.end_row();
+                                            }
+                                        },
+                                    );
+                                }
+                                ui.add_space(UI_PADDING);
+                            }
+                        });
+                }
+            });
+    }
+}
+
+#[cfg(feature = "gui")]
+fn choice_text(choice: ConflictChoice) -> String {
+    match choice {
+        ConflictChoice::KeepExisting => fl!("merge_keep"),
+        ConflictChoice::TakeIncoming => fl!("merge_take"),
+        ConflictChoice::MergeFields => fl!("merge_fields"),
+        ConflictChoice::Rename => fl!("merge_rename"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::{
+        app_data::AppData,
+        export_format::{ItemKind, field_text, find_item, test_campaign},
+        merge::{ConflictChoice, MergePreview, MergeStatus},
+    };
+
+    const HQ: (&str, &str) = ("hq", "Crow's Foot");
+
+    #[test]
+    fn conflicts_are_resolved_and_undone_as_one() {
+        let current = test_campaign(
+            &["Crow's Foot"],
+            &[
+                ("The Crows", &[("tier", "Tier2"), ("notes", "old"), HQ]),
+                ("The Hive", &[("tier", "Tier4"), HQ]),
+            ],
+        );
+        let incoming = test_campaign(
+            &["Crow's Foot"],
+            &[
+                ("The Crows", &[("tier", "Tier3"), ("notes", "new"), HQ]),
+                ("The Hive", &[("tier", "Tier4"), HQ]),
+                ("The Lampblacks", &[("tier", "Tier2"), HQ]),
+            ],
+        );
+        let mut preview = MergePreview::new(current.clone(), incoming);
+        assert_eq!(preview.count(MergeStatus::New), 1);
+        assert_eq!(preview.count(MergeStatus::Identical), 2); // the district and the Hive
+        assert_eq!(preview.count(MergeStatus::Conflict), 1);
+
+        let conflict = preview
+            .items()
+            .iter()
+            .position(|i| i.status() == MergeStatus::Conflict)
+            .unwrap();
+        let item = &mut preview.items_mut()[conflict];
+        assert_eq!(item.differences.len(), 2);
+        item.choice = ConflictChoice::MergeFields;
+        item.differences
+            .iter_mut()
+            .find(|d| d.field == "tier")
+            .unwrap()
+            .take_incoming = true;
+        let merged = preview.merged().unwrap();
+        let crows = find_item(&merged, ItemKind::Faction, "The Crows").unwrap();
+        assert_eq!(
+            field_text(&merged, ItemKind::Faction, crows, "tier"),
+            "Tier3"
+        );
+        assert_eq!(
+            field_text(&merged, ItemKind::Faction, crows, "notes"),
+            "old"
+        );
+
+        preview.items_mut()[conflict].choice = ConflictChoice::Rename;
+        preview.items_mut()[conflict].new_name = "The Hive".into();
+        assert!(preview.problem().is_some());
+        preview.items_mut()[conflict].new_name = "The Other Crows".into();
+
+        let mut data = AppData::from_json_value(current).unwrap();
+        let before = data.to_json_value().unwrap();
+        let mut reverse = data.apply_merge(preview.merged().unwrap()).unwrap();
+        let after = data.to_json_value().unwrap();
+        let renamed = find_item(&after, ItemKind::Faction, "The Other Crows").unwrap();
+        assert_eq!(
+            field_text(&after, ItemKind::Faction, renamed, "tier"),
+            "Tier3"
+        );
+        assert_eq!(
+            field_text(&after, ItemKind::Faction, renamed, "hq"),
+            "Crow's Foot"
+        );
+        assert!(find_item(&after, ItemKind::Faction, "The Lampblacks").is_ok());
+
+        data.do_action(&mut reverse).unwrap();
+        assert_eq!(data.to_json_value().unwrap(), before);
+    }
+
+    #[test]
+    fn turns_already_logged_are_not_imported_again() {
+        let turn = |faction: &str| {
+            serde_json::json!({
+                "turn": 1,
+                "results": [{"faction": faction, "dice": [4], "clock": "Expand", "ticks": 2, "finished": false}],
+            })
+        };
+        let mut current = test_campaign(&[], &[]);
+        current["turn_log"] = Value::Array(vec![turn("The Crows")]);
+        let mut incoming = test_campaign(&[], &[]);
+        incoming["turn_log"] = Value::Array(vec![turn("The Crows"), turn("The Hive")]);
+
+        let merged = MergePreview::new(current, incoming.clone())
+            .merged()
+            .unwrap();
+        let merged = MergePreview::new(merged, incoming).merged().unwrap();
+        let log = merged["turn_log"].as_array().unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[1]["turn"], 2);
+        assert_eq!(log[1]["results"][0]["faction"], "The Hive");
+    }
+}
@@ -0,0 +1,606 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use log::{debug, info, warn};
+
+use crate::{
+    clock::Clock,
+    display::MainView,
+    district::DistrictStore,
+    export_format::{join_list, split_list},
+    faction::FactionStore,
+    markdown::MARKDOWN_EXTENSION,
+    persistence::save_to_text,
+    person::PersonStore2,
+};
+
+/// Opens and closes the YAML frontmatter of a note
+const FRONTMATTER: &str = "---";
+/// Separates the name of a field from its value, as Dataview reads inline fields
+const FIELD_SEPARATOR: &str = "::";
+/// Starts a section of longer text
+const SECTION: &str = "## ";
+
+// The names in a note are read back in, so they are not localized
+pub const KIND_KEY: &str = "kind";
+pub const NAME_KEY: &str = "name";
+
+/// The items of a campaign, as they are kept in a vault, with references held by name
+#[derive(Default)]
+pub struct VaultItems {
+    pub persons: Vec<PersonStore2>,
+    pub districts: Vec<DistrictStore>,
+    pub factions: Vec<FactionStore>,
+}
+
+/// Writes one note per item into the vault, in a folder for each kind of item
+pub fn export(items: &VaultItems, vault: &Path) -> Result<()> {
+    let names = VaultNames::new(
+        items
+            .factions
+            .iter()
+            .map(|f| (MainView::Factions, f.name()))
+            .chain(items.persons.iter().map(|p| (MainView::Persons, p.name())))
+            .chain(
+                items
+                    .districts
+                    .iter()
+                    .map(|d| (MainView::Districts, d.name())),
+            ),
+    );
+
+    for faction in &items.factions {
+        let mut note = NoteWriter::new(&names, MainView::Factions, faction.name());
+        faction.write_note(&mut note);
+        note.save(vault)?;
+    }
+    for person in &items.persons {
+        let mut note = NoteWriter::new(&names, MainView::Persons, person.name());
+        person.write_note(&mut note);
+        note.save(vault)?;
+    }
+    for district in &items.districts {
+        let mut note = NoteWriter::new(&names, MainView::Districts, district.name());
+        district.write_note(&mut note);
+        note.save(vault)?;
+    }
+    info!("exported vault to {}", vault.to_string_lossy());
+    Ok(())
+}
+
+/// Reads every note in the vault which has a kind, ignoring any other notes
+pub fn import(vault: &Path) -> Result<VaultItems> {
+    if !vault.is_dir() {
+        return Err(anyhow!("{} is not a folder", vault.to_string_lossy()));
+    }
+    let mut files = Vec::new();
+    find_notes(vault, &mut files)?;
+
+    let mut notes = Vec::new();
+    for file in files {
+        let text = fs::read_to_string(&file)?;
+        let stem = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match Note::parse(&stem, &text) {
+            Some(note) => notes.push(note),
+            None => debug!("{} is not a campaign note", file.to_string_lossy()),
+        }
+    }
+
+    // links are to file names, which need not be the item names
+    let stems: BTreeMap<(MainView, String), String> = notes
+        .iter()
+        .map(|n| ((n.kind, n.stem.clone()), n.name.clone()))
+        .collect();
+
+    notes.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut items = VaultItems::default();
+    for note in &notes {
+        let reader = NoteReader {
+            note,
+            stems: &stems,
+        };
+        match note.kind {
+            MainView::Factions => items.factions.push(FactionStore::from_note(&reader)),
+            MainView::Persons => items.persons.push(PersonStore2::from_note(&reader)),
+            MainView::Districts => items.districts.push(DistrictStore::from_note(&reader)),
+            MainView::Clocks => {}
+        }
+    }
+    info!(
+        "read {} factions, {} persons, {} districts from {}",
+        items.factions.len(),
+        items.persons.len(),
+        items.districts.len(),
+        vault.to_string_lossy()
+    );
+    Ok(items)
+}
+
+/// Every Markdown file in the vault, leaving out hidden folders such as `.obsidian`
+fn find_notes(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            find_notes(&path, files)?;
+        } else if path.extension().is_some_and(|e| e == MARKDOWN_EXTENSION) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// -----------------------------
+// Kinds
+
+/// The kind of item, as it is written in the frontmatter
+fn kind_key(kind: MainView) -> &'static str {
+    match kind {
+        MainView::Factions => "faction",
+        MainView::Persons => "person",
+        MainView::Districts => "district",
+        MainView::Clocks => "clock",
+    }
+}
+
+fn kind_from_key(key: &str) -> Option<MainView> {
+    match key.trim().to_lowercase().as_str() {
+        "faction" => Some(MainView::Factions),
+        "person" => Some(MainView::Persons),
+        "district" => Some(MainView::Districts),
+        _ => None,
+    }
+}
+
+/// The folder of each kind of item
+fn folder(kind: MainView) -> &'static str {
+    match kind {
+        MainView::Factions => "Factions",
+        MainView::Persons => "People",
+        MainView::Districts => "Districts",
+        MainView::Clocks => "Clocks",
+    }
+}
+
+// -----------------------------
+// Names
+
+/// The file name of every item's note, so that references can become wikilinks
+struct VaultNames {
+    stems: BTreeMap<(MainView, String), String>,
+    /// How many notes share each file name, across every folder
+    counts: BTreeMap<String, usize>,
+}
+
+impl VaultNames {
+    fn new<'a>(items: impl Iterator<Item = (MainView, &'a str)>) -> Self {
+        let mut stems = BTreeMap::new();
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        let mut used: BTreeMap<MainView, Vec<String>> = BTreeMap::new();
+        for (kind, name) in items {
+            // names are unique, but need not stay so once they are made safe for files
+            let used = used.entry(kind).or_default();
+            let base = file_stem(name);
+            let mut stem = base.clone();
+            let mut count = 1;
+            while used.contains(&stem) {
+                count += 1;
+                stem = format!("{base} {count}");
+            }
+            used.push(stem.clone());
+            *counts.entry(stem.clone()).or_default() += 1;
+            stems.insert((kind, name.to_string()), stem);
+        }
+        VaultNames { stems, counts }
+    }
+
+    fn stem(&self, kind: MainView, name: &str) -> String {
+        self.stems
+            .get(&(kind, name.to_string()))
+            .cloned()
+            .unwrap_or_else(|| file_stem(name))
+    }
+
+    /// A wikilink to an item, with its folder when another note has the same file name
+    fn wikilink(&self, kind: MainView, name: &str) -> String {
+        let stem = self.stem(kind, name);
+        let target = if self.counts.get(&stem).copied().unwrap_or_default() > 1 {
+            format!("{}/{stem}", folder(kind))
+        } else {
+            stem.clone()
+        };
+        if stem == name {
+            format!("[[{target}]]")
+        } else {
+            format!("[[{target}|{name}]]")
+        }
+    }
+}
+
+/// A name with the characters which Obsidian does not allow in file names replaced
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if matches!(
+                c,
+                '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']'
+            ) {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let stem = stem.trim().trim_end_matches('.');
+    if stem.is_empty() {
+        "item".to_string()
+    } else {
+        stem.to_string()
+    }
+}
+
+// -----------------------------
+// Writing
+
+/// One item's note
+pub struct NoteWriter<'a> {
+    names: &'a VaultNames,
+    kind: MainView,
+    name: &'a str,
+    frontmatter: Vec<(String, String)>,
+    fields: Vec<String>,
+    sections: Vec<String>,
+}
+
+impl<'a> NoteWriter<'a> {
+    fn new(names: &'a VaultNames, kind: MainView, name: &'a str) -> Self {
+        NoteWriter {
+            names,
+            kind,
+            name,
+            frontmatter: vec![
+                (KIND_KEY.to_string(), kind_key(kind).to_string()),
+                (NAME_KEY.to_string(), quoted(name)),
+            ],
+            fields: Vec::new(),
+            sections: Vec::new(),
+        }
+    }
+
+    /// A number in the frontmatter
+    pub fn number(&mut self, key: &str, value: usize) {
+        self.frontmatter.push((key.to_string(), value.to_string()));
+    }
+
+    /// A short piece of text in the frontmatter
+    pub fn text(&mut self, key: &str, value: &str) {
+        self.frontmatter.push((key.to_string(), quoted(value)));
+    }
+
+    /// A field which refers to other items, always written so it can be filled in
+    pub fn links(&mut self, field: &str, kind: MainView, names: &[String]) {
+        let links: Vec<String> = names.iter().map(|n| self.names.wikilink(kind, n)).collect();
+        self.fields
+            .push(format!("{field}{FIELD_SEPARATOR} {}", links.join(", ")));
+    }
+
+    /// A field of plain values, separated by commas, and quoted if they hold one
+    pub fn values(&mut self, field: &str, values: &[String]) {
+        self.fields.push(format!(
+            "{field}{FIELD_SEPARATOR} {}",
+            join_list(values.iter().cloned())
+        ));
+    }
+
+    /// A longer piece of text under its own heading, left out when it is empty
+    pub fn section(&mut self, heading: &str, text: &str) {
+        if !text.trim().is_empty() {
+            self.sections
+                .push(format!("{SECTION}{heading}\n\n{}\n", text.trim()));
+        }
+    }
+
+    /// The clocks, as a list of progress and name
+    pub fn clocks(&mut self, heading: &str, clocks: &[Clock]) {
+        let lines: Vec<String> = clocks
+            .iter()
+            .map(|c| {
+                let (ticked, parts) = c.status();
+                if c.description().is_empty() {
+                    format!("- [{ticked}/{parts}] {}", c.name())
+                } else {
+                    format!("- [{ticked}/{parts}] {} - {}", c.name(), c.description())
+                }
+            })
+            .collect();
+        self.section(heading, &lines.join("\n"));
+    }
+
+    fn save(self, vault: &Path) -> Result<()> {
+        let mut text = format!("{FRONTMATTER}\n");
+        for (key, value) in &self.frontmatter {
+            text.push_str(&format!("{key}: {value}\n"));
+        }
+        text.push_str(&format!("{FRONTMATTER}\n\n"));
+        for field in &self.fields {
+            text.push_str(field.trim_end());
+            text.push('\n');
+        }
+        for section in &self.sections {
+            text.push('\n');
+            text.push_str(section);
+        }
+
+        let stem = self.names.stem(self.kind, self.name);
+        let path = vault
+            .join(folder(self.kind))
+            .join(format!("{stem}.{MARKDOWN_EXTENSION}"));
+        save_to_text(&path, &text)
+    }
+}
+
+/// A YAML string, which is also a JSON string
+fn quoted(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+// -----------------------------
+// Reading
+
+/// A note as it was written, or as it was edited in Obsidian
+struct Note {
+    stem: String,
+    kind: MainView,
+    name: String,
+    frontmatter: BTreeMap<String, String>,
+    fields: BTreeMap<String, String>,
+    sections: BTreeMap<String, String>,
+}
+
+impl Note {
+    /// Reads a note, if it has frontmatter which gives a kind of item
+    fn parse(stem: &str, text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        if lines.next()?.trim() != FRONTMATTER {
+            return None;
+        }
+
+        let mut frontmatter = BTreeMap::new();
+        for line in lines.by_ref() {
+            if line.trim() == FRONTMATTER {
+                break;
+            }
+            if let Some((key, value)) = line.split_once(':') {
+                frontmatter.insert(key.trim().to_lowercase(), unquoted(value.trim()));
+            }
+        }
+        let kind = kind_from_key(frontmatter.get(KIND_KEY)?)?;
+        let name = frontmatter
+            .get(NAME_KEY)
+            .filter(|n| !n.trim().is_empty())
+            .cloned()
+            .unwrap_or_else(|| stem.to_string());
+
+        let mut fields = BTreeMap::new();
+        let mut sections: BTreeMap<String, String> = BTreeMap::new();
+        let mut section: Option<String> = None;
+        for line in lines {
+            if let Some(heading) = line.strip_prefix(SECTION) {
+                section = Some(heading.trim().to_lowercase());
+            } else if let Some(heading) = &section {
+                let text = sections.entry(heading.clone()).or_default();
+                text.push_str(line);
+                text.push('\n');
+            } else if let Some((field, value)) = line.split_once(FIELD_SEPARATOR) {
+                fields.insert(field.trim().to_lowercase(), value.trim().to_string());
+            }
+        }
+        for text in sections.values_mut() {
+            *text = text.trim().to_string();
+        }
+
+        Some(Note {
+            stem: stem.to_string(),
+            kind,
+            name,
+            frontmatter,
+            fields,
+            sections,
+        })
+    }
+}
+
+/// Takes the quotes from a YAML string, leaving other values as they are
+fn unquoted(value: &str) -> String {
+    if value.starts_with('"') {
+        serde_json::from_str(value).unwrap_or_else(|_| value.trim_matches('"').to_string())
+    } else if value.len() > 1 && value.starts_with('\'') && value.ends_with('\'') {
+        value[1..value.len() - 1].replace("''", "'")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Reads the fields of one note, resolving its links to the names of the items they refer to
+pub struct NoteReader<'a> {
+    note: &'a Note,
+    stems: &'a BTreeMap<(MainView, String), String>,
+}
+
+impl NoteReader<'_> {
+    pub fn name(&self) -> &str {
+        &self.note.name
+    }
+
+    /// A number in the frontmatter, or 0 if it is missing
+    pub fn number(&self, key: &str) -> usize {
+        let value = self.note.frontmatter.get(key).map(|v| v.trim());
+        value.and_then(|v| v.parse().ok()).unwrap_or_else(|| {
+            if value.is_some_and(|v| !v.is_empty()) {
+                warn!("{key} in note {} is not a number", self.note.name);
+            }
+            0
+        })
+    }
+
+    pub fn text(&self, key: &str) -> String {
+        self.note.frontmatter.get(key).cloned().unwrap_or_default()
+    }
+
+    /// The names of the items a field links to; plain names are read as links
+    pub fn links(&self, field: &str, kind: MainView) -> Vec<String> {
+        self.values(field)
+            .into_iter()
+            .map(|target| {
+                self.stems
+                    .get(&(kind, target.clone()))
+                    .cloned()
+                    .unwrap_or(target)
+            })
+            .collect()
+    }
+
+    /// The first item a field links to
+    pub fn link(&self, field: &str, kind: MainView) -> Option<String> {
+        self.links(field, kind).into_iter().next()
+    }
+
+    /// The values of a field, which are the targets of its wikilinks if it has any
+    pub fn values(&self, field: &str) -> Vec<String> {
+        let Some(value) = self.note.fields.get(&field.to_lowercase()) else {
+            return Vec::new();
+        };
+        let links = wikilink_targets(value);
+        if !links.is_empty() {
+            return links;
+        }
+        split_list(value)
+    }
+
+    pub fn section(&self, heading: &str) -> String {
+        self.note
+            .sections
+            .get(&heading.to_lowercase())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The clocks, from lines such as `- [2/4] Expand`
+    pub fn clocks(&self, heading: &str) -> Vec<Clock> {
+        self.section(heading)
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim().strip_prefix("- [")?;
+                let (progress, rest) = line.split_once(']')?;
+                let (ticked, parts) = progress.split_once('/')?;
+                let (name, description) = rest.trim().split_once(" - ").unwrap_or((rest, ""));
+                Some(Clock::with_progress(
+                    name.trim(),
+                    description.trim(),
+                    parts.trim().parse().ok()?,
+                    ticked.trim().parse().ok()?,
+                ))
+            })
+            .collect()
+    }
+}
+
+/// The file names that the wikilinks in some text point to
+fn wikilink_targets(text: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        let Some(end) = rest[start..].find("]]") else {
+            break;
+        };
+        let link = &rest[start + 2..start + end];
+        // [[folder/file#heading|shown text]]
+        let target = link.split('|').next().unwrap_or_default();
+        let target = target.split('#').next().unwrap_or_default();
+        let target = target.rsplit('/').next().unwrap_or_default().trim();
+        let target = target
+            .strip_suffix(&format!(".{MARKDOWN_EXTENSION}"))
+            .unwrap_or(target);
+        if !target.is_empty() {
+            targets.push(target.to_string());
+        }
+        rest = &rest[start + end + 2..];
+    }
+    targets
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{
+        district::DistrictStore,
+        faction::FactionStore,
+        obsidian::{VaultItems, export, import, wikilink_targets},
+        persistence::test_dir,
+    };
+
+    #[test]
+    fn vault_round_trips_edits() {
+        assert_eq!(
+            wikilink_targets("[[Districts/Crow's Foot|the Foot]], [[Six Towers#History]]"),
+            ["Crow's Foot", "Six Towers"]
+        );
+
+        let items = VaultItems {
+            districts: vec![
+                serde_json::from_str(r#"{"name":"Crow's Foot","description":"","wealth":"Two","safety":"One","crime":"Four","occult":"Zero","notable":[],"notes":""}"#).unwrap(),
+                serde_json::from_str(r#"{"name":"Six Towers","description":"","wealth":"Three","safety":"Two","crime":"One","occult":"One","notable":[],"notes":""}"#).unwrap(),
+            ],
+            factions: vec![
+                serde_json::from_str(r#"{"name":"The Crows: Reborn","description":"A gang","tier":"Tier2","hq":"Crow's Foot","turf":["Crow's Foot"],"leader":null,"notable":[],"assets":"","notes":"","allies":[],"enemies":[],"general":"","clocks":[{"name":"Expand","description":"","parts":4,"ticked":1}]}"#).unwrap(),
+            ],
+            persons: vec![
+                serde_json::from_str(r#"{"name":"Lyssa","summary":"","found_in":"Crow's Foot","description":"","personality":["quick, but careless","loyal"],"notes":""}"#).unwrap(),
+            ],
+        };
+        let vault = test_dir("vault");
+        export(&items, &vault).unwrap();
+
+        // an edit in Obsidian: a new turf by wikilink, a higher tier, and another tick
+        let note = vault.join("Factions").join("The Crows- Reborn.md");
+        let text = fs::read_to_string(&note).unwrap();
+        assert!(text.contains("[[Crow's Foot]]"));
+        let text = text
+            .replace(
+                "Turf:: [[Crow's Foot]]",
+                "Turf:: [[Crow's Foot]], [[Six Towers]]",
+            )
+            .replace("tier: 2", "tier: 3")
+            .replace("[1/4]", "[2/4]");
+        fs::write(&note, text).unwrap();
+
+        let read = import(&vault).unwrap();
+        fs::remove_dir_all(&vault).unwrap();
+        assert_eq!(read.districts.len(), 2);
+        let crows: &FactionStore = &read.factions[0];
+        assert_eq!(crows.name(), "The Crows: Reborn");
+        let value = serde_json::to_value(crows).unwrap();
+        assert_eq!(value["tier"], "Tier3");
+        assert_eq!(value["hq"], "Crow's Foot");
+        assert_eq!(value["turf"][1], "Six Towers");
+        assert_eq!(value["clocks"][0]["ticked"], 2);
+        let lyssa = serde_json::to_value(&read.persons[0]).unwrap();
+        assert_eq!(lyssa["personality"][0], "quick, but careless");
+        let six: &DistrictStore = &read.districts[1];
+        assert_eq!(serde_json::to_value(six).unwrap()["wealth"], "Three");
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    fs::{self, File, OpenOptions, create_dir_all},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use bytes_cast::{BytesCast, unaligned};
+use serde::{Serialize, de::DeserializeOwned};
+
+// ---------------------------
+// Atomic writes
+
+/// Writes to a temporary file beside the target, which replaces the target only once
+/// it is complete, so a failed write never loses the previous file
+fn write_atomically<F>(file_path: &Path, kind: &str, write: F) -> anyhow::Result<()>
+where
+    F: FnOnce(&mut BufWriter<&File>) -> anyhow::Result<()>,
+{
+    use anyhow::Context;
+
+    if let Some(dir_path) = file_path.parent() {
+        create_dir_all(dir_path)?
+    }
+
+    let mut temp_name = file_path.as_os_str().to_owned();
+    temp_name.push(TEMP_EXTENSION);
+    let temp_path = PathBuf::from(temp_name);
+
+    let result = (|| {
+        let file_handler = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temp_path)
+            .with_context(|| {
+                format!(
+                    "Failed to create {kind} file [{}]",
+                    temp_path.to_string_lossy()
+                )
+            })?;
+        let mut buf_writer = BufWriter::new(&file_handler);
+        write(&mut buf_writer)?;
+        buf_writer.flush()?;
+        drop(buf_writer);
+        file_handler.sync_all()?;
+        fs::rename(&temp_path, file_path).with_context(|| {
+            format!(
+                "Failed to replace {kind} file [{}]",
+                file_path.to_string_lossy()
+            )
+        })
+    })();
+
+    if result.is_err() && temp_path.exists() {
+        let _ = fs::remove_file(&temp_path); // the original error is the one to report
+    }
+    result
+}
+
+// ---------------------------
+// Load and Save to POT files
+const SAVE_FILE_ID: &[u8] = &[0x2b, 0x4a]; // magic number - is this endian neutral?
+const TEMP_EXTENSION: &str = ".tmp";
+
+// used in Settings
+pub fn save_to_pot<T>(file_path: &Path, data: &T) -> anyhow::Result<()>
+where
+    T: Serialize,
+{
+    let mut buf = pot::to_vec(data)?;
+    buf.splice(0..0, SAVE_FILE_ID.iter().cloned());
+    write_atomically(file_path, "save", |buf_writer| {
+        buf_writer.write_all(buf.as_slice())?;
+        Ok(())
+    })
+}
+
+// used in Settings
+pub fn load_from_pot<T>(file_path: &Path) -> anyhow::Result<T>
+where
+    T: DeserializeOwned,
+{
+    use anyhow::Context;
+
+    let file_handler = OpenOptions::new()
+        .read(true)
+        .open(file_path)
+        .with_context(|| format!("Failed to open save file [{}]", file_path.to_string_lossy()))?;
+    let mut buf_reader = BufReader::new(&file_handler);
+    let mut check_id = [0u8, 0u8];
+    buf_reader.read_exact(&mut check_id)?;
+    if check_id != SAVE_FILE_ID {
+        return Err(anyhow!(
+            "File [{}] does not have expected SAVE_FILE_ID",
+            file_path.to_string_lossy()
+        ));
+    }
+    let data: T = pot::from_reader(buf_reader)?;
+    Ok(data)
+}
+
+pub fn save_to_save(
+    file_path: &Path,
+    save_version: u16,
+    mut buffer: Vec<u8>,
+) -> anyhow::Result<()> {
+    let Ok((file_id, excess)) = unaligned::U16Le::from_bytes(SAVE_FILE_ID) else {
+        return Err(anyhow!("unable to convert header bytes"));
+    };
+    assert!(excess.is_empty());
+    let save_header = SaveFileHeader {
+        file_id: *file_id,
+        save_version: save_version.into(),
+    };
+    buffer.splice(0..0, save_header.as_bytes().iter().cloned());
+
+    write_atomically(file_path, "save", |buf_writer| {
+        buf_writer.write_all(buffer.as_slice())?;
+        Ok(())
+    })
+}
+
+pub fn load_from_save(file_path: &Path) -> anyhow::Result<(u16, BufReader<File>)> {
+    use anyhow::Context;
+
+    let file_handle = OpenOptions::new()
+        .read(true)
+        .open(file_path)
+        .with_context(|| format!("Failed to open save file [{}]", file_path.to_string_lossy()))?;
+
+    let mut buf_reader = BufReader::new(file_handle);
+    let mut check_header = [0u8, 0u8, 0u8, 0u8];
+    buf_reader.read_exact(&mut check_header)?;
+    let Ok((header, excess)) = SaveFileHeader::from_bytes(&check_header) else {
+        return Err(anyhow!("unable to convert header bytes"));
+    };
+    if header.file_id.as_bytes() != SAVE_FILE_ID {
+        return Err(anyhow!("File does not have expected SAVE_FILE_ID")); // file name? pass up?
+    }
+    assert!(excess.is_empty());
+    let save_version = header.save_version.into();
+    Ok((save_version, buf_reader))
+}
+
+// ------
+#[derive(Debug, BytesCast)]
+#[repr(C)]
+struct SaveFileHeader {
+    file_id: unaligned::U16Le,
+    save_version: unaligned::U16Le,
+}
+
+// ---------------------------
+// Text files
+
+/// Writes a text document, such as an exported page
+pub fn save_to_text(file_path: &Path, text: &str) -> anyhow::Result<()> {
+    save_to_bytes(file_path, text.as_bytes())
+}
+
+/// Writes a file exactly as given, such as a font bundled with an export
+pub fn save_to_bytes(file_path: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    write_atomically(file_path, "export", |buf_writer| {
+        buf_writer.write_all(bytes)?;
+        Ok(())
+    })
+}
+
+// ---------------------------
+// Load and Save to JSON files
+
+#[allow(dead_code)]
+pub fn save_to_json<T>(file_path: &Path, data: &T) -> anyhow::Result<()>
+where
+    T: Serialize,
+{
+    write_atomically(file_path, "export", |buf_writer| {
+        serde_json::to_writer(buf_writer, data)?;
+        Ok(())
+    })
+}
+
+#[allow(dead_code)]
+pub fn load_from_json<T>(file_path: &Path) -> anyhow::Result<T>
+where
+    T: DeserializeOwned,
+{
+    use anyhow::Context;
+
+    let file_handler = OpenOptions::new()
+        .read(true)
+        .open(file_path)
+        .with_context(|| {
+            format!(
+                "Failed to open import file [{}]",
+                file_path.to_string_lossy()
+            )
+        })?;
+    let buf_reader = BufReader::new(&file_handler);
+    let data: T = serde_json::from_reader(buf_reader)?;
+    Ok(data)
+}
+
+/// A new folder in the temporary folder for a test, which the test creates and removes
+#[cfg(test)]
+pub fn test_dir(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("blades_{name}_{}", uuid::Uuid::new_v4()))
+}
@@ -1,20 +1,28 @@
+#[cfg(feature = "gui")]
 use eframe::egui::{Color32, RichText, TextEdit, TextStyle, Ui};
 use log::warn;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "gui")]
 use crate::{
     app::EditResult,
-    app_data::DataIndex,
     app_display::{
         DESCRIPTION_ROWS, FIELD_HORIZONTAL_SPACE, FIELD_VERTICAL_SPACE, NOTES_ROWS, ShowEdit,
         ShowEditInfo, show_edit_frame, show_edit_item, show_edit_stringlist_italics,
     },
+};
+use crate::{
+    app_data::DataIndex,
+    display::{ItemPage, MainView},
+    entity_id::{EntityId, NameIds},
     localize::fl,
     managed_list::{DistrictRef, Named, StringList},
+    obsidian::{NoteReader, NoteWriter},
 };
 
 #[derive(Default, Clone, PartialEq)]
 pub struct Person {
+    id: EntityId,
     name: String,
     summary: String,
     found_in: Option<DistrictRef>,
@@ -33,6 +41,33 @@ impl Person {
         }
         self.found_in = found_in;
     }
+
+    /// Returns the headings of every field which refers to the target
+    pub fn reference_fields(&self, target: DataIndex) -> Vec<String> {
+        if self
+            .found_in
+            .as_ref()
+            .is_some_and(|r| r.is_data_index(target))
+        {
+            vec![fl!("found_in_heading")]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Removes every reference to the target, returning whether anything changed
+    pub fn clear_references(&mut self, target: DataIndex) -> bool {
+        if self
+            .found_in
+            .as_ref()
+            .is_some_and(|r| r.is_data_index(target))
+        {
+            self.found_in = None;
+            true
+        } else {
+            false
+        }
+    }
 }
 
 impl Named for Person {
@@ -40,6 +75,14 @@ impl Named for Person {
         &self.name
     }
 
+    fn id(&self) -> EntityId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: EntityId) {
+        self.id = id;
+    }
+
     fn display_name(&self) -> String {
         format!(
             "{}{}",
@@ -75,15 +118,16 @@ impl Named for Person {
     }
 
     // MUST agree with display_fields
-    fn display_headings() -> Vec<RichText> {
+    fn display_headings() -> Vec<String> {
         vec![
-            RichText::new(fl!("name_heading")),
-            RichText::new(fl!("found_in_heading")),
-            RichText::new(fl!("summary_heading")),
+            fl!("name_heading"),
+            fl!("found_in_heading"),
+            fl!("summary_heading"),
         ]
     }
 }
 
+#[cfg(feature = "gui")]
 impl ShowEdit for Person {
     fn show_edit(&mut self, ui: &mut Ui, item_info: ShowEditInfo) -> Option<EditResult> {
         show_edit_frame(ui, fl!("main_item_person"), "person", item_info, |ui| {
@@ -138,6 +182,8 @@ impl ShowEdit for Person {
                     });
                 });
 
+                item_info.show_edit_relationships(ui);
+
                 ui.add_space(FIELD_VERTICAL_SPACE * 2.0);
                 ui.label(RichText::new(fl!("notes_heading")).small().weak());
                 ui.add(
@@ -150,6 +196,70 @@ impl ShowEdit for Person {
     }
 }
 
+// ---------------
+// PersonStore version 3 (references by id)
+#[allow(dead_code)]
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PersonStore3 {
+    pub id: EntityId,
+    name: String,
+    summary: String,
+    pub found_in: Option<EntityId>, // district
+    description: String,
+    personality: Vec<String>,
+    notes: String,
+}
+
+impl PersonStore3 {
+    /// Converts a store which refers to items by name
+    pub fn from_named(from_store: PersonStore2, ids: &NameIds) -> Self {
+        let name = from_store.name;
+        PersonStore3 {
+            id: ids.person(&name),
+            found_in: from_store
+                .found_in
+                .and_then(|d| ids.district_ref(&d, &format!("found_in of person {name}"))),
+            name,
+            summary: from_store.summary,
+            description: from_store.description,
+            personality: from_store.personality,
+            notes: from_store.notes,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl From<&Person> for PersonStore3 {
+    fn from(from_person: &Person) -> Self {
+        PersonStore3 {
+            id: from_person.id,
+            name: from_person.name.clone(),
+            summary: from_person.summary.clone(),
+            found_in: from_person.found_in.as_ref().and_then(|i| i.id()),
+            description: from_person.description.clone(),
+            personality: from_person.personality.list().to_vec(),
+            notes: from_person.notes.clone(),
+        }
+    }
+}
+
+impl From<&PersonStore3> for Person {
+    fn from(from_store: &PersonStore3) -> Self {
+        Person {
+            id: from_store.id,
+            name: from_store.name.clone(),
+            summary: from_store.summary.clone(),
+            found_in: None, // added after creation
+            description: from_store.description.clone(),
+            personality: StringList::from_list(from_store.personality.clone()),
+            notes: from_store.notes.clone(),
+        }
+    }
+}
+
 // ---------------
 // PersonStore version 2
 #[allow(dead_code)]
@@ -163,6 +273,45 @@ pub struct PersonStore2 {
     notes: String,
 }
 
+impl PersonStore2 {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Writes every field, with references as links to their pages
+    pub fn write_page(&self, page: &mut impl ItemPage) {
+        page.field(&fl!("summary_heading"), &self.summary);
+        page.link_field(
+            &fl!("found_in_heading"),
+            MainView::Districts,
+            self.found_in.as_deref(),
+        );
+        page.field(&fl!("personality_heading"), &self.personality.join(", "));
+        page.section(&fl!("description_heading"), &self.description);
+        page.section(&fl!("notes_heading"), &self.notes);
+    }
+
+    /// Writes the summary as frontmatter, and references as wikilinks
+    pub fn write_note(&self, note: &mut NoteWriter) {
+        note.text("summary", &self.summary);
+        note.links("Found In", MainView::Districts, self.found_in.as_slice());
+        note.values("Personality", &self.personality);
+        note.section("Description", &self.description);
+        note.section("Notes", &self.notes);
+    }
+
+    pub fn from_note(note: &NoteReader) -> Self {
+        PersonStore2 {
+            name: note.name().to_string(),
+            summary: note.text("summary"),
+            found_in: note.link("Found In", MainView::Districts),
+            description: note.section("Description"),
+            personality: note.values("Personality"),
+            notes: note.section("Notes"),
+        }
+    }
+}
+
 impl From<&Person> for PersonStore2 {
     fn from(from_person: &Person) -> Self {
         PersonStore2 {
@@ -192,6 +341,7 @@ impl From<PersonStore1> for PersonStore2 {
 impl From<&PersonStore2> for Person {
     fn from(from_store: &PersonStore2) -> Self {
         Person {
+            id: EntityId::default(), // given on add
             name: from_store.name.clone(),
             summary: from_store.summary.clone(),
             found_in: None, // added after creation
@@ -229,6 +379,7 @@ impl From<&Person> for PersonStore1 {
 impl From<&PersonStore1> for Person {
     fn from(from_store: &PersonStore1) -> Self {
         Person {
+            id: EntityId::default(), // given on add
             name: from_store.name.clone(),
             summary: from_store.summary.clone(),
             found_in: None,
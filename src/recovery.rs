@@ -0,0 +1,247 @@
+use std::{
+    fs, panic,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::Result;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_data::{AppData, JSON_EXTENSION, SAVE_EXTENSION},
+    persistence::{load_from_json, save_to_json, save_to_save},
+};
+
+/// The recovery file, in the data directory
+const RECOVERY_NAME: &str = "recovery";
+
+/// How often the campaign is written to the recovery file, while it is changing
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The latest state of the campaign, ready to be written out
+struct Snapshot {
+    save_version: u16,
+    buffer: Vec<u8>,
+    origin: RecoveryOrigin,
+}
+
+/// Which campaign the recovery file was taken from, kept beside it
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RecoveryOrigin {
+    loaded_from: Option<PathBuf>,
+}
+
+impl RecoveryOrigin {
+    pub fn loaded_from(&self) -> Option<PathBuf> {
+        self.loaded_from.clone()
+    }
+}
+
+/// Keeps a snapshot of every change, writing it out every so often, or on a panic
+pub struct Recovery {
+    save_path: PathBuf,
+    snapshot: Arc<Mutex<Option<Snapshot>>>,
+    changed: bool,
+    last_autosave: Instant,
+}
+
+impl Recovery {
+    pub fn new(data_dir: &Path) -> Self {
+        Recovery {
+            save_path: recovery_path(data_dir),
+            snapshot: Arc::default(),
+            changed: false,
+            last_autosave: Instant::now(),
+        }
+    }
+
+    /// Chains a hook in front of the current panic hook, which writes out the last snapshot
+    pub fn install_panic_hook(&self) {
+        let snapshot = self.snapshot.clone();
+        let save_path = self.save_path.clone();
+        let previous_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            // the lock may be held by the panicking thread, so never wait for it
+            if let Ok(guard) = snapshot.try_lock()
+                && let Some(snapshot) = guard.as_ref()
+            {
+                match write_snapshot(&save_path, snapshot) {
+                    Ok(()) => error!("panic: campaign saved to {}", save_path.to_string_lossy()),
+                    Err(e) => error!("panic: unable to save campaign: {e}"),
+                }
+            }
+            previous_hook(info);
+        }));
+    }
+
+    /// Takes a snapshot of the data after a change
+    pub fn record(&mut self, data: &AppData) {
+        match data.save_snapshot() {
+            Ok((save_version, buffer)) => {
+                let origin = RecoveryOrigin {
+                    loaded_from: data.get_loaded_from(),
+                };
+                if let Ok(mut guard) = self.snapshot.lock() {
+                    *guard = Some(Snapshot {
+                        save_version,
+                        buffer,
+                        origin,
+                    });
+                    self.changed = true;
+                }
+            }
+            Err(e) => error!("unable to take recovery snapshot: {e}"),
+        }
+    }
+
+    /// Writes the snapshot if it has changed, and the interval has passed
+    pub fn autosave_if_due(&mut self) {
+        if !self.changed || self.last_autosave.elapsed() < AUTOSAVE_INTERVAL {
+            return;
+        }
+        self.last_autosave = Instant::now();
+        self.changed = false;
+        if let Ok(guard) = self.snapshot.lock()
+            && let Some(snapshot) = guard.as_ref()
+        {
+            match write_snapshot(&self.save_path, snapshot) {
+                Ok(()) => info!("autosaved to {}", self.save_path.to_string_lossy()),
+                Err(e) => error!("unable to autosave: {e}"),
+            }
+        }
+    }
+
+    /// Whether a change is waiting for the next autosave
+    pub fn is_pending(&self) -> bool {
+        self.changed
+    }
+
+    /// Forgets the snapshot and removes the recovery file, once nothing needs recovering
+    pub fn clear(&mut self) {
+        if let Ok(mut guard) = self.snapshot.lock() {
+            *guard = None;
+        }
+        self.changed = false;
+        remove_recovery(&self.save_path);
+    }
+}
+
+fn recovery_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(RECOVERY_NAME).with_extension(SAVE_EXTENSION)
+}
+
+fn write_snapshot(save_path: &Path, snapshot: &Snapshot) -> Result<()> {
+    save_to_save(save_path, snapshot.save_version, snapshot.buffer.clone())?;
+    save_to_json(&save_path.with_extension(JSON_EXTENSION), &snapshot.origin)
+}
+
+fn remove_recovery(save_path: &Path) {
+    for path in [
+        save_path.to_path_buf(),
+        save_path.with_extension(JSON_EXTENSION),
+    ] {
+        if path.exists()
+            && let Err(e) = fs::remove_file(&path)
+        {
+            error!("unable to remove {}: {e}", path.to_string_lossy());
+        }
+    }
+}
+
+// -----------------------------
+// Recovering
+
+/// A recovery file left behind by a session which did not end cleanly
+#[derive(Debug, Clone)]
+pub struct RecoveredCampaign {
+    save_path: PathBuf,
+    origin: RecoveryOrigin,
+}
+
+impl RecoveredCampaign {
+    /// Finds a recovery file which is newer than the last manual save of its campaign
+    pub fn find(data_dir: &Path) -> Option<Self> {
+        let save_path = recovery_path(data_dir);
+        let recovered = modified(&save_path)?;
+        let origin: RecoveryOrigin = load_from_json(&save_path.with_extension(JSON_EXTENSION))
+            .unwrap_or_else(|e| {
+                error!("unable to read recovery origin: {e}");
+                RecoveryOrigin::default()
+            });
+
+        if let Some(saved) = origin.loaded_from.as_deref().and_then(modified)
+            && saved >= recovered
+        {
+            info!("recovery file is older than the last save - ignoring");
+            remove_recovery(&save_path);
+            return None;
+        }
+        Some(RecoveredCampaign { save_path, origin })
+    }
+
+    /// The campaign file the recovered data came from, if it had been saved
+    pub fn origin(&self) -> Option<PathBuf> {
+        self.origin.loaded_from()
+    }
+
+    pub fn load(&self) -> Result<AppData> {
+        let mut data = AppData::load_from_file(&self.save_path)?;
+        data.set_loaded_from(self.origin());
+        data.mark_changed();
+        Ok(data)
+    }
+
+    pub fn discard(&self) {
+        info!("discarding recovery file");
+        remove_recovery(&self.save_path);
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, time::Instant};
+
+    use crate::{
+        action::Action,
+        app_data::AppData,
+        campaign_clock::CampaignClock,
+        clock::Clock,
+        persistence::test_dir,
+        recovery::{AUTOSAVE_INTERVAL, RecoveredCampaign, Recovery},
+    };
+
+    #[test]
+    fn autosave_is_found_loaded_and_discarded() {
+        let dir = test_dir("recovery");
+        let campaign = dir.join("campaign.bfsav");
+        let mut data = AppData::default();
+        data.set_loaded_from(Some(campaign.clone()));
+        let clock = CampaignClock::new("Escape", Clock::new_clock_4("", ""));
+        data.do_action(&mut Action::ClockAdd(clock).into()).unwrap();
+
+        let mut recovery = Recovery::new(&dir);
+        recovery.record(&data);
+        recovery.autosave_if_due();
+        assert!(recovery.is_pending());
+        assert!(RecoveredCampaign::find(&dir).is_none());
+
+        recovery.last_autosave = Instant::now().checked_sub(AUTOSAVE_INTERVAL).unwrap();
+        recovery.autosave_if_due();
+        assert!(!recovery.is_pending());
+        let recovered = RecoveredCampaign::find(&dir).unwrap();
+        assert_eq!(recovered.origin(), Some(campaign));
+        let loaded = recovered.load().unwrap();
+        assert!(loaded.find_clock("Escape").is_some());
+        assert!(loaded.is_changed());
+
+        recovered.discard();
+        assert!(RecoveredCampaign::find(&dir).is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
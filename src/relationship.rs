@@ -1,15 +1,27 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, fmt::Display};
 
-use crate::app_data::{AppData, DataIndex};
+#[cfg(feature = "gui")]
+use eframe::egui::{ComboBox, Grid, Label, RichText, Sense, TextEdit, Ui};
+use log::error;
+#[cfg(feature = "gui")]
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_data::{AppData, DataIndex},
+    entity_id::EntityId,
+};
+#[cfg(feature = "gui")]
+use crate::{app_display::FIELD_HORIZONTAL_SPACE, localize::fl};
 
 // This cannot be based on GenericRef<> because that would make it impossible
 // to use the one struct to make relationships between categories of items
 #[allow(dead_code)]
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ActorPair(DataIndex, DataIndex);
 
-// For storing, we want to turn the DataIndexes into names
-// For loading, we want to turn names into DataIndexes
+// For storing, we want to turn the DataIndexes into ids
+// For loading, we want to turn ids into DataIndexes
 //
 // data indexes can get you to items, but if either item is None, the Pair should be ignored or removed
 
@@ -41,9 +53,26 @@ impl ActorPair {
             None
         }
     }
+
+    pub fn contains(&self, data_index: DataIndex) -> bool {
+        self.0 == data_index || self.1 == data_index
+    }
+
+    pub fn actors(&self) -> (DataIndex, DataIndex) {
+        (self.0, self.1)
+    }
+}
+
+/// Only factions and persons can have relationships
+pub fn can_have_relationships(data_index: DataIndex) -> bool {
+    matches!(
+        data_index,
+        DataIndex::FactionIndex(_) | DataIndex::PersonIndex(_)
+    )
 }
 
 #[allow(dead_code)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct Relationships {
     table: BTreeMap<ActorPair, Relationship>, // ?? Btree or Hash
 }
@@ -58,28 +87,232 @@ impl Relationships {
             .collect();
         MyRelationships {
             my_data_index,
+            original: table.clone(),
             table,
+            new: None,
+        }
+    }
+
+    pub fn get(&self, pair: &ActorPair) -> Option<&Relationship> {
+        self.table.get(pair)
+    }
+
+    /// Sets (or clears, with None) the relationship for the pair, returning the old one
+    pub fn set(
+        &mut self,
+        pair: ActorPair,
+        relationship: Option<Relationship>,
+    ) -> Option<Relationship> {
+        match relationship {
+            Some(relationship) => self.table.insert(pair, relationship),
+            None => self.table.remove(&pair),
         }
     }
+
+    /// All the pairs which include this actor
+    pub fn pairs_with(&self, data_index: DataIndex) -> Vec<ActorPair> {
+        self.table
+            .keys()
+            .filter(|k| k.contains(data_index))
+            .copied()
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&ActorPair, &Relationship)> {
+        self.table.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
 }
 
 // Need to get list of all Relationships with a given DataIndex/actor
 // That list will have only the actor info for the other party
 
+// This is also the working copy used while an item is being edited
 #[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct MyRelationships {
     my_data_index: DataIndex,
     table: BTreeMap<DataIndex, Relationship>,
+    original: BTreeMap<DataIndex, Relationship>,
+    new: Option<DataIndex>,
 }
 
 #[allow(dead_code)]
-#[derive(Clone)]
+impl MyRelationships {
+    /// For items which have not been added yet, so cannot have relationships
+    pub fn none() -> Self {
+        MyRelationships {
+            my_data_index: DataIndex::Nothing,
+            table: BTreeMap::new(),
+            original: BTreeMap::new(),
+            new: None,
+        }
+    }
+
+    pub fn my_data_index(&self) -> DataIndex {
+        self.my_data_index
+    }
+
+    pub fn table(&self) -> &BTreeMap<DataIndex, Relationship> {
+        &self.table
+    }
+
+    pub fn table_mut(&mut self) -> &mut BTreeMap<DataIndex, Relationship> {
+        &mut self.table
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.table != self.original
+    }
+
+    /// The settings needed to turn the original relationships into the edited ones
+    pub fn changes(&self) -> Vec<(ActorPair, Option<Relationship>)> {
+        let mut changes: Vec<(ActorPair, Option<Relationship>)> = self
+            .table
+            .iter()
+            .filter(|(other, rel)| self.original.get(other) != Some(rel))
+            .map(|(other, rel)| {
+                (
+                    ActorPair::new(self.my_data_index, *other),
+                    Some(rel.clone()),
+                )
+            })
+            .collect();
+        changes.extend(
+            self.original
+                .keys()
+                .filter(|other| !self.table.contains_key(other))
+                .map(|other| (ActorPair::new(self.my_data_index, *other), None)),
+        );
+        changes
+    }
+
+    pub fn new_other(&self) -> Option<DataIndex> {
+        self.new
+    }
+
+    pub fn set_new_other(&mut self, other: Option<DataIndex>) {
+        self.new = other;
+    }
+}
+
+#[cfg(feature = "gui")]
+impl MyRelationships {
+    pub fn show_edit(&mut self, app_data: &AppData, ui: &mut Ui) {
+        let mut remove = None;
+        Grid::new("relationships")
+            .num_columns(4)
+            .spacing([FIELD_HORIZONTAL_SPACE, 4.0])
+            .show(ui, |ui| {
+                for (other, relationship) in self.table.iter_mut() {
+                    // anything which has gone is skipped, and is dropped when saving
+                    let Some(other_name) = app_data.data_index_name(*other) else {
+                        continue;
+                    };
+                    ui.label(other_name.clone());
+                    relationship
+                        .attitude
+                        .show_edit(&format!("attitude {other:?}"), ui);
+                    ui.add(
+                        TextEdit::singleline(&mut relationship.reason)
+                            .hint_text(fl!("reason_heading")),
+                    );
+                    if ui
+                        .add(Label::new(RichText::new("x").weak()).sense(Sense::click()))
+                        .clicked()
+                    {
+                        info!("removing relationship with {other_name}");
+                        remove = Some(*other);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(other) = remove {
+            self.table.remove(&other);
+        }
+
+        // now perhaps add an entry
+        if self.new.is_some() {
+            let candidates: Vec<(DataIndex, String)> = {
+                let mut list: Vec<(DataIndex, String)> = app_data
+                    .actor_names()
+                    .into_iter()
+                    .filter(|(d, _)| *d != self.my_data_index && !self.table.contains_key(d))
+                    .collect();
+                list.insert(0, (DataIndex::Nothing, EMPTY_NAME.to_string()));
+                list
+            };
+            let mut selected_item = 0;
+
+            ComboBox::from_id_salt("new relationship").show_index(
+                ui,
+                &mut selected_item,
+                candidates.len(),
+                |i| candidates[i].1.clone(),
+            );
+
+            if selected_item != 0 {
+                let (other, other_name) = &candidates[selected_item];
+                info!("adding relationship with {other_name}");
+                self.table.insert(*other, Relationship::default());
+                self.new = None;
+            }
+        } else if ui
+            .add(Label::new(RichText::new("+").strong()).sense(Sense::click()))
+            .clicked()
+        {
+            info!("requested add relationship");
+            self.new = Some(DataIndex::Nothing);
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+const EMPTY_NAME: &str = "    ";
+
+#[allow(dead_code)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Relationship {
     attitude: Attitude, // Does this need names?
+    reason: String,
+}
+
+#[allow(dead_code)]
+impl Relationship {
+    pub fn new(attitude: Attitude, reason: &str) -> Self {
+        Relationship {
+            attitude,
+            reason: reason.to_string(),
+        }
+    }
+
+    pub fn attitude(&self) -> Attitude {
+        self.attitude
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn attitude_mut(&mut self) -> &mut Attitude {
+        &mut self.attitude
+    }
+
+    pub fn reason_mut(&mut self) -> &mut String {
+        &mut self.reason
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
 pub enum Attitude {
     Minus3 = -3,
     Minus2,
@@ -90,3 +323,76 @@ pub enum Attitude {
     Plus2,
     Plus3,
 }
+
+#[cfg(feature = "gui")]
+impl Attitude {
+    pub fn show_edit(&mut self, name: &str, ui: &mut Ui) {
+        let mut selected = (*self as i8 + 3) as usize;
+        ComboBox::from_id_salt(name).width(40.0).show_index(
+            ui,
+            &mut selected,
+            ATTITUDE_STRINGS.len(),
+            |i| ATTITUDE_STRINGS[i].to_string(),
+        );
+        *self = (selected as i8 - 3).into();
+    }
+}
+
+impl From<i8> for Attitude {
+    fn from(value: i8) -> Self {
+        use Attitude::*;
+
+        match value {
+            -3 => Minus3,
+            -2 => Minus2,
+            -1 => Minus1,
+            0 => Zero,
+            1 => Plus1,
+            2 => Plus2,
+            3 => Plus3,
+            _ => {
+                error!("converting {value} to Attitude");
+                Zero
+            }
+        }
+    }
+}
+
+const ATTITUDE_STRINGS: &[&str] = &["-3", "-2", "-1", "0", "+1", "+2", "+3"];
+
+impl Display for Attitude {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", ATTITUDE_STRINGS[(*self as i8 + 3) as usize])
+    }
+}
+
+// -----------------------------
+// Stored
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct RelationshipStore {
+    pub first: EntityId,
+    pub second: EntityId,
+    attitude: Attitude,
+    reason: String,
+}
+
+impl RelationshipStore {
+    pub fn new(first: EntityId, second: EntityId, relationship: &Relationship) -> Self {
+        RelationshipStore {
+            first,
+            second,
+            attitude: relationship.attitude,
+            reason: relationship.reason.clone(),
+        }
+    }
+}
+
+impl From<&RelationshipStore> for Relationship {
+    fn from(from_store: &RelationshipStore) -> Self {
+        Relationship {
+            attitude: from_store.attitude,
+            reason: from_store.reason.clone(),
+        }
+    }
+}
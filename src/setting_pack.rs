@@ -0,0 +1,286 @@
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+#[cfg(feature = "gui")]
+use eframe::egui::{Color32, RichText, ScrollArea, Ui};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use zip::ZipArchive;
+
+#[cfg(feature = "gui")]
+use crate::{app::UI_PADDING, localize::fl};
+use crate::{app_data::AppData, display::MainView};
+
+/// The folder of setting packs, in the data directory
+pub const PACK_DIR: &str = "packs";
+/// The campaign a pack starts, in the export format
+pub const PACK_EXPORT: &str = "export.json";
+/// What a pack is, for the chooser
+pub const PACK_MANIFEST: &str = "manifest.json";
+/// A pack may be zipped up, rather than a folder
+pub const PACK_ZIP_EXTENSION: &str = "zip";
+
+pub fn pack_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(PACK_DIR)
+}
+
+/// The manifest of a pack; only the name is needed
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PackManifest {
+    pub name: String,
+    /// The game the pack is for, such as Blades in the Dark or Scum and Villainy
+    #[serde(default)]
+    pub game: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub author: String,
+    #[serde(default)]
+    pub version: String,
+}
+
+/// A pack which has been read, and checked that its campaign loads
+#[derive(Debug, Clone)]
+pub struct SettingPack {
+    path: PathBuf,
+    manifest: PackManifest,
+    factions: usize,
+    persons: usize,
+    districts: usize,
+}
+
+impl SettingPack {
+    pub fn read(path: &Path) -> Result<Self> {
+        let manifest: PackManifest = serde_json::from_slice(&read_entry(path, PACK_MANIFEST)?)
+            .map_err(|e| anyhow!("{PACK_MANIFEST} is not a manifest: {e}"))?;
+        if manifest.name.trim().is_empty() {
+            return Err(anyhow!("{PACK_MANIFEST} has no name"));
+        }
+        let data = AppData::from_pack(path)?;
+        Ok(SettingPack {
+            path: path.to_path_buf(),
+            manifest,
+            factions: data.view_size(MainView::Factions),
+            persons: data.view_size(MainView::Persons),
+            districts: data.view_size(MainView::Districts),
+        })
+    }
+
+    /// Every folder and zip in the pack folder, in name order, with those which cannot be used
+    pub fn find(data_dir: &Path) -> Vec<(PathBuf, Result<SettingPack>)> {
+        let Ok(entries) = fs::read_dir(pack_dir(data_dir)) else {
+            return Vec::new(); // no packs have been installed
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_dir() || p.extension().is_some_and(|e| e == PACK_ZIP_EXTENSION))
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let pack = SettingPack::read(&path);
+                if let Err(e) = &pack {
+                    warn!("unable to read pack {}: {e}", path.to_string_lossy());
+                }
+                (path, pack)
+            })
+            .collect()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn manifest(&self) -> &PackManifest {
+        &self.manifest
+    }
+
+    /// How many factions, people and districts the pack starts with
+    pub fn counts(&self) -> (usize, usize, usize) {
+        (self.factions, self.persons, self.districts)
+    }
+}
+
+/// Reads the campaign of a pack, ready to be imported
+pub fn read_export(path: &Path) -> Result<serde_json::Value> {
+    serde_json::from_slice(&read_entry(path, PACK_EXPORT)?)
+        .map_err(|e| anyhow!("{PACK_EXPORT} is not JSON: {e}"))
+}
+
+/// Reads a file from a pack folder, or from a zip, where it may be inside one folder
+fn read_entry(path: &Path, name: &str) -> Result<Vec<u8>> {
+    if path.is_dir() {
+        return fs::read(path.join(name)).map_err(|e| anyhow!("unable to read {name}: {e}"));
+    }
+
+    let mut archive = ZipArchive::new(File::open(path)?)?;
+    let entry_name = archive
+        .file_names()
+        .filter(|n| *n == name || n.ends_with(&format!("/{name}")))
+        .min_by_key(|n| n.len())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("there is no {name} in the zip"))?;
+    let mut entry = archive.by_name(&entry_name)?;
+    let mut buffer = Vec::new();
+    entry.read_to_end(&mut buffer)?;
+    Ok(buffer)
+}
+
+// -----------------------------
+// Chooser
+
+/// The installed packs, one of which may be picked to start a campaign
+#[cfg_attr(not(feature = "gui"), allow(dead_code))]
+pub struct PackChooser {
+    pack_dir: PathBuf,
+    packs: Vec<(PathBuf, Result<SettingPack>)>,
+    selected: Option<usize>,
+}
+
+impl PackChooser {
+    pub fn new(data_dir: &Path) -> Self {
+        let packs = SettingPack::find(data_dir);
+        // start with the first pack which can be used
+        let selected = packs.iter().position(|(_, p)| p.is_ok());
+        PackChooser {
+            pack_dir: pack_dir(data_dir),
+            packs,
+            selected,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&SettingPack> {
+        self.packs
+            .get(self.selected?)
+            .and_then(|(_, p)| p.as_ref().ok())
+    }
+}
+
+#[cfg(feature = "gui")]
+impl PackChooser {
+    /// Lists the packs, and previews the one which is selected
+    pub fn show_edit(&mut self, ui: &mut Ui) {
+        const LIST_HEIGHT: f32 = 200.0;
+
+        ui.label(fl!(
+            "pack_folder",
+            folder = self.pack_dir.to_string_lossy().to_string()
+        ));
+        ui.add_space(UI_PADDING);
+        if self.packs.is_empty() {
+            ui.label(RichText::new(fl!("pack_none")).italics());
+            return;
+        }
+
+        ScrollArea::vertical()
+            .max_height(LIST_HEIGHT)
+            .show(ui, |ui| {
+                for (index, (path, pack)) in self.packs.iter().enumerate() {
+                    match pack {
+                        Ok(pack) => {
+                            let selected = self.selected == Some(index);
+                            if ui
+                                .selectable_label(selected, &pack.manifest().name)
+                                .clicked()
+                            {
+                                self.selected = Some(index);
+                            }
+                        }
+                        Err(e) => {
+                            let name = path.file_name().unwrap_or_default().to_string_lossy();
+                            ui.label(
+                                RichText::new(format!("{name}: {e}")).color(Color32::LIGHT_RED),
+                            );
+                        }
+                    }
+                }
+            });
+
+        if let Some(pack) = self.selected() {
+            let manifest = pack.manifest();
+            let (factions, persons, districts) = pack.counts();
+            ui.add_space(UI_PADDING * 2.);
+            ui.label(RichText::new(&manifest.name).strong());
+            for line in [&manifest.game, &manifest.author, &manifest.version] {
+                if !line.is_empty() {
+                    ui.label(line);
+                }
+            }
+            if !manifest.description.is_empty() {
+                ui.label(&manifest.description);
+            }
+            ui.label(fl!(
+                "pack_counts",
+                factions = factions,
+                persons = persons,
+                districts = districts
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write};
+
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    use crate::{
+        dataset::{DOSKVOL, load},
+        persistence::test_dir,
+        setting_pack::{PACK_DIR, PACK_EXPORT, PACK_MANIFEST, SettingPack},
+    };
+
+    #[test]
+    fn packs_are_read_from_folders_and_zips() {
+        let data_dir = test_dir("pack");
+        let packs = data_dir.join(PACK_DIR);
+        let export = serde_json::to_vec(&load(DOSKVOL).unwrap()).unwrap();
+        let manifest = br#"{"name":"Doskvol","game":"Blades in the Dark"}"#;
+
+        let folder = packs.join("doskvol");
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join(PACK_EXPORT), &export).unwrap();
+        fs::write(folder.join(PACK_MANIFEST), manifest).unwrap();
+
+        let mut zip = ZipWriter::new(fs::File::create(packs.join("zipped.zip")).unwrap());
+        for (name, contents) in [
+            (format!("zipped/{PACK_MANIFEST}"), manifest.as_slice()),
+            (format!("zipped/{PACK_EXPORT}"), export.as_slice()),
+        ] {
+            zip.start_file(name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let broken = packs.join("broken");
+        fs::create_dir_all(&broken).unwrap();
+        fs::write(broken.join(PACK_MANIFEST), manifest).unwrap();
+
+        // a campaign of another schema is listed as unusable, rather than read
+        let other = packs.join("other");
+        fs::create_dir_all(&other).unwrap();
+        fs::write(other.join(PACK_MANIFEST), manifest).unwrap();
+        fs::write(
+            other.join(PACK_EXPORT),
+            br#"{"save_schema":"SomeOtherData","save_version":1,"persons":[],"districts":[],"factions":[]}"#,
+        )
+        .unwrap();
+
+        let found = SettingPack::find(&data_dir);
+        assert_eq!(found.len(), 4);
+        assert!(found[0].1.is_err()); // broken has no export
+        assert!(found[2].1.is_err());
+        let folder_pack = found[1].1.as_ref().unwrap();
+        let zip_pack = found[3].1.as_ref().unwrap();
+        assert_eq!(folder_pack.manifest().game, "Blades in the Dark");
+        assert_eq!(folder_pack.counts(), zip_pack.counts());
+        assert!(folder_pack.counts().0 > 0);
+        fs::remove_dir_all(&data_dir).unwrap();
+    }
+}
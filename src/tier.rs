@@ -1,5 +1,6 @@
 use std::fmt::Display;
 
+#[cfg(feature = "gui")]
 use eframe::egui::{ComboBox, FontFamily, FontId, TextStyle, Ui};
 use log::error;
 use serde::{Deserialize, Serialize};
@@ -19,6 +20,7 @@ pub enum Tier {
     Tier5,
 }
 
+#[cfg(feature = "gui")]
 impl Tier {
     pub fn show_edit ( &mut self, name: &str, ui: &mut Ui ) {
         let mut selected = *self as usize;
@@ -33,6 +35,18 @@ impl Tier {
     }
 }
 
+impl Tier {
+    /// Reads a tier written as a numeral, a number or the name of the variant
+    pub fn parse ( text: &str ) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix("Tier").unwrap_or(text);
+        TIER_STRINGS.iter()
+            .position(|s| s.eq_ignore_ascii_case(text))
+            .or_else(|| text.parse::<usize>().ok().filter(|n| *n < TIER_STRINGS.len()))
+            .map(Tier::from)
+    }
+}
+
 impl From<Tier> for usize {
     fn from ( value: Tier ) -> Self {
         value as usize
@@ -1,21 +1,32 @@
 
-use log::error;
+use log::{error, info};
 
 use crate::action::ActionNode;
 
 
+/// Where the reverse of a todo node goes, once it has been done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoKind {
+    /// a new change, which can be undone
+    Change,
+    /// the reverse of a change, which can be redone
+    Undo,
+    /// the reverse of an undo, which can be undone again
+    Redo,
+}
+
 #[allow(dead_code)]
 #[derive(Default)]
 pub struct TodoUndo {
-    todo: Option<ActionNode>,
+    todo: Option<(TodoKind, ActionNode)>,
     undo: Vec<ActionNode>,
-    done: Vec<ActionNode>,
+    done: Vec<ActionNode>,  // the redo stack, filled by undoing
 }
 
 #[allow(dead_code)]
 impl TodoUndo {
     /// this removes the returned action
-    pub fn todo ( &mut self ) -> Option<ActionNode> {
+    pub fn todo ( &mut self ) -> Option<(TodoKind, ActionNode)> {
         self.todo.take()  // leaves todo as None
     }
 
@@ -25,10 +36,48 @@ impl TodoUndo {
             error!("adding todo action when a todo action is already present");
             // this destroys the previous todo item, probably without doing it
         }
-        self.todo = Some(actions);
+        self.todo = Some((TodoKind::Change, actions));
         self.done.clear();
     }
 
+    /// Files the reverse of a completed todo on the stack it belongs to
+    pub fn complete ( &mut self, kind: TodoKind, reverse: ActionNode ) {
+        if reverse.is_empty() {
+            info!("{kind:?} had no effect; nothing to reverse");
+            return;
+        }
+        match kind {
+            TodoKind::Change | TodoKind::Redo => self.undo.push(reverse),
+            TodoKind::Undo => self.done.push(reverse),
+        }
+    }
+
+    pub fn can_undo ( &self ) -> bool {
+        self.todo.is_none() && !self.undo.is_empty()
+    }
+
+    pub fn can_redo ( &self ) -> bool {
+        self.todo.is_none() && !self.done.is_empty()
+    }
+
+    /// Queues the most recent change to be reversed, returning false if there is none
+    pub fn undo ( &mut self ) -> bool {
+        if !self.can_undo() {
+            return false;
+        }
+        self.todo = self.undo.pop().map(|node| (TodoKind::Undo, node));
+        true
+    }
+
+    /// Queues the most recent undo to be done again, returning false if there is none
+    pub fn redo ( &mut self ) -> bool {
+        if !self.can_redo() {
+            return false;
+        }
+        self.todo = self.done.pop().map(|node| (TodoKind::Redo, node));
+        true
+    }
+
     pub fn add_done ( &mut self, actions: ActionNode ) {
         self.done.push(actions);
     }
@@ -48,4 +97,11 @@ impl TodoUndo {
     pub fn clear_undo ( &mut self ) {
         self.undo.clear();
     }
+
+    /// Forgets all history, for when the data it refers to has gone
+    pub fn clear ( &mut self ) {
+        self.clear_todo();
+        self.clear_undo();
+        self.clear_done();
+    }
 }